@@ -1,10 +1,19 @@
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
-use keyhunter_core::{scan_and_write, ScanEngine, ScanOptions};
+use clap::{Args, Parser, Subcommand};
+use keyhunter_core::{
+    audit_rules, build_run_metadata, Baseline, check_field_renames, check_rules, describe_plan, diff_results, load_results, mask_value, load_prefilter_plan, load_rule_test_cases, run_rule_examples, run_rule_tests, scan_roots_and_write_report, scan_roots_to_dir,
+    scan_roots_to_targets, scan_bytes_and_write_report, scan_bytes_to_targets, BinaryMode, DedupScope, FileOrder, HashMode, OutputFormat, OutputTarget, ProgressEvent, ProgressFn, RedactMode, SortOrder, PlanReport, RuleTestCase, ScanEngine, ScanOptions, ScanStats,
+    Severity, ValidationMode, DEFAULT_BINARY_PRINTABLE_THRESHOLD, DEFAULT_MAX_PEM_WINDOWS, DEFAULT_SMALL_FILE_FULLSCAN_THRESHOLD, DEFAULT_MAX_VALUE_LEN, DEFAULT_REGEX_SIZE_LIMIT,
+    DEFAULT_REORDER_WINDOW, DEFAULT_WINDOW_AFTER, DEFAULT_WINDOW_BEFORE,
+};
 use std::fs::File;
-use std::io::{BufWriter, Write};
-use std::path::PathBuf;
-use tracing::{error, info};
+use std::io::{BufWriter, IsTerminal, Read, Write};
+use std::collections::{HashMap, HashSet};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tracing::info;
 
 /// 命令行入口（基于 clap）
 #[derive(Parser, Debug)]
@@ -17,82 +26,1520 @@ struct Cli {
 #[derive(Subcommand, Debug)]
 enum Commands {
     /// 扫描目录并生成 result.json
-    Scan {
-        /// 输入目录（数据集或样本目录）
-        #[arg(long)]
-        input: PathBuf,
+    ///
+    /// 退出码：0 扫描完成（未启用 --fail-on-findings，或计入的命中数不超过 --fail-threshold）；
+    /// 1 运行出错（参数、规则、读写错误等）；
+    /// 2 启用 --fail-on-findings 且计入的命中数（按 --fail-on-severity 筛选）超过 --fail-threshold
+    Scan(Box<ScanArgs>),
+    /// 加载规则并打印预筛计划（每条规则的锚点、是否无锚点）
+    Explain(ExplainArgs),
+    /// 规则工具
+    #[command(subcommand)]
+    Rules(RulesCommand),
+    /// 比较两次扫描的结果文件，列出新增与消失的命中
+    Diff(DiffArgs),
+}
+
+#[derive(Subcommand, Debug)]
+enum RulesCommand {
+    /// 用正例 / 反例字符串测试规则，报告每条样本命中的规则及是否符合期望；
+    /// 未给出样本时检查规则文件中各规则自带的 examples / negatives
+    Test(RulesTestArgs),
+    /// 打印实际生效的规则（加载与合并后）：id、名称、严重级别、锚点状态与模式，不执行扫描
+    List(RulesListArgs),
+    /// 审计规则集：找出锚点完全相同且模式高度相似的疑似冗余规则对
+    Audit(RulesAuditArgs),
+    /// 校验规则：完整编译每条正则规则，列出无法使用的规则（语法错误、超出 --regex-size-limit），不执行扫描
+    Check(RulesCheckArgs),
+}
+
+/// rules check 子命令参数
+#[derive(Args, Debug)]
+struct RulesCheckArgs {
+    /// 规则文件路径（TOML 或 http(s) URL），默认 ./rules/default.toml
+    #[arg(long)]
+    rules: Option<PathBuf>,
+
+    /// 远程规则的本地缓存文件（配合 URL 形式的 --rules）
+    #[arg(long, value_name = "PATH", requires = "rules")]
+    rules_cache: Option<PathBuf>,
+
+    /// 命名规则集（NAME=PATH，可重复），与 scan 的同名参数一致
+    #[arg(long, value_name = "NAME=PATH", value_parser = parse_named_rules, conflicts_with = "rules")]
+    rules_named: Vec<(String, PathBuf)>,
+
+    /// 单条规则正则编译后的大小上限（字节），与 scan 的同名参数一致；0 表示不限制
+    #[arg(long, value_name = "BYTES", default_value_t = DEFAULT_REGEX_SIZE_LIMIT)]
+    regex_size_limit: usize,
+
+    /// 以 JSON 输出（默认为可读文本）
+    #[arg(long)]
+    json: bool,
+}
+
+/// rules audit 子命令参数
+#[derive(Args, Debug)]
+struct RulesAuditArgs {
+    /// 规则文件路径（TOML 或 http(s) URL），默认 ./rules/default.toml
+    #[arg(long)]
+    rules: Option<PathBuf>,
+
+    /// 远程规则的本地缓存文件（配合 URL 形式的 --rules）
+    #[arg(long, value_name = "PATH", requires = "rules")]
+    rules_cache: Option<PathBuf>,
+
+    /// 命名规则集（NAME=PATH，可重复），与 scan 的同名参数一致
+    #[arg(long, value_name = "NAME=PATH", value_parser = parse_named_rules, conflicts_with = "rules")]
+    rules_named: Vec<(String, PathBuf)>,
+
+    /// 模式相似度阈值（0~1，字符二元组 Dice 系数），不低于该值的同锚点规则对被报告
+    #[arg(long, value_name = "RATIO", default_value_t = 0.8)]
+    min_similarity: f32,
+
+    /// 以 JSON 输出（默认为可读文本）
+    #[arg(long)]
+    json: bool,
+}
+
+/// rules list 子命令参数
+#[derive(Args, Debug)]
+struct RulesListArgs {
+    /// 规则文件路径（TOML 或 http(s) URL），默认 ./rules/default.toml
+    #[arg(long)]
+    rules: Option<PathBuf>,
+
+    /// 远程规则的本地缓存文件（配合 URL 形式的 --rules）
+    #[arg(long, value_name = "PATH", requires = "rules")]
+    rules_cache: Option<PathBuf>,
+
+    /// 命名规则集（NAME=PATH，可重复），与 scan 的同名参数一致
+    #[arg(long, value_name = "NAME=PATH", value_parser = parse_named_rules, conflicts_with = "rules")]
+    rules_named: Vec<(String, PathBuf)>,
+
+    /// 以 JSON 输出（默认为对齐的文本表格）
+    #[arg(long)]
+    json: bool,
+}
+
+/// rules test 子命令参数
+#[derive(Args, Debug)]
+struct RulesTestArgs {
+    /// 规则文件路径（TOML），默认 ./rules/default.toml
+    #[arg(long)]
+    rules: Option<PathBuf>,
+
+    /// 期望被命中的样本（可重复）
+    #[arg(long, value_name = "TEXT")]
+    positive: Vec<String>,
+
+    /// 期望不被命中的样本（可重复）
+    #[arg(long, value_name = "TEXT")]
+    negative: Vec<String>,
+
+    /// 用例文件（TOML）：expect_match = [...]、expect_no_match = [...]
+    #[arg(long, value_name = "FILE")]
+    cases: Option<PathBuf>,
+}
+
+/// explain 子命令参数
+#[derive(Args, Debug)]
+struct ExplainArgs {
+    /// 规则文件路径（TOML），默认 ./rules/default.toml
+    #[arg(long)]
+    rules: Option<PathBuf>,
+
+    /// 以 JSON 输出报告（默认为可读文本）
+    #[arg(long)]
+    json: bool,
+}
+
+/// diff 子命令参数
+#[derive(Args, Debug)]
+struct DiffArgs {
+    /// 旧结果文件（result.json）
+    old: PathBuf,
+
+    /// 新结果文件（result.json）
+    new: PathBuf,
+
+    /// 比较键中加入 rule_id（默认按 (file_hash, value) 比较；result.json 中没有 rule_id 的项按空值参与比较）
+    #[arg(long)]
+    with_rule_id: bool,
+
+    /// 输出格式：text（可读文本，值已脱敏，默认）或 json（完整的新增 / 消失项）
+    #[arg(long, default_value = "text", value_parser = ["text", "json"])]
+    format: String,
+}
+
+/// scan 子命令参数
+#[derive(Args, Debug)]
+struct ScanArgs {
+    /// 输入目录（数据集或样本目录）或单个文件（file_hash 为其文件名），可重复指定多个根目录
+    /// （结果按给出的顺序、目录内按文件名排列）；
+    /// 为 "-" 时从标准输入读取单个文件内容（此时只能有一个 --input）
+    #[arg(long, required_unless_present = "stdin")]
+    input: Vec<PathBuf>,
+
+    /// 从标准输入读取单个文件内容扫描（等同于 --input -，不落盘），如 `curl -s URL | keyhunter scan --stdin`；
+    /// 与 --input 互斥
+    #[arg(long, conflicts_with = "input")]
+    stdin: bool,
+
+    /// 输出中 file_hash 的取值：name（文件名，默认）、relpath（相对输入根目录的路径，
+    /// 多个 --input 时加 "<根目录序号>/" 前缀，区分不同根目录下的同名文件），
+    /// 或 sha256 / md5（文件内容的十六进制摘要，内容相同的文件 file_hash 相同；每个文件多读一遍）
+    #[arg(long = "hash", value_name = "MODE", default_value = "name", value_parser = ["name", "relpath", "sha256", "md5"])]
+    hash_mode: String,
+
+    /// 在 JSON 输出中附带文件相对输入根目录的路径（path，多个 --input 时同样加根目录序号前缀），
+    /// 与 file_hash 并列，便于在目录树中定位文件
+    #[arg(long)]
+    emit_path: bool,
+
+    /// 配合 --stdin / --input -：标准输入内容的逻辑文件名（输出中的 file_hash，并参与路径作用域规则匹配），默认 <stdin>
+    #[arg(long, value_name = "NAME")]
+    stdin_name: Option<String>,
+
+    /// 输出文件（JSON 数组）
+    #[arg(long, default_value = "./result.json")]
+    output: PathBuf,
+
+    /// 逐文件输出目录：每个已扫描文件的命中写入 `<dir>/<file_hash>.json`（与 --output 互斥，仅 json 格式）；
+    /// 旁路报告写在目录旁（如 `<dir>.skipped.json`）
+    #[arg(long, value_name = "DIR", conflicts_with = "output")]
+    output_dir: Option<PathBuf>,
+
+    /// 只计数：照常扫描与过滤，但不写出命中，输出文件中只写出扫描统计（JSON 对象，含按规则计数 findings_by_rule），
+    /// 用于估算扫描成本与调整规则；忽略 --format
+    #[arg(long, conflicts_with_all = ["output_dir", "max_output_bytes"])]
+    count_only: bool,
+
+    /// 配合 --output-dir：没有命中的文件也写出 `[]`（默认不写）
+    #[arg(long, requires = "output_dir")]
+    emit_empty: bool,
+
+    /// 线程数（bytes 引擎启用并行；"auto"=CPU 核心数，否则为正整数）
+    // 使用完整路径的 Option，避免 clap 将其视为“可省略参数”，由 parse_threads 负责解析 auto
+    #[arg(long, default_value = "auto", value_parser = parse_threads)]
+    threads: std::option::Option<usize>,
+
+    /// 并行扫描时文件的处理顺序：name（默认）、size-desc（大文件优先）或 mtime-desc（最近修改优先）。
+    /// 只改变处理先后，输出始终按文件名顺序；单线程或 utf8 引擎下不生效
+    #[arg(long, default_value = "name", value_parser = ["name", "size-desc", "mtime-desc"])]
+    file_order: String,
+
+    /// 并行扫描的背压窗口（文件数）：前面的文件较慢时，后面的文件最多领先这么多个，限制待输出结果占用的内存；
+    /// 0 表示不限制。仅在 --file-order name 下生效
+    #[arg(long, default_value_t = DEFAULT_REORDER_WINDOW, value_name = "FILES")]
+    reorder_window: usize,
+
+    /// 命中值最小香农熵（bits/字节）：低于该值的命中丢弃（如 aaaaaaaa… 一类占位值，建议 3.0~3.5）；
+    /// 带确定前缀的规则（AKIA、ghp_、PEM 私钥等）不受约束；0 表示不过滤
+    #[arg(long, default_value_t = 0.0)]
+    min_score: f32,
+
+    /// 最大扫描文件大小（单位字节，例如 5242880 代表 5MB）
+    #[arg(long)]
+    max_file_size: Option<u64>,
+
+    /// 每个文件只读取并扫描开头 N 字节（快速排查把配置 / 密钥放在开头的超大文件）；
+    /// 偏移仍为文件内绝对偏移，跨越区域边界的命中会漏掉
+    #[arg(long, value_name = "N", conflicts_with = "tail_bytes")]
+    head_bytes: Option<u64>,
+
+    /// 每个文件只读取并扫描末尾 N 字节（定位后读取）；偏移 = 文件大小 - 区域长度 + 区域内偏移，
+    /// 跨越区域边界的命中会漏掉，行号（--with-location）从区域起点计数
+    #[arg(long, value_name = "N")]
+    tail_bytes: Option<u64>,
+
+    /// 以内存映射读取 64 KiB 到 1 MiB 之间的文件（避免复制到堆上；更大的文件仍分块读取）；映射失败时回退到普通读取，
+    /// 扫描期间被改写的文件记为跳过。注意：映射期间文件被其他进程截断可能使进程因 SIGBUS 退出
+    #[arg(long)]
+    mmap: bool,
+
+    /// 输入目录的遍历深度：1 只扫描目录下的文件（默认），N 进入至多 N-1 层子目录，0 表示不限制；
+    /// 子目录中的同名文件可用 --hash relpath 区分
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    max_depth: usize,
+
+    /// 遍历输入目录时遵循其中的 .gitignore，并跳过 .git 目录（适合扫描代码仓库检出）
+    #[arg(long)]
+    respect_gitignore: bool,
+
+    /// 遍历输入目录时跟随符号链接（默认忽略符号链接）；指向输入目录之外的链接同样扫描，
+    /// 路径按链接所在位置记录，循环链接与重复进入的目录会被跳过
+    #[arg(long)]
+    follow_symlinks: bool,
+
+    /// 排除匹配的路径（glob，可重复），匹配相对输入目录的路径或文件/目录名，例如 --exclude '*.min.js' --exclude node_modules
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+
+    /// 只扫描该扩展名的文件（可重复，不区分大小写，如 --ext env --ext pem --ext tar.gz；.env 这类点文件同样匹配）；
+    /// 与 --name-glob 同时给出时满足其一即可，直接作为 --input 给出的文件不受影响
+    #[arg(long = "ext", value_name = "EXT")]
+    include_extensions: Vec<String>,
+
+    /// 只扫描名称匹配的文件（glob，可重复），匹配相对输入目录的路径或文件名，例如 --name-glob '*.yaml' --name-glob 'config/*.json'
+    #[arg(long, value_name = "GLOB")]
+    name_glob: Vec<String>,
+
+    /// 扫描 .zip / .tar / .tar.gz（.tgz）压缩包内的文件与单独的 .gz 文件（内存中解压，不落盘），
+    /// 命中的 file_hash 为 "<压缩包>!<条目路径>"（.gz 文件的条目为去掉 .gz 的文件名）；
+    /// 条目按解压后的大小受 --max-file-size 约束
+    #[arg(long)]
+    scan_archives: bool,
+
+    /// 最多扫描的文件数（按文件名排序后取前 N 个），防止误指向超大目录
+    #[arg(long, value_name = "N")]
+    max_files: Option<usize>,
+
+    /// 随机抽样 N 个文件扫描（排序后按 --seed 确定性选取，输出仍按排序顺序），
+    /// 用于在超大数据集上快速验证规则改动；在 --max-files 截断之前生效
+    #[arg(long, value_name = "N")]
+    sample: Option<usize>,
+
+    /// 抽样随机数种子：同一种子重复运行选中相同文件
+    #[arg(long, value_name = "S", default_value_t = 0, requires = "sample")]
+    seed: u64,
+
+    /// 扫描引擎：bytes、utf8 或 auto（默认 bytes）；auto 按文件开头抽样的编码逐文件选择
+    /// （UTF-8 文本且规则依赖 Unicode 语义时用 utf8，其余用 bytes）
+    #[arg(long, default_value = "bytes", value_parser = ["bytes", "utf8", "auto"])]
+    engine: String,
+
+    /// 规则文件路径（TOML），默认 ./rules/default.toml；也可为 https:// / http:// URL（启动时拉取，超时 10 秒，上限 4 MiB）
+    #[arg(long)]
+    rules: Option<PathBuf>,
+
+    /// 远程规则的本地缓存文件：拉取成功后写入，拉取失败时回退到该文件（配合 URL 形式的 --rules）
+    #[arg(long, value_name = "PATH", requires = "rules")]
+    rules_cache: Option<PathBuf>,
+
+    /// 命名规则集（NAME=PATH，可重复）：按顺序加载多个规则文件，配合 --with-ruleset 标注命中来源；
+    /// 规则 id 在规则集之间必须唯一
+    #[arg(long, value_name = "NAME=PATH", value_parser = parse_named_rules, conflicts_with = "rules")]
+    rules_named: Vec<(String, PathBuf)>,
+
+    /// 只启用指定 id 的规则（可重复；命名规则集下也可写 集合名/id），其余规则全部停用，无需修改规则文件
+    #[arg(long = "enable-rule", value_name = "ID")]
+    enable_rules: Vec<String>,
+
+    /// 停用指定 id 的规则（可重复）；与 --enable-rule 同时给出时在启用的规则中再排除。没有匹配任何规则的 id 会告警
+    #[arg(long = "disable-rule", value_name = "ID")]
+    disable_rules: Vec<String>,
+
+    /// 二进制文件处理：skip（跳过，默认）、strings（提取可打印串后扫描）或
+    /// raw（不做二进制判定，含 NUL 字节等看似二进制的文件也按原始字节整体扫描）
+    #[arg(long, default_value = "skip", value_parser = ["skip", "strings", "raw"])]
+    scan_binaries: String,
 
-        /// 输出文件（JSON 数组）
-        #[arg(long, default_value = "./result.json")]
-        output: PathBuf,
+    /// 二进制判定的可打印字符占比阈值（0~1）：抽样中可打印字符占比低于该值时视为二进制；0 表示只按 NUL 字节判定
+    #[arg(long, value_name = "RATIO", default_value_t = DEFAULT_BINARY_PRINTABLE_THRESHOLD, value_parser = parse_ratio)]
+    binary_printable_threshold: f32,
 
-        /// 线程数（bytes 引擎启用并行；"auto"=CPU 核心数）
-        #[arg(long, default_value = "auto")]
-        threads: String,
+    /// 识别 UTF-16 文本：带 BOM（FF FE / FE FF）或隔字节为 NUL 的 UTF-16 文件转码为 UTF-8 后扫描（偏移为原始文件偏移）；
+    /// 默认不识别，此类文件按二进制处理（见 --scan-binaries）
+    #[arg(long)]
+    detect_encoding: bool,
 
-        /// 最小打分阈值（demo 暂未使用）
-        #[arg(long, default_value_t = 0.0)]
-        min_score: f32,
+    /// 输出运行元数据（运行 ID、时间戳、工具版本、规则集哈希、统计）：grouped-json 与 sarif 格式嵌入输出
+    /// （grouped-json 为 {"files": [...], "metadata": {...}}，sarif 为 run 的 properties.metadata）；
+    /// 其余格式写入旁路文件 `<output>.meta.json`，保持主输出为纯数组 / 逐行结构
+    #[arg(long)]
+    with_metadata: bool,
 
-        /// 最大扫描文件大小（单位字节，例如 5242880 代表 5MB）
-        #[arg(long)]
-        max_file_size: Option<u64>,
+    /// 宽泛锚点告警阈值：单个锚点映射的规则数超过该值时输出告警
+    #[arg(long, default_value_t = 8)]
+    broad_anchor_threshold: usize,
 
-        /// 扫描引擎：bytes 或 utf8（默认 bytes）
-        #[arg(long, default_value = "bytes", value_parser = ["bytes", "utf8"])]
-        engine: String,
+    /// 拆分宽泛锚点：窗口内需同时出现规则更具体的次级字面量才运行该规则（提速，可能略降召回）
+    #[arg(long)]
+    split_broad_anchors: bool,
 
-        /// 规则文件路径（TOML），默认 ./rules/default.toml
-        #[arg(long)]
-        rules: Option<PathBuf>,
-    },
+    /// 锚点大小写不敏感匹配（ASCII），窗口内精准正则同样忽略大小写；命中值保留原始大小写
+    #[arg(long)]
+    anchors_ignore_case: bool,
+
+    /// 单文件窗口累计字节上限：锚点过密时超过该值则回退为整文件单次扫描（默认不限制）
+    #[arg(long, value_name = "BYTES")]
+    max_window_bytes: Option<usize>,
+
+    /// 锚点窗口向前的字节数（规则的 window_before 优先）
+    #[arg(long, value_name = "BYTES", default_value_t = DEFAULT_WINDOW_BEFORE)]
+    window_before: usize,
+
+    /// 锚点窗口向后的字节数（规则的 window_after 优先）；值离锚点较远或很长（如数 KB 的 JWT）时需要放大
+    #[arg(long, value_name = "BYTES", default_value_t = DEFAULT_WINDOW_AFTER)]
+    window_after: usize,
+
+    /// PEM / 私钥类锚点的窗口向前放大字节数
+    #[arg(long, value_name = "BYTES", default_value_t = 2048)]
+    pem_window_before: usize,
+
+    /// PEM / 私钥类锚点的窗口向后放大字节数（需容纳完整的 BEGIN..END 块）
+    #[arg(long, value_name = "BYTES", default_value_t = 16 * 1024)]
+    pem_window_after: usize,
+
+    /// 单文件 PEM 放大窗口数上限：超过则回退为整文件单次扫描（如大量提及 PRIVATE KEY 的文档）；0 表示不限制
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_MAX_PEM_WINDOWS)]
+    max_pem_windows: usize,
+
+    /// 小文件整缓冲区扫描阈值：短于该值的文件在锚点命中后直接对全文运行候选规则，不再切分窗口
+    /// （窗口开销可省，也不会漏掉值落在窗口之外的命中）；0 表示总是按窗口扫描
+    #[arg(long, value_name = "BYTES", default_value_t = DEFAULT_SMALL_FILE_FULLSCAN_THRESHOLD)]
+    small_file_fullscan_threshold: usize,
+
+    /// 单条规则正则编译后的大小上限（字节）：超出的规则（如嵌套量词导致自动机膨胀）被拒绝并告警，
+    /// 配合 --warmup 时直接报错退出；0 表示不限制
+    #[arg(long, value_name = "BYTES", default_value_t = DEFAULT_REGEX_SIZE_LIMIT)]
+    regex_size_limit: usize,
+
+    /// 单条规则在单个窗口上的匹配时间上限（毫秒，bytes / auto 引擎）：超时后放弃该窗口上的剩余匹配并告警，
+    /// 防止病态规则拖住整个文件；0 表示不限制
+    #[arg(long, value_name = "MS", default_value_t = 0)]
+    rule_timeout_ms: u64,
+
+    /// 大文件分块缓冲区的全局内存预算（字节），按线程数缩小块大小；峰值约为 线程数 × (块大小 + 512)
+    #[arg(long, value_name = "BYTES")]
+    memory_budget: Option<usize>,
+
+    /// 懒编译正则缓存容量（条）：超出时淘汰最久未使用的规则正则，适合规则量大的长时间运行场景（默认不限制）
+    #[arg(long, value_name = "N")]
+    regex_cache_capacity: Option<NonZeroUsize>,
+
+    /// 锚点抽取结果的缓存目录：规则不变时再次运行直接复用上次抽取的预筛锚点，规则变化时自动重建；
+    /// 默认 $XDG_CACHE_HOME/keyhunter（未设置时为 ~/.cache/keyhunter）
+    #[arg(long, value_name = "DIR")]
+    cache_dir: Option<PathBuf>,
+
+    /// 不读写锚点缓存，每次运行重新抽取
+    #[arg(long, conflicts_with = "cache_dir")]
+    no_cache: bool,
+
+    /// 预热：扫描前编译全部规则正则（bytes 引擎），消除首次命中时的编译延迟，无法编译的规则直接报错
+    #[arg(long)]
+    warmup: bool,
+
+    /// 输出字段名重映射（可重复），格式 原字段=新字段，例如 --rename-field file_hash=hash；
+    /// 原字段须为已知的输出字段，重映射后的字段名不能与其它字段重名
+    #[arg(long = "rename-field", value_name = "FROM=TO")]
+    rename_field: Vec<String>,
+
+    /// 重建相邻字符串字面量拼接（如 "ghp_" + "abc…"）后再扫描；启发式、与语言相关，默认关闭
+    #[arg(long)]
+    join_string_literals: bool,
+
+    /// 解码内嵌的 base64 串（≥ 40 字符，如 JSON / Kubernetes Secret 中的值）后再扫描；解码内容中的命中
+    /// file_hash 为 "<文件>!b64"，偏移为 base64 串的起始偏移；仅 bytes 引擎，默认关闭
+    #[arg(long)]
+    decode_base64: bool,
+
+    /// 在 JSON 输出中附带规则说明与参考链接（description / references）
+    #[arg(long)]
+    with_rule_meta: bool,
+
+    /// 在 JSON 输出中附带命中的行号范围（start_line / end_line，从 1 开始；跨多行的命中如 PEM 块占多行）
+    #[arg(long)]
+    with_location: bool,
+
+    /// 在 JSON 输出中附带命中所在行及前后 N 行的上下文（context，命中值已脱敏）；
+    /// 超过 1 MiB 的文件分块扫描，上下文在块边界处可能被截断
+    #[arg(long, value_name = "N")]
+    context_lines: Option<usize>,
+
+    /// 在 JSON 输出中附带命中前后各至多 N 字节的片段（context，命中值已脱敏），用于快速判断是否为文档示例；
+    /// 0 表示不输出，不能与 --context-lines 同时使用
+    #[arg(long, value_name = "N", default_value_t = 0, conflicts_with = "context_lines")]
+    context_bytes: usize,
+
+    /// 在 JSON 输出中附带置信度（confidence，0~1）：综合校验结果、命中值熵与规则严重级别加权计算，
+    /// 权重可在规则文件的 [scoring] 段配置（validator_weight / entropy_weight / severity_weight）
+    #[arg(long)]
+    with_score: bool,
+
+    /// 在 JSON 输出中附带命中规则所属的命名规则集（ruleset，需配合 --rules-named）
+    #[arg(long)]
+    with_ruleset: bool,
+
+    /// 在 JSON 输出中附带产生命中的规则（rule_id，规则声明了名称时另附 rule_name）
+    #[arg(long)]
+    emit_rule_id: bool,
+
+    /// 诊断：在 JSON 输出中附带产生命中的扫描路径（source：small_file 整缓冲区 / window 锚点窗口 /
+    /// chunked 大文件分块 / strings 二进制可打印串），用于排查分块边界等偏移问题
+    #[arg(long)]
+    with_source: bool,
+
+    /// 在 JSON 输出中附带命中规则的严重级别（severity：low|medium|high|critical，未声明的规则为 medium），便于下游排序
+    #[arg(long)]
+    with_severity: bool,
+
+    /// 只输出不低于该严重级别的命中（low|medium|high|critical），更低的在写出时丢弃；
+    /// 与 --fail-on-severity 不同，被丢弃的命中不出现在任何输出中
+    #[arg(long, value_name = "SEVERITY")]
+    min_severity: Option<Severity>,
+
+    /// 启用行内抑制注释：命中所在行或上一行含抑制标记时丢弃该命中
+    #[arg(long)]
+    inline_suppression: bool,
+
+    /// 行内抑制标记（配合 --inline-suppression）
+    #[arg(long, default_value = "keyhunter:ignore")]
+    suppression_marker: String,
+
+    /// 按扩展名剥离源码注释（// # /* */，等长替换为空格，偏移不变）后再扫描；启发式，无法识别扩展名的文件原样扫描
+    #[arg(long)]
+    ignore_comments: bool,
+
+    /// 关闭文件内按 value 去重：重复出现的同一 value 逐次输出（各自的偏移，仍按偏移排序），用于取证等需要完整出现列表的场景
+    #[arg(long)]
+    no_dedup: bool,
+
+    /// 去重时忽略命中值首尾的空白与引号（" ' `）：`ghp_abc` 与 `"ghp_abc"` 视为同一值，只输出偏移最小的一条；
+    /// 仅影响去重，输出的仍是原始值
+    #[arg(long, conflicts_with = "no_dedup")]
+    normalize_dedup: bool,
+
+    /// 去重范围：per-file（文件内按 value 去重，默认）或 global（再跨文件去重，同一值只在首个出现它的文件中输出一次，
+    /// 需在内存中保留全部已输出的值）
+    #[arg(long, value_name = "SCOPE", default_value = "per-file", value_parser = ["per-file", "global"], conflicts_with = "no_dedup")]
+    dedup: String,
+
+    /// 文件内抑制被包含的命中：区间完全落在另一条命中之内的命中不输出（如通用规则命中了 `sk-` 密钥的一部分），
+    /// 区间相同时保留优先级较高（同优先级时规则文件中靠前）的规则的命中
+    #[arg(long)]
+    suppress_overlaps: bool,
+
+    /// 发现命中时以退出码 2 结束（用于 CI 阻断）；未发现时退出码为 0
+    #[arg(long)]
+    fail_on_findings: bool,
+
+    /// 配合 --fail-on-findings：计入的命中数超过 N 时才以退出码 2 结束（默认 0，即有命中即失败）
+    #[arg(long, value_name = "N", default_value_t = 0, requires = "fail_on_findings")]
+    fail_threshold: usize,
+
+    /// 配合 --fail-on-findings：仅统计不低于该严重级别的命中（low|medium|high|critical）
+    #[arg(long, value_name = "SEVERITY")]
+    fail_on_severity: Option<Severity>,
+
+    /// 检查规则中在 bytes / utf8 引擎下语义不同的构造（`.`、`\w`、`[^…]` 等）并逐条告警
+    #[arg(long)]
+    check_rule_semantics: bool,
+
+    /// 存在死规则（没有预筛锚点、扫描中从不运行的规则）时报错退出，而不只是告警
+    #[arg(long)]
+    strict_rules: bool,
+
+    /// bytes 引擎下为规则自动加上 `(?-u)`，强制 ASCII/字节语义（与 Unicode 专属构造冲突的规则保持原样）
+    #[arg(long)]
+    ascii_bytes: bool,
+
+    /// 生成跨文件共享密钥报告（同一 value 出现在哪些文件），写入旁路文件 `<output>.cross_file.json`
+    #[arg(long)]
+    cross_file_report: bool,
+
+    /// 生成原始命中位置报告（每个文件去重前的 offset / rule_id 列表，用于命中密度可视化），
+    /// 写入旁路文件 `<output>.hits.json`，按 file_hash 分组
+    #[arg(long)]
+    hit_positions: bool,
+
+    /// 生成未命中规则报告：整次扫描中输出命中数为 0 的规则 id（按加载顺序），写入旁路文件 `<output>.unused_rules.json`；
+    /// 在有代表性的数据上多次扫描后，可据此清理从不触发的规则
+    #[arg(long)]
+    unused_rules: bool,
+
+    /// 将被跳过的文件（过大、读取失败如权限不足、扫描失败）及原因写入旁路文件 `<output>.skipped.json`
+    #[arg(long)]
+    report_skipped: bool,
+
+    /// 规则校验器（validate = "luhn" 等）未通过时的处理：drop（丢弃，默认）或 annotate（保留并输出 "validated": false）
+    #[arg(long, default_value = "drop", value_parser = ["drop", "annotate"])]
+    validation_mode: String,
+
+    /// 仅输出通过校验的命中：未声明 validate 的规则的命中与校验失败的命中一律丢弃。
+    /// 需要规则声明校验器（validate = "luhn" 等）才有意义，否则不会输出任何命中
+    #[arg(long)]
+    only_validated: bool,
+
+    /// 输出中以 sha256(value) 的前 16 个十六进制字符代替原始命中值（单向指纹，便于比对两份报告的重叠而不暴露密钥）
+    #[arg(long)]
+    fingerprint: bool,
+
+    /// 写出的命中值脱敏：partial（保留首尾各 4 个字符，不超过 8 个字符的值整体遮蔽，默认）、
+    /// full（sha256 前 16 个十六进制字符，相同的值仍可比对）或 none（原样输出完整密钥，需显式指定）；
+    /// 仅影响写出的结果，去重与校验仍基于原始值；--fingerprint 时命中值已是指纹，不再脱敏
+    #[arg(long, value_name = "MODE", default_value = "partial", value_parser = ["none", "partial", "full"], conflicts_with = "fingerprint")]
+    redact: String,
+
+    /// 命中值最大长度（字节）：超过的命中被丢弃（不截断）；规则可用 max_len 单独覆盖；0 表示不限制
+    #[arg(long, value_name = "BYTES", default_value_t = DEFAULT_MAX_VALUE_LEN)]
+    max_value_len: usize,
+
+    /// 命中值白名单（每行一项，空行与 # 开头的行忽略）：普通行为字面值，命中值与其完全相同时丢弃；
+    /// `regex:` 开头的行为正则，在命中值中查找到即丢弃（需整值匹配时自行加 ^ / $）；只按值匹配不区分文件
+    #[arg(long, value_name = "PATH", visible_alias = "allowlist-file")]
+    allow_values: Option<PathBuf>,
+
+    /// 同时使用内置白名单：丢弃 AWS / Stripe 文档中的示例密钥、jwt.io 示例令牌等公开的测试值（缺省不启用，照常报告）
+    #[arg(long)]
+    default_allowlist: bool,
+
+    /// 基线文件（JSON 数组，每项为 {"file_hash", "value"} 或 {"file_hash", "sha256"}）：在基线中的命中不再输出，
+    /// 按原始值匹配（与 --redact / --fingerprint 可同时使用）；生成与使用基线时的 --hash 应相同
+    #[arg(long, value_name = "PATH")]
+    baseline: Option<PathBuf>,
+
+    /// 扫描结束后把本次的全部命中（含因 --baseline 被丢弃的）写为新的基线文件，只保存命中值的 sha256
+    #[arg(long, value_name = "PATH")]
+    write_baseline: Option<PathBuf>,
+
+    /// 增量扫描状态文件：记录各文件的大小、修改时间与扫描结果，再次扫描时未变化的文件直接复用上次的结果
+    /// （输出仍是完整结果）；规则或影响扫描结果的选项变化时全部重新扫描。不适用于标准输入
+    #[arg(long, value_name = "PATH")]
+    state: Option<PathBuf>,
+
+    /// 原子输出：先写入 `<output>.tmp`，扫描成功完成后再重命名为 --output，避免中途崩溃留下不完整的结果文件
+    /// （仅 json / json-v1 / jsonl / grouped-json / sarif 格式；table 输出到标准输出，不受影响）
+    #[arg(long)]
+    atomic_output: bool,
+
+    /// 诊断输出：按规则统计“锚点命中但规则正则未匹配”的窗口数并在扫描结束后以 debug 级别记录，
+    /// 帮助定位正则与锚点对不上的规则；同时以 debug 级别记录逐文件的内容判定（二进制跳过 / UTF-16 转码）；
+    /// 未设置 RUST_LOG 时同时开启本工具的 debug 日志
+    #[arg(long)]
+    verbose_files: bool,
+
+    /// 扫描结束后向标准错误打印统计摘要（扫描文件数、命中数、二进制跳过的文件与字节数等）
+    #[arg(long)]
+    summary: bool,
+
+    /// 不显示进度条（缺省在标准错误为终端时显示已处理的文件数与当前文件）
+    #[arg(long)]
+    quiet: bool,
+
+    /// 扫描结束后将统计（含按规则计数 findings_by_rule、有命中的文件数 files_with_findings）
+    /// 以 JSON 对象写入指定文件，便于机器读取；与 --summary 可同时使用
+    #[arg(long, value_name = "PATH")]
+    summary_json: Option<PathBuf>,
+
+    /// 输出排序：file（按文件流式输出，默认，内存占用低）或 rule（全局按 rule_id、file_hash、偏移排序；
+    /// 需在内存中缓存全部命中，扫描结束前不写出，上限见 --max-findings）
+    #[arg(long, default_value = "file", value_parser = ["file", "rule"])]
+    sort_by: String,
+
+    /// --sort-by rule 时内存中缓存的命中数上限，超过则报错终止；0 表示不限制
+    #[arg(long, value_name = "N", default_value_t = 1_000_000)]
+    max_findings: usize,
+
+    /// 单文件命中数上限：达到后停止扫描该文件，只保留偏移最靠前的 N 条（统计中计入 truncated files）；
+    /// 0 表示不限制
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    max_matches_per_file: usize,
+
+    /// 单行长度上限（字节）：超过的行（压缩后的 JS、打包产物等单行大块）不扫描，其中的命中不输出；
+    /// 仅 Bytes 引擎生效；0 表示不限制
+    #[arg(long, value_name = "BYTES", default_value_t = 0)]
+    max_line_length: usize,
+
+    /// 输出文件大小上限（字节）：达到后不再写出命中、提前停止扫描，JSON 仍正常闭合，
+    /// 统计中标记 output_truncated（防止误扫生成的密钥文件时写出数 GB 的结果）；
+    /// 需要 json / json-v1 / jsonl / grouped-json / sarif 且按文件排序，不能与 --output-dir 同时使用
+    #[arg(long, value_name = "N", conflicts_with = "output_dir")]
+    max_output_bytes: Option<u64>,
+
+    /// 输出格式：json（写入 --output 的命中数组，默认）、json-v1（带版本号的信封
+    /// `{"schema": "keyhunter/1", "findings": [...], "stats": {...}}`，便于工具识别格式版本并同时拿到统计）、
+    /// jsonl（JSON Lines：每条命中一行 JSON 对象写入 --output，便于 jq / Elasticsearch bulk 逐行消费）、
+    /// grouped-json（按文件分组写入 --output，每个文件附带 file_size 与 scanned_bytes）、
+    /// sarif（SARIF 2.1.0 日志写入 --output，供 GitHub 代码扫描等 CI 平台导入，自动附带行号）、
+    /// csv（首行为列名、每条命中一行写入 --output，便于电子表格打开；列随启用的字段而定）
+    /// 或 table（对齐表格打印到标准输出，值已脱敏）
+    #[arg(long, default_value = "json", value_parser = ["json", "json-v1", "jsonl", "grouped-json", "sarif", "csv", "table"])]
+    format: String,
+
+    /// 同一次扫描的命中同时打印到标准输出（json 或 table），如完整 JSON 报告写入 --output、
+    /// 表格打印到终端供人工查看；不能与 --format table（已输出到标准输出）或 --output-dir 同时使用
+    #[arg(long, value_name = "FORMAT", value_parser = ["json", "table"], conflicts_with = "output_dir")]
+    also_stdout: Option<String>,
+
+    /// 标准输出中的命中值脱敏（保留首尾各 4 个字符；--also-stdout json 时生效，--redact none 时仍对标准输出脱敏，表格总是脱敏）
+    #[arg(long, requires = "also_stdout")]
+    redact_stdout: bool,
 }
 
 fn main() -> Result<()> {
+    // 参数错误以 1 退出（clap 缺省为 2，会与 --fail-on-findings 的退出码混淆）；--help / --version 仍以 0 退出
+    let cli = Cli::try_parse().unwrap_or_else(|e| {
+        let _ = e.print();
+        std::process::exit(if e.use_stderr() { 1 } else { 0 });
+    });
     // 初始化日志（支持通过 RUST_LOG 控制等级，例如 info、debug）
-    init_tracing();
-    let cli = Cli::parse();
-
-    match cli.command {
-        Commands::Scan { input, output, threads, min_score, max_file_size, engine, rules } => {
-            info!(?input, ?output, "starting scan");
-
-            // 以缓冲方式打开输出文件，按 JSON 数组流式写入
-            let mut out = BufWriter::new(File::create(&output).context("create output file")?);
-
-            // 解析扫描引擎参数
-            let engine = match engine.as_str() {
-                "utf8" => ScanEngine::Utf8,
-                _ => ScanEngine::Bytes,
-            };
-            // 解析线程参数："auto" 表示自动（等于 CPU 核数）；其他为具体数值
-            let threads_opt = parse_threads(&threads);
-
-            // 组装扫描参数（min_score 暂未使用，预留）
-            let opts = ScanOptions { min_score, max_file_size, engine, rules_path: rules, threads: threads_opt };
-            let stats = scan_and_write(&input, &mut out, &opts).context("scan and write failed")?;
-            out.flush().ok();
-
-            info!(files_scanned = stats.files_scanned, outputs_written = stats.outputs_written, "scan finished");
+    let verbose = matches!(&cli.command, Commands::Scan(args) if args.verbose_files);
+    init_tracing(verbose);
+
+    let code = match cli.command {
+        Commands::Scan(args) => run_scan(&args)?,
+        Commands::Explain(args) => run_explain(&args)?,
+        Commands::Rules(RulesCommand::Test(args)) => run_rules_test(&args)?,
+        Commands::Rules(RulesCommand::List(args)) => run_rules_list(&args)?,
+        Commands::Rules(RulesCommand::Audit(args)) => run_rules_audit(&args)?,
+        Commands::Rules(RulesCommand::Check(args)) => run_rules_check(&args)?,
+        Commands::Diff(args) => run_diff(&args)?,
+    };
+    // 非零退出码（如 --fail-on-findings 命中）需显式退出，Result 传播只能得到 1
+    if code != 0 {
+        std::process::exit(code);
+    }
+
+    Ok(())
+}
+
+/// 命中触发 CI 阻断时的退出码
+const EXIT_FINDINGS: i32 = 2;
+
+/// 归档文件扩展名（小写）
+const ARCHIVE_EXTENSIONS: &[&str] = &[".zip", ".jar", ".war", ".tar", ".tar.gz", ".tgz", ".tar.bz2", ".tar.xz", ".gz", ".bz2", ".xz"];
+/// --scan-archives 支持的归档扩展名（小写，与核心库识别的格式一致）
+const SCANNABLE_ARCHIVE_EXTENSIONS: &[&str] = &[".zip", ".tar", ".tar.gz", ".tgz", ".gz"];
+
+/// `--input` 取该值时从标准输入读取
+const STDIN_INPUT: &str = "-";
+/// 标准输入内容的默认逻辑文件名
+const DEFAULT_STDIN_NAME: &str = "<stdin>";
+
+/// 执行 scan 子命令，返回进程退出码
+fn run_scan(args: &ScanArgs) -> Result<i32> {
+    let output = args.output_dir.as_ref().unwrap_or(&args.output);
+    info!(input = ?args.input, ?output, "starting scan");
+    let from_stdin = args.stdin || args.input.iter().any(|p| p.as_os_str() == STDIN_INPUT);
+    if from_stdin && args.input.len() > 1 {
+        anyhow::bail!("--input - cannot be combined with other inputs");
+    }
+    if args.stdin_name.is_some() && !from_stdin {
+        anyhow::bail!("--stdin-name requires --stdin or --input -");
+    }
+    // 进度条：仅在标准错误为终端时显示（重定向到文件 / CI 日志时不输出控制字符）；标准输入只有一个文件，不显示
+    let show_progress = !args.quiet && !from_stdin && std::io::stderr().is_terminal();
+    // 归档 / 压缩文件作为输入：--scan-archives 支持的格式（zip / tar / tar.gz / gz）按条目扫描，
+    // 其余（或未开启 --scan-archives 时）明确报错而不是静默扫描 0 个文件；按扩展名判断，扩展名无法判断时看文件头魔数
+    let scannable = |p: &Path| args.scan_archives && has_extension(p, SCANNABLE_ARCHIVE_EXTENSIONS);
+    if let Some(archive) = args.input.iter().find(|p| p.is_file() && !scannable(p) && (has_extension(p, ARCHIVE_EXTENSIONS) || has_compressed_magic(p))) {
+        anyhow::bail!(
+            "{} is an archive or compressed file; only .zip, .tar, .tar.gz/.tgz and .gz are supported (with --scan-archives), \
+             extract it and scan the extracted files instead",
+            archive.display()
+        );
+    }
+
+    // 解析输出格式：table 打印到标准输出，其余写入输出文件
+    let format = match args.format.as_str() {
+        "table" => OutputFormat::Table,
+        "grouped-json" => OutputFormat::GroupedJson,
+        "json-v1" => OutputFormat::JsonV1,
+        "sarif" => OutputFormat::Sarif,
+        "jsonl" => OutputFormat::JsonLines,
+        "csv" => OutputFormat::Csv,
+        _ => OutputFormat::Json,
+    };
+    if format == OutputFormat::GroupedJson && args.sort_by == "rule" {
+        anyhow::bail!("--format grouped-json cannot be combined with --sort-by rule");
+    }
+    if args.output_dir.is_some() {
+        if format != OutputFormat::Json { anyhow::bail!("--output-dir writes JSON files and cannot be combined with --format {}", args.format); }
+        if from_stdin { anyhow::bail!("--output-dir cannot be combined with --stdin / --input -"); }
+        if args.sort_by == "rule" { anyhow::bail!("--output-dir cannot be combined with --sort-by rule"); }
+    }
+    if args.max_output_bytes.is_some() && (format == OutputFormat::Table || args.sort_by == "rule") {
+        anyhow::bail!("--max-output-bytes requires streaming JSON output and cannot be combined with --format table or --sort-by rule");
+    }
+    let also_stdout = args.also_stdout.as_deref().map(|f| if f == "table" { OutputFormat::Table } else { OutputFormat::Json });
+    if also_stdout.is_some() && format == OutputFormat::Table {
+        anyhow::bail!("--also-stdout cannot be combined with --format table, which already prints to stdout");
+    }
+    let color = (format == OutputFormat::Table || also_stdout == Some(OutputFormat::Table)) && std::io::stdout().is_terminal();
+    // 原子输出：写入临时文件，成功后再重命名（逐文件输出不适用）
+    let atomic_tmp = (args.atomic_output && format != OutputFormat::Table && args.output_dir.is_none()).then(|| temp_output_path(&args.output));
+    // 解析扫描引擎参数
+    let engine = match args.engine.as_str() {
+        "utf8" => ScanEngine::Utf8,
+        "auto" => ScanEngine::Auto,
+        _ => ScanEngine::Bytes,
+    };
+    // 解析二进制文件处理策略
+    let binary_mode = match args.scan_binaries.as_str() {
+        "strings" => BinaryMode::Strings,
+        "raw" => BinaryMode::Raw,
+        _ => BinaryMode::Skip,
+    };
+    // 解析文件处理顺序
+    let file_order = match args.file_order.as_str() {
+        "size-desc" => FileOrder::SizeDesc,
+        "mtime-desc" => FileOrder::MtimeDesc,
+        _ => FileOrder::Name,
+    };
+    // 解析 file_hash 取值方式
+    let hash_mode = match args.hash_mode.as_str() {
+        "relpath" => HashMode::Relpath,
+        "sha256" => HashMode::Sha256,
+        "md5" => HashMode::Md5,
+        _ => HashMode::Name,
+    };
+    // 解析脱敏方式
+    let redact = match args.redact.as_str() {
+        "partial" => RedactMode::Partial,
+        "full" => RedactMode::Full,
+        _ => RedactMode::None,
+    };
+    // 解析去重范围
+    let dedup = match args.dedup.as_str() {
+        "global" => DedupScope::Global,
+        _ => DedupScope::PerFile,
+    };
+    // 解析输出排序方式
+    let sort_by = match args.sort_by.as_str() {
+        "rule" => SortOrder::Rule,
+        _ => SortOrder::File,
+    };
+    // 解析校验失败处理方式
+    let validation_mode = match args.validation_mode.as_str() {
+        "annotate" => ValidationMode::Annotate,
+        _ => ValidationMode::Drop,
+    };
+    // 命中值白名单：字面值与 `regex:` 正则
+    let (allow_values, allow_patterns) = match &args.allow_values {
+        Some(path) => load_allow_values(path)?,
+        None => (HashSet::new(), Vec::new()),
+    };
+    // 解析输出字段名映射
+    let output_fields = parse_field_renames(&args.rename_field)?;
+
+    // 组装扫描参数
+    let opts = ScanOptions {
+        min_score: args.min_score,
+        max_file_size: args.max_file_size,
+        head_bytes: args.head_bytes,
+        tail_bytes: args.tail_bytes,
+        use_mmap: args.mmap,
+        max_depth: (args.max_depth > 0).then_some(args.max_depth),
+        respect_gitignore: args.respect_gitignore,
+        follow_symlinks: args.follow_symlinks,
+        exclude_globs: args.exclude.clone(),
+        include_extensions: args.include_extensions.clone(),
+        name_globs: args.name_glob.clone(),
+        scan_archives: args.scan_archives,
+        max_files: args.max_files,
+        sample: args.sample,
+        seed: args.seed,
+        engine,
+        rules_path: args.rules.clone(),
+        rules_cache: args.rules_cache.clone(),
+        threads: args.threads,
+        file_order,
+        reorder_window: args.reorder_window,
+        hash_mode,
+        emit_path: args.emit_path,
+        sort_by,
+        max_findings: (args.max_findings > 0).then_some(args.max_findings),
+        max_matches_per_file: (args.max_matches_per_file > 0).then_some(args.max_matches_per_file),
+        max_line_length: (args.max_line_length > 0).then_some(args.max_line_length),
+        max_output_bytes: args.max_output_bytes,
+        output_fields,
+        binary_mode,
+        binary_printable_threshold: args.binary_printable_threshold,
+        detect_encoding: args.detect_encoding,
+        broad_anchor_threshold: args.broad_anchor_threshold,
+        split_broad_anchors: args.split_broad_anchors,
+        anchors_ignore_case: args.anchors_ignore_case,
+        max_window_bytes: args.max_window_bytes,
+        window_before: args.window_before,
+        window_after: args.window_after,
+        pem_window_before: args.pem_window_before,
+        pem_window_after: args.pem_window_after,
+        max_pem_windows: (args.max_pem_windows > 0).then_some(args.max_pem_windows),
+        small_file_fullscan_threshold: args.small_file_fullscan_threshold,
+        memory_budget: args.memory_budget,
+        regex_cache_capacity: args.regex_cache_capacity,
+        anchor_cache_dir: if args.no_cache { None } else { args.cache_dir.clone().or_else(default_cache_dir) },
+        regex_size_limit: (args.regex_size_limit > 0).then_some(args.regex_size_limit),
+        rule_timeout: (args.rule_timeout_ms > 0).then(|| Duration::from_millis(args.rule_timeout_ms)),
+        precompile: args.warmup,
+        format,
+        count_only: args.count_only,
+        with_metadata: args.with_metadata,
+        emit_empty: args.emit_empty,
+        color,
+        join_string_literals: args.join_string_literals,
+        decode_base64: args.decode_base64,
+        with_rule_meta: args.with_rule_meta,
+        // SARIF 结果需要行号定位
+        with_location: args.with_location || format == OutputFormat::Sarif,
+        context_lines: args.context_lines,
+        context_bytes: args.context_bytes,
+        rule_sets: args.rules_named.clone(),
+        enabled_rules: args.enable_rules.clone(),
+        disabled_rules: args.disable_rules.clone(),
+        with_score: args.with_score,
+        with_ruleset: args.with_ruleset,
+        with_source: args.with_source,
+        with_severity: args.with_severity,
+        min_severity: args.min_severity,
+        emit_rule_id: args.emit_rule_id,
+        inline_suppression: args.inline_suppression,
+        suppression_marker: args.suppression_marker.clone(),
+        ignore_comments: args.ignore_comments,
+        no_dedup: args.no_dedup,
+        normalize_dedup: args.normalize_dedup,
+        dedup,
+        suppress_overlaps: args.suppress_overlaps,
+        cross_file_report: args.cross_file_report,
+        hit_positions: args.hit_positions,
+        unused_rules: args.unused_rules,
+        check_rule_semantics: args.check_rule_semantics,
+        strict_rules: args.strict_rules,
+        verbose_files: args.verbose_files,
+        ascii_bytes_semantics: args.ascii_bytes,
+        validators: Vec::new(),
+        allow_values,
+        allow_patterns,
+        default_allowlist: args.default_allowlist,
+        baseline: match &args.baseline {
+            Some(path) => Some(Arc::new(Baseline::load(path)?)),
+            None => None,
+        },
+        write_baseline: args.write_baseline.clone(),
+        state_path: args.state.clone(),
+        validation_mode,
+        only_validated: args.only_validated,
+        fingerprint: args.fingerprint,
+        redact,
+        max_value_len: (args.max_value_len > 0).then_some(args.max_value_len),
+        cancel: None,
+        on_progress: show_progress.then(|| Arc::new(render_progress) as Arc<dyn ProgressFn>),
+    };
+    // 所有选项（白名单、字段映射、基线）解析完成后才打开输出目标；输出文件推迟到首次写出时创建，
+    // 规则文件或状态文件加载失败时已有的输出文件保持原样
+    let mut out: Box<dyn Write> = match format {
+        OutputFormat::Table => Box::new(BufWriter::new(std::io::stdout().lock())),
+        _ if args.output_dir.is_some() => Box::new(std::io::sink()),
+        _ => Box::new(DeferredFile::new(atomic_tmp.as_deref().unwrap_or(&args.output))),
+    };
+    let started = SystemTime::now();
+    let clock = Instant::now();
+    let mut content = Vec::new();
+    if from_stdin {
+        std::io::stdin().lock().read_to_end(&mut content).context("read stdin")?;
+    }
+    let name = args.stdin_name.as_deref().unwrap_or(DEFAULT_STDIN_NAME);
+    let scanned = if let Some(stdout_format) = also_stdout {
+        // 同一次扫描写入两个目标：--output（完整结果）与标准输出（可脱敏）
+        let mut stdout = BufWriter::new(std::io::stdout().lock());
+        let targets = vec![
+            OutputTarget { out: &mut out, format, redact: false },
+            OutputTarget { out: &mut stdout, format: stdout_format, redact: args.redact_stdout },
+        ];
+        let scanned = if from_stdin {
+            scan_bytes_to_targets(name, &content, targets, &opts)
+        } else {
+            scan_roots_to_targets(&args.input, targets, &opts)
+        };
+        stdout.flush().ok();
+        scanned
+    } else if from_stdin {
+        scan_bytes_and_write_report(name, &content, &mut out, &opts)
+    } else if let Some(dir) = &args.output_dir {
+        scan_roots_to_dir(&args.input, dir, &opts)
+    } else {
+        scan_roots_and_write_report(&args.input, &mut out, &opts)
+    };
+    let elapsed = clock.elapsed();
+    // 清除进度条所在行，避免与之后的日志 / 摘要混在一起
+    if show_progress { eprint!("\r\x1b[2K"); }
+    let report = match scanned {
+        Ok(r) => r,
+        Err(e) => {
+            // 扫描失败：丢弃不完整的临时文件，最终路径保持原样
+            drop(out);
+            if let Some(tmp) = &atomic_tmp { let _ = std::fs::remove_file(tmp); }
+            return Err(e.context("scan and write failed"));
+        }
+    };
+    match &atomic_tmp {
+        Some(tmp) => {
+            out.flush().context("flush output file")?;
+            drop(out);
+            std::fs::rename(tmp, &args.output).context("rename temp output file")?;
         }
+        // 输出文件在此之前可能尚未创建（没有写出任何内容的格式），flush 时创建
+        None if format != OutputFormat::Table => out.flush().context("flush output file")?,
+        None => { out.flush().ok(); }
     }
+    let stats = report.stats;
 
+    // 跨文件共享密钥报告：写入旁路文件（result.json -> result.cross_file.json）
+    if let Some(cross) = &report.cross_file {
+        let cross_path = output.with_extension("cross_file.json");
+        let f = File::create(&cross_path).context("create cross-file report")?;
+        serde_json::to_writer_pretty(BufWriter::new(f), cross).context("write cross-file report")?;
+        info!(?cross_path, shared_values = cross.shared.len(), "cross-file report written");
+    }
+
+    // 原始命中位置报告：写入旁路文件（result.json -> result.hits.json）
+    if let Some(hits) = &report.hit_positions {
+        let hits_path = output.with_extension("hits.json");
+        let f = File::create(&hits_path).context("create hit positions report")?;
+        serde_json::to_writer_pretty(BufWriter::new(f), hits).context("write hit positions report")?;
+        info!(?hits_path, files = hits.files.len(), "hit positions report written");
+    }
+
+    // 未命中规则报告：写入旁路文件（result.json -> result.unused_rules.json）
+    if let Some(unused) = &report.unused_rules {
+        let unused_path = output.with_extension("unused_rules.json");
+        let f = File::create(&unused_path).context("create unused rules report")?;
+        serde_json::to_writer_pretty(BufWriter::new(f), unused).context("write unused rules report")?;
+        info!(?unused_path, rules = unused.len(), "unused rules report written");
+    }
+
+    // 跳过的文件：写入旁路文件（result.json -> result.skipped.json）
+    if args.report_skipped {
+        let skipped_path = output.with_extension("skipped.json");
+        let f = File::create(&skipped_path).context("create skipped files report")?;
+        serde_json::to_writer_pretty(BufWriter::new(f), &report.skipped).context("write skipped files report")?;
+        info!(?skipped_path, files = report.skipped.len(), "skipped files report written");
+    }
+
+    // 运行元数据：grouped-json / sarif 已嵌入输出（只计数时除外）；其余格式写入旁路文件（result.json -> result.meta.json）
+    let metadata_embedded = !args.count_only && matches!(format, OutputFormat::GroupedJson | OutputFormat::Sarif);
+    if args.with_metadata && !metadata_embedded {
+        let meta = build_run_metadata(&opts, &stats, started).context("build run metadata")?;
+        let meta_path = output.with_extension("meta.json");
+        let f = File::create(&meta_path).context("create metadata file")?;
+        serde_json::to_writer_pretty(BufWriter::new(f), &meta).context("write metadata file")?;
+        info!(?meta_path, run_id = %meta.run_id, "metadata written");
+    }
+
+    info!(files_scanned = stats.files_scanned, outputs_written = stats.outputs_written, "scan finished");
+    info!(
+        bytes_scanned = stats.bytes_scanned,
+        elapsed_ms = elapsed.as_millis() as u64,
+        "throughput: {:.1} MB/s",
+        throughput_mb_s(stats.bytes_scanned, elapsed)
+    );
+    info!("findings by severity: {}", stats.severity_summary());
+    if stats.validation_failed > 0 {
+        info!(validation_failed = stats.validation_failed, "findings failed rule validation");
+    }
+    if stats.values_too_long > 0 {
+        info!(values_too_long = stats.values_too_long, "findings dropped for exceeding max value length");
+    }
+    if stats.low_entropy > 0 {
+        info!(low_entropy = stats.low_entropy, "findings dropped for entropy below --min-score");
+    }
+    if stats.global_duplicates > 0 {
+        info!(global_duplicates = stats.global_duplicates, "findings dropped as duplicates of values already written from earlier files");
+    }
+    if stats.overlaps_suppressed > 0 {
+        info!(overlaps_suppressed = stats.overlaps_suppressed, "findings dropped as contained in another finding's span");
+    }
+    if stats.allowlisted > 0 {
+        info!(allowlisted = stats.allowlisted, "findings dropped by the value allowlist");
+    }
+    if stats.suppressed > 0 {
+        info!(suppressed = stats.suppressed, "findings suppressed by the baseline");
+    }
+    if stats.truncated_files > 0 {
+        info!(truncated_files = stats.truncated_files, "files truncated at --max-matches-per-file, later matches in them were not reported");
+    }
+    if stats.files_reused > 0 {
+        info!(files_reused = stats.files_reused, "unchanged files reused from the scan state");
+    }
+    if stats.files_errored > 0 {
+        info!(files_errored = stats.files_errored, "files skipped after read or scan errors, see warnings above or --report-skipped");
+    }
+    if stats.long_lines_skipped > 0 {
+        info!(long_lines_skipped = stats.long_lines_skipped, "lines longer than --max-line-length skipped");
+    }
+    if stats.rule_timeouts > 0 {
+        info!(rule_timeouts = stats.rule_timeouts, "rule windows abandoned after exceeding --rule-timeout-ms, results may be incomplete");
+    }
+    if stats.below_min_severity > 0 {
+        info!(below_min_severity = stats.below_min_severity, "findings dropped for severity below --min-severity");
+    }
+    if let Some(path) = &args.write_baseline {
+        info!(?path, "baseline written");
+    }
+    if stats.output_truncated {
+        info!(max_output_bytes = args.max_output_bytes, outputs_written = stats.outputs_written, "output truncated at the size limit, scan stopped early");
+    }
+    if args.summary {
+        print_summary(&stats, report.skipped.len(), elapsed)?;
+    }
+    if let Some(summary_path) = &args.summary_json {
+        let f = File::create(summary_path).context("create summary report")?;
+        serde_json::to_writer_pretty(BufWriter::new(f), &stats).context("write summary report")?;
+        info!(?summary_path, rules = stats.findings_by_rule.len(), "summary report written");
+    }
+
+    // CI 阻断：可按最低严重级别筛选计数，避免低危信息性命中导致失败；计数超过阈值才失败
+    if args.fail_on_findings {
+        let counted = match args.fail_on_severity {
+            Some(min) => stats.findings_at_least(min),
+            None => stats.outputs_written,
+        };
+        if counted > args.fail_threshold {
+            info!(counted, threshold = args.fail_threshold, "findings above the failure threshold");
+            return Ok(EXIT_FINDINGS);
+        }
+    }
+    Ok(0)
+}
+
+/// 文件名（不区分大小写）是否以 `extensions` 之一结尾（如 .zip / .jar / .tar.gz）
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    let name = path.file_name().and_then(|s| s.to_str()).unwrap_or_default().to_ascii_lowercase();
+    extensions.iter().any(|ext| name.ends_with(ext))
+}
+
+/// 文件头是否为 gzip / bzip2 / xz 压缩格式（读取失败视为否，由扫描阶段报告）：
+/// gzip 要求魔数与 deflate 方法字节 `1F 8B 08`，bzip2 要求完整的流头与首块魔数 `BZh[1-9]1AY&SY`，
+/// xz 要求 6 字节流头魔数；以 "BZh" 开头的普通文本不会被误判
+fn has_compressed_magic(path: &Path) -> bool {
+    let mut head = [0u8; 10];
+    let n = File::open(path).and_then(|mut f| f.read(&mut head)).unwrap_or(0);
+    let head = &head[..n];
+    head.starts_with(b"\x1F\x8B\x08")
+        || (n == 10 && head.starts_with(b"BZh") && (b'1'..=b'9').contains(&head[3]) && &head[4..] == b"1AY&SY")
+        || head.starts_with(b"\xFD7zXZ\x00")
+}
+
+/// 默认锚点缓存目录：$XDG_CACHE_HOME/keyhunter，未设置时为 $HOME/.cache/keyhunter；两者都没有时不缓存
+fn default_cache_dir() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_CACHE_HOME").filter(|v| !v.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(std::env::var_os("HOME").filter(|v| !v.is_empty())?).join(".cache"),
+    };
+    Some(base.join("keyhunter"))
+}
+
+/// 读取命中值白名单：每行一项（去掉行尾 CR），忽略空行与 `#` 开头的注释行；返回字面值与 `regex:` 开头的正则
+fn load_allow_values(path: &Path) -> Result<(HashSet<String>, Vec<String>)> {
+    let txt = std::fs::read_to_string(path).with_context(|| format!("read allow values {}", path.display()))?;
+    let mut values = HashSet::new();
+    let mut patterns = Vec::new();
+    for line in txt.lines().map(|l| l.trim_end_matches('\r')).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+        match line.strip_prefix("regex:") {
+            Some(pat) => patterns.push(pat.to_string()),
+            None => { values.insert(line.to_string()); }
+        }
+    }
+    Ok((values, patterns))
+}
+
+/// 进度条：在标准错误同一行重绘 `[####....] 已处理/总数 当前文件`；约每 1% 重绘一次，不保存状态
+fn render_progress(ev: ProgressEvent<'_>) {
+    const WIDTH: usize = 30;
+    const PATH_WIDTH: usize = 40;
+    let step = (ev.files_total / 100).max(1);
+    if !ev.files_done.is_multiple_of(step) && ev.files_done != ev.files_total { return; }
+    let filled = (ev.files_done * WIDTH).checked_div(ev.files_total).unwrap_or(WIDTH).min(WIDTH);
+    let path = ev.current_path.display().to_string();
+    // 路径过长时只保留末尾，保证整行不折行
+    let skip = path.chars().count().saturating_sub(PATH_WIDTH);
+    let tail: String = path.chars().skip(skip).collect();
+    let ellipsis = if skip > 0 { "…" } else { "" };
+    eprint!("\r\x1b[2K[{}{}] {}/{} {}{}", "#".repeat(filled), ".".repeat(WIDTH - filled), ev.files_done, ev.files_total, ellipsis, tail);
+}
+
+/// 首次写出（或 flush）时才创建的输出文件：扫描在写出任何内容之前失败时不截断已有文件
+struct DeferredFile {
+    path: PathBuf,
+    file: Option<BufWriter<File>>,
+}
+
+impl DeferredFile {
+    fn new(path: &Path) -> Self {
+        Self { path: path.to_path_buf(), file: None }
+    }
+
+    fn file(&mut self) -> std::io::Result<&mut BufWriter<File>> {
+        if self.file.is_none() {
+            let f = File::create(&self.path)
+                .map_err(|e| std::io::Error::new(e.kind(), format!("create output file {}: {}", self.path.display(), e)))?;
+            self.file = Some(BufWriter::new(f));
+        }
+        Ok(self.file.as_mut().expect("output file just created"))
+    }
+}
+
+impl Write for DeferredFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.file()?.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file()?.flush()
+    }
+}
+
+/// 原子输出使用的临时文件路径：`<output>.tmp`（与最终文件同目录，保证 rename 不跨文件系统）
+fn temp_output_path(output: &Path) -> PathBuf {
+    let mut tmp = output.as_os_str().to_os_string();
+    tmp.push(".tmp");
+    PathBuf::from(tmp)
+}
+
+/// 扫描吞吐量（MB/s，1 MB = 10^6 字节）；`elapsed` 为扫描的墙钟时间，含规则加载与遍历目录
+fn throughput_mb_s(bytes: u64, elapsed: Duration) -> f64 {
+    let secs = elapsed.as_secs_f64();
+    if secs > 0.0 { bytes as f64 / 1e6 / secs } else { 0.0 }
+}
+
+/// 打印扫描统计摘要（标准错误，避免与表格输出混在一起）
+fn print_summary(stats: &ScanStats, skipped_files: usize, elapsed: Duration) -> Result<()> {
+    let mut err = std::io::stderr().lock();
+    writeln!(err, "files scanned:         {}", stats.files_scanned)?;
+    writeln!(err, "files skipped:         {}", skipped_files)?;
+    writeln!(err, "files errored:         {}", stats.files_errored)?;
+    writeln!(err, "files reused:          {}", stats.files_reused)?;
+    writeln!(err, "bytes scanned:         {} ({:.1} MB/s)", stats.bytes_scanned, throughput_mb_s(stats.bytes_scanned, elapsed))?;
+    writeln!(err, "binary files skipped:  {} ({} bytes)", stats.files_skipped_binary, stats.bytes_skipped_binary)?;
+    writeln!(err, "utf-16 files decoded:  {}", stats.files_utf16)?;
+    writeln!(err, "findings written:      {}", stats.outputs_written)?;
+    writeln!(err, "files with findings:   {}", stats.files_with_findings)?;
+    writeln!(err, "findings by severity:  {}", stats.severity_summary())?;
+    writeln!(err, "validation failed:     {}", stats.validation_failed)?;
+    writeln!(err, "not validated:         {}", stats.not_validated)?;
+    writeln!(err, "values too long:       {}", stats.values_too_long)?;
+    writeln!(err, "low entropy values:    {}", stats.low_entropy)?;
+    writeln!(err, "allowlisted values:    {}", stats.allowlisted)?;
+    writeln!(err, "baseline suppressed:   {}", stats.suppressed)?;
+    writeln!(err, "below min severity:    {}", stats.below_min_severity)?;
+    writeln!(err, "global duplicates:     {}", stats.global_duplicates)?;
+    writeln!(err, "overlaps suppressed:   {}", stats.overlaps_suppressed)?;
+    writeln!(err, "rule timeouts:         {}", stats.rule_timeouts)?;
+    writeln!(err, "long lines skipped:    {}", stats.long_lines_skipped)?;
+    writeln!(err, "truncated files:       {}", stats.truncated_files)?;
+    if stats.output_truncated { writeln!(err, "output truncated:      yes")?; }
+    Ok(())
+}
+
+/// 执行 explain 子命令：打印预筛计划报告，返回进程退出码
+fn run_explain(args: &ExplainArgs) -> Result<i32> {
+    let opts = ScanOptions { rules_path: args.rules.clone(), ..Default::default() };
+    let plan = load_prefilter_plan(&opts).context("load rules")?;
+    let report = describe_plan(&plan);
+
+    let stdout = std::io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    if args.json {
+        serde_json::to_writer_pretty(&mut out, &report).context("write report")?;
+        writeln!(out)?;
+    } else {
+        write_plan_report(&mut out, &report)?;
+    }
+    out.flush()?;
+    Ok(0)
+}
+
+/// 执行 rules list 子命令：按加载顺序列出生效规则
+/// 锚点状态：anchored（由预筛触发）、anchorless（无锚点，不会运行）、path（路径作用域，整文件运行）、
+/// 以及 env / der / url（专用检测器，不经过预筛）
+fn run_rules_list(args: &RulesListArgs) -> Result<i32> {
+    let opts = ScanOptions {
+        rules_path: args.rules.clone(),
+        rules_cache: args.rules_cache.clone(),
+        rule_sets: args.rules_named.clone(),
+        ..Default::default()
+    };
+    let plan = load_prefilter_plan(&opts).context("load rules")?;
+    let report = describe_plan(&plan);
+
+    let stdout = std::io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    if args.json {
+        serde_json::to_writer_pretty(&mut out, &report.rules).context("write rule list")?;
+        writeln!(out)?;
+    } else {
+        let rows: Vec<[String; 5]> = report
+            .rules
+            .iter()
+            .map(|r| {
+                let status = if r.path.is_some() {
+                    "path"
+                } else if r.kind != "regex" {
+                    r.kind.as_str()
+                } else if r.anchorless {
+                    "anchorless"
+                } else {
+                    "anchored"
+                };
+                let id = match &r.ruleset { Some(set) => format!("{}/{}", set, r.id), None => r.id.clone() };
+                [id, r.name.clone().unwrap_or_default(), r.severity.to_string(), status.to_string(), r.pattern.clone().unwrap_or_default()]
+            })
+            .collect();
+        let header = ["id", "name", "severity", "anchor", "pattern"];
+        let mut widths = header.map(str::len);
+        for row in &rows {
+            for (w, cell) in widths.iter_mut().zip(row.iter()).take(4) { *w = (*w).max(cell.chars().count()); }
+        }
+        let line = |cells: [&str; 5]| {
+            let mut s = String::new();
+            for (i, cell) in cells.iter().enumerate().take(4) {
+                s.push_str(&format!("{:<width$}  ", cell, width = widths[i]));
+            }
+            s.push_str(cells[4]);
+            s
+        };
+        writeln!(out, "{}", line(header))?;
+        for row in &rows {
+            writeln!(out, "{}", line([&row[0], &row[1], &row[2], &row[3], &row[4]]))?;
+        }
+        writeln!(out, "{} rules, {} anchors, {} anchorless", report.rules.len(), report.anchor_count, report.anchorless_count())?;
+    }
+    out.flush()?;
+    Ok(0)
+}
+
+/// 执行 rules audit 子命令：打印锚点族数量与疑似冗余的规则对（仅报告，退出码恒为 0）
+fn run_rules_audit(args: &RulesAuditArgs) -> Result<i32> {
+    if !(0.0..=1.0).contains(&args.min_similarity) {
+        anyhow::bail!("--min-similarity must be between 0 and 1");
+    }
+    let opts = ScanOptions {
+        rules_path: args.rules.clone(),
+        rules_cache: args.rules_cache.clone(),
+        rule_sets: args.rules_named.clone(),
+        ..Default::default()
+    };
+    let plan = load_prefilter_plan(&opts).context("load rules")?;
+    let report = audit_rules(&plan, args.min_similarity);
+
+    let stdout = std::io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    if args.json {
+        serde_json::to_writer_pretty(&mut out, &report).context("write audit report")?;
+        writeln!(out)?;
+    } else {
+        for p in &report.redundant_pairs {
+            writeln!(out, "{} <-> {} (similarity {:.3})", p.first, p.second, p.similarity)?;
+            writeln!(out, "  shared anchors: {}", p.anchors.join(", "))?;
+        }
+        writeln!(out, "{} anchor families, {} likely redundant pairs", report.anchor_families, report.redundant_pairs.len())?;
+    }
+    out.flush()?;
+    Ok(0)
+}
+
+/// 存在无法使用的规则时 rules check 的退出码
+const EXIT_RULES_BROKEN: i32 = 1;
+
+/// 执行 rules check 子命令：逐条打印无法使用的规则及原因，存在时返回非零退出码
+fn run_rules_check(args: &RulesCheckArgs) -> Result<i32> {
+    let opts = ScanOptions {
+        rules_path: args.rules.clone(),
+        rules_cache: args.rules_cache.clone(),
+        rule_sets: args.rules_named.clone(),
+        regex_size_limit: (args.regex_size_limit > 0).then_some(args.regex_size_limit),
+        ..Default::default()
+    };
+    let broken = check_rules(&opts).context("load rules")?;
+
+    let stdout = std::io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    if args.json {
+        serde_json::to_writer_pretty(&mut out, &broken).context("write check report")?;
+        writeln!(out)?;
+    } else {
+        for b in &broken {
+            writeln!(out, "rule '{}':", b.rule_id)?;
+            for line in b.error.lines() {
+                writeln!(out, "  {}", line)?;
+            }
+        }
+        writeln!(out, "{} broken rules", broken.len())?;
+    }
+    out.flush()?;
+    Ok(if broken.is_empty() { 0 } else { EXIT_RULES_BROKEN })
+}
+
+/// 执行 diff 子命令：打印两次结果之间新增（+）与消失（-）的命中
+fn run_diff(args: &DiffArgs) -> Result<i32> {
+    let old = load_results(&args.old)?;
+    let new = load_results(&args.new)?;
+    let diff = diff_results(&old, &new, args.with_rule_id);
+
+    let stdout = std::io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    if args.format == "json" {
+        serde_json::to_writer_pretty(&mut out, &diff).context("write diff")?;
+        writeln!(out)?;
+    } else {
+        for (sign, items) in [("+", &diff.added), ("-", &diff.removed)] {
+            for it in items {
+                let rule = if it.rule_id.is_empty() { String::new() } else { format!(" [{}]", it.rule_id) };
+                writeln!(out, "{} {} {}{}", sign, it.file_hash, mask_value(&it.value), rule)?;
+            }
+        }
+        writeln!(out, "{} added, {} removed", diff.added.len(), diff.removed.len())?;
+    }
+    out.flush()?;
+    Ok(0)
+}
+
+/// 规则自测存在失败用例时的退出码
+const EXIT_RULE_TEST_FAILED: i32 = 1;
+
+/// 执行 rules test 子命令：逐条打印 PASS/FAIL 与命中的规则，有失败用例时返回非零退出码
+fn run_rules_test(args: &RulesTestArgs) -> Result<i32> {
+    let mut cases: Vec<RuleTestCase> = Vec::new();
+    if let Some(path) = &args.cases {
+        cases.extend(load_rule_test_cases(path)?);
+    }
+    cases.extend(args.positive.iter().map(|s| RuleTestCase { input: s.clone(), expect_match: true }));
+    cases.extend(args.negative.iter().map(|s| RuleTestCase { input: s.clone(), expect_match: false }));
+    let opts = ScanOptions { rules_path: args.rules.clone(), ..Default::default() };
+    if cases.is_empty() {
+        return run_rule_examples_test(&opts);
+    }
+
+    let outcomes = run_rule_tests(&opts, &cases).context("run rule tests")?;
+
+    let stdout = std::io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    for o in &outcomes {
+        let status = if o.passed { "PASS" } else { "FAIL" };
+        let expect = if o.expect_match { "match" } else { "no match" };
+        writeln!(out, "{} [expect {}] {:?}", status, expect, o.input)?;
+        for m in &o.matches {
+            let note = if m.prefilter_hit { "" } else { " (no anchor hit: skipped by the prefilter in real scans)" };
+            writeln!(out, "  {} => {:?}{}", m.rule_id, m.value, note)?;
+        }
+    }
+    let failed = outcomes.iter().filter(|o| !o.passed).count();
+    writeln!(out, "{} cases, {} passed, {} failed", outcomes.len(), outcomes.len() - failed, failed)?;
+    out.flush()?;
+    Ok(if failed > 0 { EXIT_RULE_TEST_FAILED } else { 0 })
+}
+
+/// 检查规则自带的 examples / negatives：逐条打印 PASS/FAIL，有失败样本时返回非零退出码
+fn run_rule_examples_test(opts: &ScanOptions) -> Result<i32> {
+    let outcomes = run_rule_examples(opts).context("run rule examples")?;
+    if outcomes.is_empty() {
+        anyhow::bail!("no test cases: no rule declares examples / negatives, use --positive / --negative or --cases");
+    }
+
+    let stdout = std::io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    for o in &outcomes {
+        let status = if o.passed { "PASS" } else { "FAIL" };
+        let kind = if o.expect_match { "example" } else { "negative" };
+        writeln!(out, "{} {} [{}] {:?}", status, o.rule_id, kind, o.input)?;
+        match &o.matched {
+            Some(m) if !m.prefilter_hit => {
+                writeln!(out, "  => {:?} (no anchor hit: skipped by the prefilter in real scans)", m.value)?
+            }
+            Some(m) => writeln!(out, "  => {:?}", m.value)?,
+            None => {}
+        }
+    }
+    let failed: HashSet<&str> = outcomes.iter().filter(|o| !o.passed).map(|o| o.rule_id.as_str()).collect();
+    let rules: HashSet<&str> = outcomes.iter().map(|o| o.rule_id.as_str()).collect();
+    let failed_cases = outcomes.iter().filter(|o| !o.passed).count();
+    writeln!(
+        out,
+        "{} rules, {} samples, {} passed, {} failed ({} rules failing)",
+        rules.len(),
+        outcomes.len(),
+        outcomes.len() - failed_cases,
+        failed_cases,
+        failed.len()
+    )?;
+    out.flush()?;
+    Ok(if failed_cases > 0 { EXIT_RULE_TEST_FAILED } else { 0 })
+}
+
+/// 以可读文本打印预筛计划报告（无锚点规则以 [anchorless] 标记）
+fn write_plan_report(out: &mut dyn Write, report: &PlanReport) -> Result<()> {
+    for r in &report.rules {
+        match &r.name {
+            Some(name) => writeln!(out, "{} ({})", r.id, name)?,
+            None => writeln!(out, "{}", r.id)?,
+        }
+        if let Some(glob) = &r.path {
+            writeln!(out, "  [path: {}] runs on matching files only, bypasses the prefilter", glob)?;
+        } else if r.kind == "env" {
+            writeln!(out, "  [env] line-based NAME=value detector, no anchors")?;
+        } else if r.kind == "der" {
+            writeln!(out, "  [der] headerless base64 DER key detector, no anchors")?;
+        } else if r.kind == "url" {
+            writeln!(out, "  [url] user:password@ credentials in URLs, no anchors")?;
+        } else if r.anchorless {
+            writeln!(out, "  [anchorless] never triggered by the prefilter")?;
+        } else {
+            writeln!(out, "  anchors: {}", r.anchors.join(", "))?;
+        }
+    }
+    writeln!(
+        out,
+        "{} rules, {} anchors, {} anchorless",
+        report.rules.len(),
+        report.anchor_count,
+        report.anchorless_count()
+    )?;
     Ok(())
 }
 
-fn init_tracing() {
+/// `verbose`：未设置 RUST_LOG 时对本工具的模块开启 debug 级别（`--verbose-files`）
+fn init_tracing(verbose: bool) {
     use tracing_subscriber::{EnvFilter, FmtSubscriber};
     // 支持通过环境变量 RUST_LOG 控制日志等级，如：RUST_LOG=debug
-    let env_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
-    let subscriber = FmtSubscriber::builder().with_env_filter(env_filter).finish();
+    let default = if verbose { "info,keyhunter=debug,keyhunter_core=debug" } else { "info" };
+    let env_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default));
+    // 日志输出到 stderr，避免与标准输出上的结果（如表格格式）混杂
+    let subscriber = FmtSubscriber::builder().with_env_filter(env_filter).with_writer(std::io::stderr).finish();
     let _ = tracing::subscriber::set_global_default(subscriber);
 }
 
-/// 解析线程参数
-fn parse_threads(s: &str) -> Option<usize> {
-    if s.eq_ignore_ascii_case("auto") { return None; }
+/// 解析线程参数："auto" 表示自动（等于 CPU 核数，返回 None）；其余必须为正整数，否则报错
+fn parse_threads(s: &str) -> Result<Option<usize>, String> {
+    if s.eq_ignore_ascii_case("auto") { return Ok(None); }
     match s.parse::<usize>() {
-        Ok(n) if n >= 1 => Some(n),
-        _ => None,
+        Ok(n) if n >= 1 => Ok(Some(n)),
+        Ok(_) => Err("thread count must be at least 1 (or \"auto\")".to_string()),
+        Err(_) => Err(format!("expected a positive integer or \"auto\", got '{}'", s)),
+    }
+}
+
+/// 解析 0~1 之间的比例
+fn parse_ratio(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(r) if (0.0..=1.0).contains(&r) => Ok(r),
+        Ok(_) => Err("ratio must be between 0 and 1".to_string()),
+        Err(_) => Err(format!("expected a number between 0 and 1, got '{}'", s)),
+    }
+}
+
+/// 解析命名规则集参数（NAME=PATH）
+fn parse_named_rules(s: &str) -> Result<(String, PathBuf), String> {
+    match s.split_once('=') {
+        Some((name, path)) if !name.trim().is_empty() && !path.is_empty() => Ok((name.trim().to_string(), PathBuf::from(path))),
+        _ => Err(format!("expected NAME=PATH, got '{}'", s)),
+    }
+}
+
+/// 解析字段名映射参数（FROM=TO）：格式错误、同一字段重复映射、未知的原字段名或重映射后字段名冲突时直接报错
+fn parse_field_renames(items: &[String]) -> Result<HashMap<String, String>> {
+    let mut map = HashMap::new();
+    for it in items {
+        match it.split_once('=') {
+            Some((from, to)) if !from.trim().is_empty() && !to.trim().is_empty() => {
+                if map.insert(from.trim().to_string(), to.trim().to_string()).is_some() {
+                    anyhow::bail!("--rename-field renames '{}' more than once", from.trim());
+                }
+            }
+            _ => anyhow::bail!("invalid --rename-field value '{}', expected FROM=TO", it),
+        }
     }
+    check_field_renames(&map).context("invalid --rename-field")?;
+    Ok(map)
 }
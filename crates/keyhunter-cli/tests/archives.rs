@@ -0,0 +1,45 @@
+//! 归档 / 压缩文件作为 `--input`：--scan-archives 支持的格式按条目扫描，其余明确报错
+mod common;
+
+use common::{scan, TempDir, GHP_TOKEN};
+
+/// 以 stored（不压缩）deflate 块组成的最小 gzip 文件（CRC 字段写 0，扫描不校验 CRC）
+fn stored_gzip(data: &[u8]) -> Vec<u8> {
+    let len = data.len() as u16;
+    let mut out = vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 255, 1];
+    out.extend_from_slice(&len.to_le_bytes());
+    out.extend_from_slice(&(!len).to_le_bytes());
+    out.extend_from_slice(data);
+    out.extend_from_slice(&[0; 4]);
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out
+}
+
+#[test]
+fn compressed_inputs_are_scanned_or_rejected() {
+    let dir = TempDir::new("archives");
+    dir.write("notes.txt", format!("BZh is how this note starts\nGITHUB_TOKEN={}\n", GHP_TOKEN));
+    dir.write("app.log.gz", stored_gzip(format!("GITHUB_TOKEN={}\n", GHP_TOKEN).as_bytes()));
+    dir.write("dump", b"BZh91AY&SY\x00\x01\x02\x03 rest of a bzip2 stream");
+
+    let run = |args: &[&str]| scan(&dir, &[&["--output", "result.json"][..], args].concat());
+    // 以 "BZh" 开头的普通文本不是 bzip2，正常扫描
+    let out = run(&["--input", "notes.txt"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert!(dir.read("result.json").contains(GHP_TOKEN));
+
+    // .gz 不开 --scan-archives 时报错，开启后解压扫描
+    let out = run(&["--input", "app.log.gz"]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("--scan-archives"));
+    let out = run(&["--input", "app.log.gz", "--scan-archives"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert!(dir.read("result.json").contains("app.log.gz!app.log"));
+
+    // 完整 bzip2 文件头：无论是否开启 --scan-archives 都报错
+    for extra in [&[][..], &["--scan-archives"][..]] {
+        let out = run(&[&["--input", "dump"][..], extra].concat());
+        assert_eq!(out.status.code(), Some(1));
+        assert!(String::from_utf8_lossy(&out.stderr).contains("archive or compressed file"));
+    }
+}
@@ -0,0 +1,19 @@
+//! UTF-16 文本只在 --detect-encoding 时转码扫描，默认按二进制跳过
+mod common;
+
+use common::{scan, TempDir, GHP_TOKEN};
+
+#[test]
+fn detect_encoding_flag_enables_utf16_scanning() {
+    let dir = TempDir::new("encoding");
+    let text = format!("GITHUB_TOKEN={}\r\n", GHP_TOKEN);
+    dir.write("in/settings.ini", [&[0xFF, 0xFE][..], &text.encode_utf16().flat_map(u16::to_le_bytes).collect::<Vec<u8>>()].concat());
+
+    let out = scan(&dir, &["--input", "in", "--output", "result.json"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(dir.read("result.json"), "[]");
+
+    let out = scan(&dir, &["--input", "in", "--output", "result.json", "--detect-encoding"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert!(dir.read("result.json").contains(GHP_TOKEN));
+}
@@ -0,0 +1,29 @@
+//! scan 的退出码约定：0 完成，1 出错，2 启用 --fail-on-findings 且命中数超过阈值
+mod common;
+
+use common::{scan, TempDir, GHP_TOKEN};
+
+#[test]
+fn fail_on_findings_sets_exit_code_two() {
+    let dir = TempDir::new("exit-code");
+    dir.write("dirty/a.env", format!("A={}\nB={}\n", GHP_TOKEN, GHP_TOKEN.replace("R8kq", "Q7jp")));
+    dir.write("clean/a.txt", "nothing to see here\n");
+
+    let code = |args: &[&str]| {
+        let base = ["--output", "result.json"];
+        scan(&dir, &[&base[..], args].concat()).status.code()
+    };
+    // 不启用 --fail-on-findings 时有命中也以 0 结束
+    assert_eq!(code(&["--input", "dirty"]), Some(0));
+    assert_eq!(code(&["--input", "clean", "--fail-on-findings"]), Some(0));
+    assert_eq!(code(&["--input", "dirty", "--fail-on-findings"]), Some(2));
+    // 阈值：命中数（2）超过 N 才失败
+    assert_eq!(code(&["--input", "dirty", "--fail-on-findings", "--fail-threshold", "1"]), Some(2));
+    assert_eq!(code(&["--input", "dirty", "--fail-on-findings", "--fail-threshold", "2"]), Some(0));
+    // 只统计不低于给定严重级别的命中
+    assert_eq!(code(&["--input", "dirty", "--fail-on-findings", "--fail-on-severity", "critical"]), Some(0));
+    // 运行出错与参数错误都以 1 结束，不与命中混淆
+    assert_eq!(code(&["--input", "dirty", "--fail-on-findings", "--baseline", "missing.json"]), Some(1));
+    assert_eq!(code(&["--input", "dirty", "--fail-on-findings", "--no-such-flag"]), Some(1));
+    assert_eq!(code(&["--input", "dirty", "--fail-threshold", "1"]), Some(1));
+}
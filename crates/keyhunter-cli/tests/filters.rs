@@ -0,0 +1,23 @@
+//! 按扩展名 / 名称过滤输入文件（--ext、--name-glob）
+mod common;
+
+use common::{scan, TempDir, GHP_TOKEN};
+
+#[test]
+fn ext_and_name_glob_select_the_scanned_files() {
+    let dir = TempDir::new("filters");
+    for (i, name) in ["a.env", "b.yaml", "c.json", "d.pem", "e.txt"].iter().enumerate() {
+        dir.write(&format!("in/{}", name), format!("GITHUB_TOKEN={}\n", GHP_TOKEN.replace("R8kq", &format!("X{}y{}", i, i))));
+    }
+
+    let files = |args: &[&str]| -> Vec<String> {
+        let out = scan(&dir, &[&["--input", "in", "--output", "result.json"][..], args].concat());
+        assert!(out.status.success(), "{:?}: {}", args, String::from_utf8_lossy(&out.stderr));
+        let items: Vec<serde_json::Value> = serde_json::from_str(&dir.read("result.json")).unwrap();
+        items.iter().map(|it| it["file_hash"].as_str().unwrap().to_string()).collect()
+    };
+    assert_eq!(files(&["--ext", "env"]), ["a.env"]);
+    assert_eq!(files(&["--ext", "ENV", "--ext", ".pem"]), ["a.env", "d.pem"]);
+    assert_eq!(files(&["--ext", "env", "--name-glob", "*.yaml"]), ["a.env", "b.yaml"]);
+    assert_eq!(files(&[]).len(), 5);
+}
@@ -0,0 +1,72 @@
+//! rules 子命令
+mod common;
+
+use common::{default_rules, fixture, keyhunter, TempDir, GHP_TOKEN};
+
+/// rules test：逐条报告样本命中的规则，有不符合期望的样本时以 1 退出
+#[test]
+fn rules_test_reports_matches_and_fails_on_unexpected_results() {
+    let dir = TempDir::new("rules-test");
+    let rules = default_rules();
+    let positive = format!("token = {}", GHP_TOKEN);
+    let base = ["rules", "test", "--rules", rules.to_str().unwrap(), "--positive", &positive];
+
+    let out = keyhunter(&dir, &[&base[..], &["--negative", "notakey"]].concat());
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(out.status.code(), Some(0), "{}", stdout);
+    assert!(stdout.contains("github.pat"), "{}", stdout);
+    assert!(stdout.contains("2 cases, 2 passed, 0 failed"), "{}", stdout);
+
+    // 同一个令牌作为反例：该样本失败
+    let out = keyhunter(&dir, &[&base[..], &["--negative", GHP_TOKEN]].concat());
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(out.status.code(), Some(1), "{}", stdout);
+    assert!(stdout.contains("2 cases, 1 passed, 1 failed"), "{}", stdout);
+}
+
+/// rules test 不带样本：检查规则自带的 examples / negatives，逐条报告并在有失败时以 1 退出
+#[test]
+fn rules_test_without_samples_checks_rule_examples() {
+    let dir = TempDir::new("rules-examples");
+    let rules = fixture("rule_examples.toml");
+
+    let out = keyhunter(&dir, &["rules", "test", "--rules", rules.to_str().unwrap()]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(out.status.code(), Some(1), "{}", stdout);
+    assert!(stdout.contains("PASS test.pass [example] \"key = tk_live_0123456789ab\""), "{}", stdout);
+    assert!(stdout.contains("FAIL test.fail [example] \"sk_prod_0123\""), "{}", stdout);
+    assert!(stdout.contains("FAIL test.fail [negative] \"sk_prod_0123456789ab\""), "{}", stdout);
+    assert!(stdout.contains("2 rules, 5 samples, 3 passed, 2 failed (1 rules failing)"), "{}", stdout);
+
+    // 仓库自带规则的样本全部通过
+    let default = default_rules();
+    let out = keyhunter(&dir, &["rules", "test", "--rules", default.to_str().unwrap()]);
+    assert_eq!(out.status.code(), Some(0), "{}", String::from_utf8_lossy(&out.stdout));
+}
+
+/// rules check：列出无法编译的规则并以 1 退出；scan 遇到同样的规则集时报错
+#[test]
+fn rules_check_lists_broken_patterns() {
+    let dir = TempDir::new("rules-check");
+    dir.write(
+        "rules.toml",
+        "[[rules]]\nid = \"test.valid\"\nprefixes = [\"ghp_\"]\npattern = \"[0-9a-zA-Z]{36}\"\n\n\
+         [[rules]]\nid = \"test.broken\"\npattern = \"api_key=([a-z0-9]{16}\"\n",
+    );
+    dir.write("in/a.env", format!("GITHUB_TOKEN={}\n", GHP_TOKEN));
+
+    let out = keyhunter(&dir, &["rules", "check", "--rules", "rules.toml"]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(out.status.code(), Some(1), "{}", stdout);
+    assert!(stdout.contains("rule 'test.broken'"), "{}", stdout);
+    assert!(!stdout.contains("test.valid"), "{}", stdout);
+    assert!(stdout.contains("1 broken rules"), "{}", stdout);
+
+    let out = keyhunter(&dir, &["scan", "--rules", "rules.toml", "--input", "in", "--output", "result.json", "--no-cache"]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("test.broken"));
+    assert!(!dir.path().join("result.json").exists());
+
+    let out = keyhunter(&dir, &["rules", "check", "--rules", default_rules().to_str().unwrap()]);
+    assert_eq!(out.status.code(), Some(0), "{}", String::from_utf8_lossy(&out.stdout));
+}
@@ -0,0 +1,27 @@
+//! 从标准输入扫描（--stdin / --input -）
+mod common;
+
+use common::{default_rules, keyhunter_with_stdin, TempDir, GHP_TOKEN};
+
+#[test]
+fn piped_content_is_scanned_like_a_single_file() {
+    let dir = TempDir::new("stdin");
+    let rules = default_rules();
+    let content = format!("export GITHUB_TOKEN={}\n", GHP_TOKEN);
+    let base = ["scan", "--rules", rules.to_str().unwrap(), "--no-cache", "--redact", "none", "--output", "result.json"];
+
+    for (args, name) in [
+        (&["--stdin"][..], "<stdin>"),
+        (&["--input", "-"][..], "<stdin>"),
+        (&["--stdin", "--stdin-name", "deploy.sh"][..], "deploy.sh"),
+    ] {
+        let out = keyhunter_with_stdin(&dir, &[&base[..], args].concat(), content.as_bytes());
+        assert!(out.status.success(), "{:?}: {}", args, String::from_utf8_lossy(&out.stderr));
+        let expected = format!("[{{\"file_hash\":\"{}\",\"value\":\"{}\"}}]", name, GHP_TOKEN);
+        assert_eq!(std::fs::read_to_string(dir.path().join("result.json")).unwrap(), expected, "{:?}", args);
+    }
+
+    // --stdin 与 --input 互斥
+    let out = keyhunter_with_stdin(&dir, &[&base[..], &["--stdin", "--input", "."]].concat(), content.as_bytes());
+    assert_eq!(out.status.code(), Some(1));
+}
@@ -0,0 +1,71 @@
+//! 结果写出路径基准：逐条构建 `serde_json::json!` 值 vs 直接序列化 `OutputItem`
+//!
+//! 运行：`cargo bench -p keyhunter-core --bench write_path [-- <命中条数>]`（默认 200 万条）。
+//! 只测量序列化与写出（写入 `io::sink`），不含扫描本身。
+use std::hint::black_box;
+use std::io::{self, BufWriter, Write};
+use std::time::{Duration, Instant};
+
+use keyhunter_core::OutputItem;
+
+/// 预先生成的命中（file_hash, value），模拟大量命中的扫描结果
+fn findings(n: usize) -> Vec<(String, String)> {
+    (0..n).map(|i| (format!("src/module_{}/config.env", i % 997), format!("ghp_{:036}", i))).collect()
+}
+
+fn item<'a>(file_hash: &'a str, value: &'a str) -> OutputItem<'a> {
+    OutputItem {
+        file_hash,
+        value,
+        rule_id: None,
+        rule_name: None,
+        ruleset: None,
+        description: None,
+        references: None,
+        validated: None,
+        start_line: None,
+        end_line: None,
+        context: None,
+        confidence: None,
+        group_index: None,
+        source: None,
+        path: None,
+        severity: None,
+    }
+}
+
+/// 以逗号分隔写出 JSON 数组，`write_one` 负责单个元素
+fn write_array(items: &[(String, String)], mut write_one: impl FnMut(&mut dyn Write, &str, &str) -> io::Result<()>) -> Duration {
+    let mut out = BufWriter::new(io::sink());
+    let start = Instant::now();
+    out.write_all(b"[").unwrap();
+    for (i, (file_hash, value)) in items.iter().enumerate() {
+        if i > 0 { out.write_all(b",").unwrap(); }
+        write_one(&mut out, file_hash, value).unwrap();
+    }
+    out.write_all(b"]").unwrap();
+    out.flush().unwrap();
+    start.elapsed()
+}
+
+fn report(name: &str, n: usize, elapsed: Duration) {
+    println!("{:<12} {:>9} items  {:>8.1} ms  {:>6.1} ns/item", name, n, elapsed.as_secs_f64() * 1e3, elapsed.as_nanos() as f64 / n as f64);
+}
+
+fn main() {
+    // cargo bench 会追加 `--bench`，只取第一个数字参数
+    let n = std::env::args().skip(1).find_map(|a| a.parse().ok()).unwrap_or(2_000_000);
+    let items = findings(n);
+
+    let value = write_array(&items, |out, file_hash, value| {
+        let v = serde_json::json!({ "file_hash": file_hash, "value": value });
+        serde_json::to_writer(out, black_box(&v)).map_err(io::Error::from)
+    });
+    let direct = write_array(&items, |out, file_hash, value| {
+        serde_json::to_writer(out, black_box(&item(file_hash, value))).map_err(io::Error::from)
+    });
+
+    report("json! value", n, value);
+    report("OutputItem", n, direct);
+    println!("speedup: {:.2}x", value.as_secs_f64() / direct.as_secs_f64());
+}
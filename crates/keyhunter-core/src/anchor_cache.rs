@@ -0,0 +1,115 @@
+//! 锚点抽取结果的磁盘缓存（见 `ScanOptions::anchor_cache_dir`）
+//!
+//! - 从规则模式中抽取锚点（解析正则语法树、求必含字面量）是构建预筛计划的主要开销，
+//!   结果只取决于模式文本与抽取实现，因此可跨进程复用；编译后的正则与 AC 自动机不缓存；
+//! - 缓存目录下只有一个 JSON 文件，记录键（keyhunter 版本与全部待抽取模式文本的 SHA-256）及各模式的锚点；
+//! - 键不一致（规则或版本变化）、文件损坏或条目数不符时视为未命中，重新抽取后覆盖；
+//! - 缓存只用于加速：读写失败仅记录日志，不影响扫描结果。
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use tracing::debug;
+
+/// 缓存文件名
+const CACHE_FILE: &str = "prefilter-anchors.json";
+
+#[derive(Serialize, Deserialize)]
+struct AnchorCacheFile {
+    key: String,
+    /// 与待抽取模式一一对应的锚点列表
+    anchors: Vec<Vec<Vec<u8>>>,
+}
+
+/// 逐个模式抽取锚点；给定缓存目录时先查缓存，未命中时抽取并写回
+pub(crate) fn extract_all(patterns: &[&str], dir: Option<&Path>, extract: impl Fn(&str) -> Vec<Vec<u8>>) -> Vec<Vec<Vec<u8>>> {
+    let Some(dir) = dir else { return patterns.iter().map(|p| extract(p)).collect() };
+    let key = cache_key(patterns);
+    if let Some(anchors) = load(dir, &key, patterns.len()) {
+        debug!(dir = %dir.display(), patterns = patterns.len(), "prefilter anchors loaded from cache");
+        return anchors;
+    }
+    let anchors: Vec<Vec<Vec<u8>>> = patterns.iter().map(|p| extract(p)).collect();
+    match store(dir, &key, &anchors) {
+        Ok(()) => debug!(dir = %dir.display(), patterns = patterns.len(), "prefilter anchors extracted and cached"),
+        Err(e) => debug!(dir = %dir.display(), error = %e, "failed to write prefilter anchor cache"),
+    }
+    anchors
+}
+
+/// 缓存键：keyhunter 版本与各模式文本（带长度前缀，避免拼接歧义）的 SHA-256
+fn cache_key(patterns: &[&str]) -> String {
+    let mut h = Sha256::new();
+    h.update(env!("CARGO_PKG_VERSION").as_bytes());
+    for p in patterns {
+        h.update((p.len() as u64).to_le_bytes());
+        h.update(p.as_bytes());
+    }
+    h.finalize().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn load(dir: &Path, key: &str, n: usize) -> Option<Vec<Vec<Vec<u8>>>> {
+    let txt = std::fs::read(dir.join(CACHE_FILE)).ok()?;
+    let file: AnchorCacheFile = match serde_json::from_slice(&txt) {
+        Ok(f) => f,
+        Err(e) => {
+            debug!(dir = %dir.display(), error = %e, "prefilter anchor cache unreadable, rebuilding");
+            return None;
+        }
+    };
+    if file.key != key || file.anchors.len() != n {
+        debug!(dir = %dir.display(), "prefilter anchor cache is stale, rebuilding");
+        return None;
+    }
+    Some(file.anchors)
+}
+
+/// 先写临时文件再重命名，并发运行的进程不会读到写了一半的缓存
+fn store(dir: &Path, key: &str, anchors: &[Vec<Vec<u8>>]) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let body = serde_json::to_vec(&AnchorCacheFile { key: key.to_string(), anchors: anchors.to_vec() })?;
+    let tmp = dir.join(format!("{}.{}.tmp", CACHE_FILE, std::process::id()));
+    std::fs::write(&tmp, body)?;
+    std::fs::rename(&tmp, dir.join(CACHE_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// 记录调用次数的锚点抽取：每个模式的锚点为其前 4 字节
+    fn counting(calls: &AtomicUsize) -> impl Fn(&str) -> Vec<Vec<u8>> + '_ {
+        move |p: &str| {
+            calls.fetch_add(1, Ordering::Relaxed);
+            vec![p.as_bytes()[..4].to_vec()]
+        }
+    }
+
+    #[test]
+    fn second_run_loads_anchors_from_the_cache() {
+        let dir = std::env::temp_dir().join(format!("keyhunter-anchor-cache-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let calls = AtomicUsize::new(0);
+        let patterns = ["ghp_[0-9a-zA-Z]{36}", "AKIA[0-9A-Z]{16}"];
+
+        let first = extract_all(&patterns, Some(&dir), counting(&calls));
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+        assert!(dir.join(CACHE_FILE).is_file());
+        // 模式不变：直接读取缓存，结果相同
+        assert_eq!(extract_all(&patterns, Some(&dir), counting(&calls)), first);
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+
+        // 模式变化：重新抽取并覆盖
+        let changed = ["ghp_[0-9a-zA-Z]{36}", "ASIA[0-9A-Z]{16}"];
+        assert_eq!(extract_all(&changed, Some(&dir), counting(&calls)), [b"ghp_".to_vec(), b"ASIA".to_vec()].map(|a| vec![a]));
+        assert_eq!(calls.load(Ordering::Relaxed), 4);
+        extract_all(&changed, Some(&dir), counting(&calls));
+        assert_eq!(calls.load(Ordering::Relaxed), 4);
+
+        // 缓存损坏：重新抽取
+        std::fs::write(dir.join(CACHE_FILE), "not json").unwrap();
+        assert_eq!(extract_all(&changed, Some(&dir), counting(&calls)).len(), 2);
+        assert_eq!(calls.load(Ordering::Relaxed), 6);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,347 @@
+//! 压缩包扫描（`ScanOptions::scan_archives`）：不解压到磁盘，在内存中逐个还原条目内容后扫描
+//!
+//! 支持的格式（按扩展名识别，大小写不敏感）：
+//! - `.zip`：stored / deflate 条目；加密、ZIP64 与其它压缩方法的条目记为跳过
+//! - `.tar`：普通文件条目（含 GNU 长文件名与 pax `path`）
+//! - `.tar.gz` / `.tgz`：gzip 解压后按 tar 处理
+//! - `.gz`（其余 gzip 文件，如压缩的日志）：解压后作为单个条目扫描，条目名为去掉 `.gz` 的文件名
+//!
+//! 条目的 file_hash 为 `<压缩包 file_hash>!<条目路径>`（如 `dataset.zip!secrets.env`），偏移与行号相对条目内容；
+//! 不递归进入嵌套的压缩包。条目按解压后的大小受 `max_file_size` 约束（未设置时为 `ARCHIVE_ENTRY_MAX`），
+//! 超过的条目记为跳过；`.tar.gz` 的整个 tar 流另受 `TAR_GZ_EXPANDED_MAX` 约束。
+//! 看似二进制的条目与普通文件一样按 `binary_mode` 处理。
+use anyhow::{bail, Context, Result};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use crate::findings::FileScan;
+use crate::inflate::{inflate, LimitExceeded};
+use crate::options::ScanOptions;
+use crate::types::SkipReason;
+
+/// 未设置 `max_file_size` 时单个条目解压后的大小上限（字节），防止解压炸弹耗尽内存
+pub(crate) const ARCHIVE_ENTRY_MAX: u64 = 256 * 1024 * 1024;
+
+/// `.tar.gz` 解压后整个 tar 流的大小上限（字节）：tar 流整体解压到内存后再逐条目读取，超过时整个压缩包记为跳过
+pub(crate) const TAR_GZ_EXPANDED_MAX: u64 = 1024 * 1024 * 1024;
+
+/// tar 的 GNU 长文件名（'L'）与 pax 扩展头（'x'）数据的大小上限（字节）：只从中取路径，超过时视为损坏的压缩包
+const TAR_META_MAX: u64 = 1024 * 1024;
+
+/// 压缩包格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+    Gz,
+}
+
+impl ArchiveKind {
+    /// 按文件名识别压缩包格式
+    pub(crate) fn of(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+        if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".gz") {
+            Some(Self::Gz)
+        } else {
+            None
+        }
+    }
+}
+
+/// 扫描压缩包内的全部条目，合并为压缩包文件的扫描结果（输出时命中按条目的 file_hash 排序，见 `sort_findings_stable`）
+/// - `scan_entry(内容, 逻辑路径, file_hash)`：扫描单个条目（逻辑路径为压缩包路径下的条目路径，供路径作用域规则匹配）
+/// - 结果的 `file_size` 为各条目解压后的大小之和；无法读取或过大的条目记入 `skipped_entries`
+pub(crate) fn scan_archive(
+    path: &Path,
+    kind: ArchiveKind,
+    file_hash: &str,
+    opts: &ScanOptions,
+    scan_entry: impl Fn(&[u8], &Path, &str) -> Result<FileScan>,
+) -> Result<FileScan> {
+    let limit = opts.max_file_size.unwrap_or(ARCHIVE_ENTRY_MAX);
+    let mut merged = FileScan::default();
+    let mut on_entry = |name: &str, content: Result<Vec<u8>>| {
+        let entry_hash = format!("{}!{}", file_hash, name);
+        match content.and_then(|buf| {
+            merged.file_size += buf.len() as u64;
+            scan_entry(&buf, &path.join(name), &entry_hash)
+        }) {
+            Ok(scan) => merged.absorb(scan),
+            Err(e) => {
+                let reason = if e.is::<LimitExceeded>() { SkipReason::TooLarge } else { SkipReason::Error(format!("{:#}", e)) };
+                merged.skipped_entries.push((PathBuf::from(format!("{}!{}", path.display(), name)), reason));
+            }
+        }
+    };
+    match kind {
+        ArchiveKind::Zip => read_zip(path, limit, &mut on_entry)?,
+        ArchiveKind::Tar => read_tar(BufReader::new(File::open(path)?), limit, &mut on_entry)?,
+        ArchiveKind::TarGz => {
+            let mut gz = Vec::new();
+            File::open(path)?.read_to_end(&mut gz)?;
+            let tar = gunzip(&gz, TAR_GZ_EXPANDED_MAX)?;
+            read_tar(tar.as_slice(), limit, &mut on_entry)?;
+        }
+        ArchiveKind::Gz => {
+            let mut gz = Vec::new();
+            File::open(path)?.read_to_end(&mut gz)?;
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            let entry = &name[..name.len() - ".gz".len()];
+            on_entry(entry, gunzip(&gz, limit));
+        }
+    }
+    Ok(merged)
+}
+
+/// 解压 gzip（RFC 1952）的第一个成员
+fn gunzip(data: &[u8], limit: u64) -> Result<Vec<u8>> {
+    if data.len() < 18 || data[0] != 0x1f || data[1] != 0x8b { bail!("not a gzip file"); }
+    if data[2] != 8 { bail!("unsupported gzip compression method {}", data[2]); }
+    let flags = data[3];
+    let mut pos = 10;
+    // FEXTRA
+    if flags & 0x04 != 0 {
+        let xlen = u16::from_le_bytes([*data.get(pos).unwrap_or(&0), *data.get(pos + 1).unwrap_or(&0)]) as usize;
+        pos += 2 + xlen;
+    }
+    // FNAME / FCOMMENT：以 NUL 结尾的字符串
+    for flag in [0x08, 0x10] {
+        if flags & flag != 0 {
+            let Some(end) = data.get(pos..).and_then(|d| memchr::memchr(0, d)) else { bail!("truncated gzip header") };
+            pos += end + 1;
+        }
+    }
+    // FHCRC
+    if flags & 0x02 != 0 { pos += 2; }
+    let Some(body) = data.get(pos..) else { bail!("truncated gzip header") };
+    let (out, _) = inflate(body, usize::try_from(limit).unwrap_or(usize::MAX))?;
+    Ok(out)
+}
+
+/// 依次读取 tar 流中的普通文件条目；超过 `limit` 的条目不读入内存，以 `LimitExceeded` 交给回调
+fn read_tar<R: Read>(mut r: R, limit: u64, on_entry: &mut dyn FnMut(&str, Result<Vec<u8>>)) -> Result<()> {
+    let mut header = [0u8; 512];
+    // GNU 长文件名（'L'）或 pax 扩展头（'x'）给出的下一个条目的路径
+    let mut next_name: Option<String> = None;
+    loop {
+        if !read_block(&mut r, &mut header)? { break; }
+        // 全零块：归档结束
+        if header.iter().all(|&b| b == 0) { break; }
+        let size = parse_octal(&header[124..136]).context("invalid tar entry size")?;
+        let padded = size.div_ceil(512) * 512;
+        let typeflag = header[156];
+        let name = next_name.take().unwrap_or_else(|| {
+            let name = cstr(&header[..100]);
+            let prefix = if &header[257..262] == b"ustar" { cstr(&header[345..500]) } else { String::new() };
+            if prefix.is_empty() { name } else { format!("{}/{}", prefix, name) }
+        });
+        match typeflag {
+            b'L' | b'x' => {
+                if size > TAR_META_MAX { bail!("tar extended header of {} bytes exceeds {} bytes", size, TAR_META_MAX); }
+                let mut data = vec![0u8; usize::try_from(padded).context("tar header too large")?];
+                r.read_exact(&mut data)?;
+                data.truncate(size as usize);
+                next_name = if typeflag == b'L' { Some(cstr(&data)) } else { pax_path(&data) };
+            }
+            b'0' | 0 if size > limit => {
+                skip_bytes(&mut r, padded)?;
+                on_entry(&name, Err(LimitExceeded.into()));
+            }
+            b'0' | 0 => {
+                let mut data = vec![0u8; padded as usize];
+                r.read_exact(&mut data)?;
+                data.truncate(size as usize);
+                on_entry(&name, Ok(data));
+            }
+            // 目录、链接等：跳过其数据
+            _ => skip_bytes(&mut r, padded)?,
+        }
+    }
+    Ok(())
+}
+
+/// 读满一个 512 字节块；流在块边界处结束时返回 false
+fn read_block<R: Read>(r: &mut R, buf: &mut [u8; 512]) -> Result<bool> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        let n = r.read(&mut buf[filled..])?;
+        if n == 0 {
+            if filled == 0 { return Ok(false); }
+            bail!("truncated tar header");
+        }
+        filled += n;
+    }
+    Ok(true)
+}
+
+fn skip_bytes<R: Read>(r: &mut R, n: u64) -> Result<()> {
+    let copied = std::io::copy(&mut r.take(n), &mut std::io::sink())?;
+    if copied != n { bail!("truncated tar entry"); }
+    Ok(())
+}
+
+/// tar 头中的八进制数字段（允许前后空格与 NUL）
+fn parse_octal(field: &[u8]) -> Result<u64> {
+    let s = std::str::from_utf8(field)?.trim_matches(|c: char| c == '\0' || c == ' ');
+    if s.is_empty() { return Ok(0); }
+    Ok(u64::from_str_radix(s, 8)?)
+}
+
+/// 以 NUL 结尾的字段
+fn cstr(field: &[u8]) -> String {
+    let end = memchr::memchr(0, field).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+/// pax 扩展头中的 `path` 记录（每条记录形如 `<长度> path=<值>\n`）
+fn pax_path(data: &[u8]) -> Option<String> {
+    String::from_utf8_lossy(data)
+        .lines()
+        .find_map(|rec| rec.split_once(' ')?.1.strip_prefix("path=").map(str::to_string))
+}
+
+/// zip 中央目录记录
+struct ZipEntry {
+    name: String,
+    method: u16,
+    flags: u16,
+    compressed: u64,
+    uncompressed: u64,
+    local_offset: u64,
+}
+
+/// 依次读取 zip 中的文件条目（按中央目录顺序，目录条目跳过）
+fn read_zip(path: &Path, limit: u64, on_entry: &mut dyn FnMut(&str, Result<Vec<u8>>)) -> Result<()> {
+    let mut f = File::open(path)?;
+    let len = f.metadata()?.len();
+    let entries = zip_central_directory(&mut f, len)?;
+    for e in entries {
+        if e.name.ends_with('/') { continue; }
+        let content = read_zip_entry(&mut f, len, &e, limit);
+        on_entry(&e.name, content);
+    }
+    Ok(())
+}
+
+/// 定位中央目录结束记录并解析全部中央目录记录；`len` 为压缩包文件大小
+fn zip_central_directory(f: &mut File, len: u64) -> Result<Vec<ZipEntry>> {
+    const EOCD_SIG: &[u8] = b"PK\x05\x06";
+    // 结束记录 22 字节 + 至多 65535 字节注释
+    let tail_len = len.min(22 + 65535);
+    let mut tail = vec![0u8; tail_len as usize];
+    f.seek(SeekFrom::Start(len - tail_len))?;
+    f.read_exact(&mut tail)?;
+    let Some(eocd) = memchr::memmem::rfind(&tail, EOCD_SIG) else { bail!("not a zip file: end of central directory not found") };
+    let eocd = &tail[eocd..];
+    if eocd.len() < 22 { bail!("truncated zip end of central directory"); }
+    let count = le16(&eocd[10..]) as usize;
+    let cd_size = le32(&eocd[12..]) as u64;
+    let cd_offset = le32(&eocd[16..]) as u64;
+    if cd_offset == 0xFFFF_FFFF || count == 0xFFFF { bail!("ZIP64 archives are not supported"); }
+    // 中央目录须完整位于文件内：按记录中的大小分配缓冲区之前先校验，损坏或恶意构造的记录不会触发巨额分配
+    if cd_offset + cd_size > len { bail!("zip central directory exceeds the file size"); }
+    let mut cd = vec![0u8; cd_size as usize];
+    f.seek(SeekFrom::Start(cd_offset))?;
+    f.read_exact(&mut cd).context("read zip central directory")?;
+    let mut entries = Vec::with_capacity(count);
+    let mut pos = 0;
+    for _ in 0..count {
+        let Some(rec) = cd.get(pos..pos + 46) else { bail!("truncated zip central directory") };
+        if &rec[..4] != b"PK\x01\x02" { bail!("invalid zip central directory record"); }
+        let name_len = le16(&rec[28..]) as usize;
+        let extra_len = le16(&rec[30..]) as usize;
+        let comment_len = le16(&rec[32..]) as usize;
+        let Some(name) = cd.get(pos + 46..pos + 46 + name_len) else { bail!("truncated zip central directory") };
+        entries.push(ZipEntry {
+            name: String::from_utf8_lossy(name).into_owned(),
+            method: le16(&rec[10..]),
+            flags: le16(&rec[8..]),
+            compressed: le32(&rec[20..]) as u64,
+            uncompressed: le32(&rec[24..]) as u64,
+            local_offset: le32(&rec[42..]) as u64,
+        });
+        pos += 46 + name_len + extra_len + comment_len;
+    }
+    Ok(entries)
+}
+
+/// 读取并解压单个 zip 条目；`len` 为压缩包文件大小
+fn read_zip_entry(f: &mut File, len: u64, e: &ZipEntry, limit: u64) -> Result<Vec<u8>> {
+    if e.flags & 1 != 0 { bail!("encrypted zip entry"); }
+    if e.compressed == 0xFFFF_FFFF || e.uncompressed == 0xFFFF_FFFF { bail!("ZIP64 entries are not supported"); }
+    if e.uncompressed > limit || (e.method == 0 && e.compressed > limit) { bail!(LimitExceeded); }
+    let mut local = [0u8; 30];
+    f.seek(SeekFrom::Start(e.local_offset))?;
+    f.read_exact(&mut local)?;
+    if &local[..4] != b"PK\x03\x04" { bail!("invalid zip local header"); }
+    let data_offset = e.local_offset + 30 + le16(&local[26..]) as u64 + le16(&local[28..]) as u64;
+    if data_offset + e.compressed > len { bail!("zip entry data exceeds the file size"); }
+    let mut data = vec![0u8; e.compressed as usize];
+    f.seek(SeekFrom::Start(data_offset))?;
+    f.read_exact(&mut data)?;
+    match e.method {
+        0 => Ok(data),
+        8 => Ok(inflate(&data, limit as usize)?.0),
+        m => bail!("unsupported zip compression method {}", m),
+    }
+}
+
+fn le16(b: &[u8]) -> u16 {
+    u16::from_le_bytes([b[0], b[1]])
+}
+
+fn le32(b: &[u8]) -> u32 {
+    u32::from_le_bytes([b[0], b[1], b[2], b[3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 512 字节的 tar 头（仅填写名称、大小与类型，校验和不参与解析）
+    fn tar_header(name: &str, size: u64, typeflag: u8) -> [u8; 512] {
+        let mut h = [0u8; 512];
+        h[..name.len()].copy_from_slice(name.as_bytes());
+        h[124..135].copy_from_slice(format!("{:011o}", size).as_bytes());
+        h[156] = typeflag;
+        h
+    }
+
+    fn entries(tar: &[u8]) -> Result<Vec<(String, Vec<u8>)>> {
+        let mut out = Vec::new();
+        read_tar(tar, 1024, &mut |name, content| out.push((name.to_string(), content.unwrap())))?;
+        Ok(out)
+    }
+
+    #[test]
+    fn tar_long_name_applies_to_next_entry() {
+        let long = "d/".repeat(80) + "secret.env";
+        let mut tar = tar_header("././@LongLink", long.len() as u64, b'L').to_vec();
+        let mut name = long.clone().into_bytes();
+        name.resize(long.len().div_ceil(512) * 512, 0);
+        tar.extend_from_slice(&name);
+        tar.extend_from_slice(&tar_header("truncated", 3, b'0'));
+        let mut data = b"abc".to_vec();
+        data.resize(512, 0);
+        tar.extend_from_slice(&data);
+        assert_eq!(entries(&tar).unwrap(), vec![(long, b"abc".to_vec())]);
+    }
+
+    #[test]
+    fn tar_oversized_extended_header_is_rejected() {
+        // 11 位八进制的最大值约 8 GiB：应直接报错，而不是按该大小分配缓冲区
+        for typeflag in [b'L', b'x'] {
+            let tar = tar_header("././@LongLink", 0o77_777_777_777, typeflag);
+            let err = entries(&tar).unwrap_err();
+            assert!(err.to_string().contains("extended header"), "{:#}", err);
+        }
+    }
+}
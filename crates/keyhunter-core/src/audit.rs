@@ -0,0 +1,87 @@
+//! 规则集冗余审计（rules audit 子命令）
+//!
+//! 复用预筛计划中的 锚点 -> 规则 映射：锚点集合完全相同的规则归为一个“锚点族”，
+//! 族内两两比较模式文本的相似度（字符二元组的 Dice 系数，0~1），
+//! 不低于阈值的规则对视为疑似冗余（同一窗口内必然一起运行，且往往产出看似重复的命中）。
+//! 无锚点规则、路径作用域规则与非正则规则不参与审计。
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::prefilter::PrefilterPlan;
+use crate::rulecheck::qualified_id;
+
+/// 疑似冗余的规则对（按规则加载顺序，`first` 在前）
+#[derive(Debug, Clone, Serialize)]
+pub struct RedundantRulePair {
+    /// 规则 id（配置了命名规则集时为 `集合名/id`）
+    pub first: String,
+    pub second: String,
+    /// 两条规则共享的锚点
+    pub anchors: Vec<String>,
+    /// 模式文本相似度（0~1，保留三位小数）
+    pub similarity: f32,
+}
+
+/// 规则集审计报告
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditReport {
+    /// 锚点族数量（锚点集合完全相同且至少包含两条规则）
+    pub anchor_families: usize,
+    /// 相似度不低于阈值的规则对
+    pub redundant_pairs: Vec<RedundantRulePair>,
+}
+
+/// 审计预筛计划中的规则：报告锚点族数量与相似度不低于 `min_similarity` 的规则对
+pub fn audit_rules(plan: &PrefilterPlan, min_similarity: f32) -> AuditReport {
+    // 规则 -> 锚点索引（按锚点顺序，天然有序）
+    let mut rule_anchors: Vec<Vec<usize>> = vec![Vec::new(); plan.rules.len()];
+    for (aid, rules) in plan.anchor_to_rules.iter().enumerate() {
+        for &ri in rules.iter() { rule_anchors[ri].push(aid); }
+    }
+    // 锚点集合 -> 规则索引（BTreeMap 保证输出顺序稳定）
+    let mut families: BTreeMap<&[usize], Vec<usize>> = BTreeMap::new();
+    for (ri, anchors) in rule_anchors.iter().enumerate() {
+        if anchors.is_empty() || plan.rules[ri].pattern().is_none() { continue; }
+        families.entry(anchors.as_slice()).or_default().push(ri);
+    }
+
+    let qualified = |ri: usize| qualified_id(&plan.rules[ri]);
+    let mut anchor_families = 0;
+    let mut pairs: Vec<(usize, usize, RedundantRulePair)> = Vec::new();
+    for (anchors, rules) in families.iter().filter(|(_, rules)| rules.len() > 1) {
+        anchor_families += 1;
+        let anchor_text: Vec<String> = anchors.iter().map(|&aid| String::from_utf8_lossy(&plan.anchors[aid]).to_string()).collect();
+        let grams: HashMap<usize, HashMap<(char, char), usize>> =
+            rules.iter().map(|&ri| (ri, bigrams(plan.rules[ri].pattern().unwrap_or_default()))).collect();
+        for (i, &a) in rules.iter().enumerate() {
+            for &b in &rules[i + 1..] {
+                let similarity = dice(&grams[&a], &grams[&b]);
+                if similarity < min_similarity { continue; }
+                pairs.push((a, b, RedundantRulePair {
+                    first: qualified(a),
+                    second: qualified(b),
+                    anchors: anchor_text.clone(),
+                    similarity: (similarity * 1000.0).round() / 1000.0,
+                }));
+            }
+        }
+    }
+    pairs.sort_by_key(|(a, b, _)| (*a, *b));
+    AuditReport { anchor_families, redundant_pairs: pairs.into_iter().map(|(_, _, p)| p).collect() }
+}
+
+/// 字符二元组的多重集合
+fn bigrams(s: &str) -> HashMap<(char, char), usize> {
+    let chars: Vec<char> = s.chars().collect();
+    let mut out = HashMap::new();
+    for w in chars.windows(2) { *out.entry((w[0], w[1])).or_insert(0) += 1; }
+    out
+}
+
+/// Dice 系数：2 × |交集| / (|A| + |B|)（多重集合；两者皆空时视为完全相同）
+fn dice(a: &HashMap<(char, char), usize>, b: &HashMap<(char, char), usize>) -> f32 {
+    let total: usize = a.values().sum::<usize>() + b.values().sum::<usize>();
+    if total == 0 { return 1.0; }
+    let common: usize = a.iter().map(|(g, n)| (*n).min(b.get(g).copied().unwrap_or(0))).sum();
+    (2 * common) as f32 / total as f32
+}
@@ -0,0 +1,93 @@
+//! base64 内嵌内容解码重扫（`decode_base64`）
+//!
+//! JSON、env 文件与 Kubernetes Secret 中的密钥常以 base64 编码保存，原文上的正则不会命中。
+//! 本模块查找较长的 base64 串（`[A-Za-z0-9+/]` 加最多两个 `=`，不短于 `MIN_RUN_LEN`），解码后对解码内容再运行一遍预筛扫描：
+//! - 解码内容中的命中 file_hash 记为 `<file_hash>!b64`，偏移记为 base64 串在原文件中的起始偏移；
+//! - 长度不合法（去掉填充后余 1 个字符、填充位置 / 个数不对）或末尾多余位非零的串视为不能干净解码，跳过；
+//! - 解码膨胀防护：单个串超过 `MAX_RUN_LEN` 时跳过，单个缓冲区累计解码超过 `MAX_DECODED_TOTAL` 后不再解码；
+//! - 只解码一层（解码内容中的 base64 不再递归解码），默认关闭。
+use crate::engine_bytes::scan_buffer_with_prefilter;
+use crate::findings::{FindingPublic as Finding, FindingSource};
+use crate::prefilter::PrefilterPlan;
+
+/// 参与解码的 base64 串最小长度（字符）
+const MIN_RUN_LEN: usize = 40;
+/// 单个 base64 串的最大长度（字符），更长的串跳过
+const MAX_RUN_LEN: usize = 64 * 1024;
+/// 单个缓冲区累计解码字节数上限
+const MAX_DECODED_TOTAL: usize = 16 * 1024 * 1024;
+
+/// 在缓冲区中查找 base64 串，解码后执行预筛扫描
+/// - `base_offset`：缓冲区在原文件中的起始偏移
+/// - 不去重，由调用方统一去重
+pub(crate) fn scan_base64_blobs(buf: &[u8], base_offset: usize, file_hash: &str, plan: &PrefilterPlan) -> Vec<Finding> {
+    let mut findings: Vec<Finding> = Vec::new();
+    let decoded_hash = format!("{}!b64", file_hash);
+    let mut decoded_total = 0usize;
+
+    for (start, run) in base64_runs(buf) {
+        if run.len() > MAX_RUN_LEN { continue; }
+        let Some(decoded) = decode(run) else { continue };
+        decoded_total += decoded.len();
+        if decoded_total > MAX_DECODED_TOTAL {
+            tracing::debug!(file_hash, limit = MAX_DECODED_TOTAL, "base64 decode limit reached, remaining runs are not decoded");
+            break;
+        }
+        for mut f in scan_buffer_with_prefilter(&decoded, 0, &decoded_hash, plan) {
+            f.start_offset = base_offset + start;
+            f.source = FindingSource::Base64;
+            findings.push(f);
+        }
+    }
+
+    findings
+}
+
+/// 长度不低于 `MIN_RUN_LEN` 的 base64 串：(起始偏移, 串内容，含末尾填充)
+fn base64_runs(buf: &[u8]) -> impl Iterator<Item = (usize, &[u8])> {
+    let mut i = 0usize;
+    std::iter::from_fn(move || {
+        while i < buf.len() {
+            if !is_base64_char(buf[i]) { i += 1; continue; }
+            let start = i;
+            while i < buf.len() && is_base64_char(buf[i]) { i += 1; }
+            let mut end = i;
+            while end < buf.len() && end - i < 2 && buf[end] == b'=' { end += 1; }
+            i = end;
+            if end - start >= MIN_RUN_LEN { return Some((start, &buf[start..end])); }
+        }
+        None
+    })
+}
+
+fn is_base64_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'+' || b == b'/'
+}
+
+/// 严格解码：带填充时总长须为 4 的倍数，末尾多余位须为 0；不能干净解码时返回 None
+fn decode(run: &[u8]) -> Option<Vec<u8>> {
+    fn val(b: u8) -> u32 {
+        match b {
+            b'A'..=b'Z' => (b - b'A') as u32,
+            b'a'..=b'z' => (b - b'a') as u32 + 26,
+            b'0'..=b'9' => (b - b'0') as u32 + 52,
+            b'+' => 62,
+            _ => 63,
+        }
+    }
+    let padding = run.iter().rev().take_while(|&&b| b == b'=').count();
+    let chars = &run[..run.len() - padding];
+    let rem = chars.len() % 4;
+    if rem == 1 || (padding > 0 && !run.len().is_multiple_of(4)) { return None; }
+    let mut out = Vec::with_capacity(chars.len() / 4 * 3 + 2);
+    for group in chars.chunks(4) {
+        let mut acc = 0u32;
+        for &c in group { acc = (acc << 6) | val(c); }
+        // 不完整的末组：多余的低位须为 0（否则不是某段字节的规范编码）
+        let spare = match group.len() { 2 => 4, 3 => 2, _ => 0 };
+        if acc & ((1 << spare) - 1) != 0 { return None; }
+        acc <<= 6 * (4 - group.len() as u32);
+        out.extend_from_slice(&acc.to_be_bytes()[1..group.len()]);
+    }
+    Some(out)
+}
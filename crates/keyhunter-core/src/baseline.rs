@@ -0,0 +1,82 @@
+//! 基线文件：已知 / 已接受的命中（测试夹具、已轮换的密钥），扫描时不再重复报告
+//!
+//! 文件为 JSON 数组，每项为 `{"file_hash": ..., "value": ...}` 或 `{"file_hash": ..., "sha256": ...}`
+//! （`sha256` 为命中值的 SHA-256 小写十六进制）；`write_baseline` 生成的基线只写 sha256，不在基线中保存密钥原文。
+//! - 按 `(file_hash, 命中值)` 匹配，file_hash 随 `hash_mode` 而变，生成与使用基线时应使用相同的 `hash_mode`；
+//! - 匹配基于原始命中值（在指纹替换与脱敏之前），与 `fingerprint` / `redact` 可同时使用。
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use crate::metadata::hex_sha256;
+
+/// 基线中的一项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BaselineEntry {
+    file_hash: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    value: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sha256: Option<String>,
+}
+
+/// 已接受的命中集合：file_hash -> 命中值的 SHA-256
+#[derive(Debug, Clone, Default)]
+pub struct Baseline {
+    entries: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl Baseline {
+    /// 读取基线文件；每项须有 `value` 与 `sha256` 之一
+    pub fn load(path: &Path) -> Result<Self> {
+        let txt = std::fs::read_to_string(path).with_context(|| format!("read baseline {}", path.display()))?;
+        let items: Vec<BaselineEntry> =
+            serde_json::from_str(&txt).with_context(|| format!("parse baseline {}", path.display()))?;
+        let mut baseline = Self::default();
+        for (i, e) in items.into_iter().enumerate() {
+            let digest = match (e.value, e.sha256) {
+                (Some(v), None) => hex_sha256(v.as_bytes()),
+                (None, Some(h)) if h.len() == 64 && h.bytes().all(|b| b.is_ascii_hexdigit()) => h.to_ascii_lowercase(),
+                (None, Some(h)) => bail!("baseline {} entry {}: sha256 '{}' is not a hex SHA-256 digest", path.display(), i, h),
+                _ => bail!("baseline {} entry {}: expected exactly one of value / sha256", path.display(), i),
+            };
+            baseline.entries.entry(e.file_hash).or_default().insert(digest);
+        }
+        Ok(baseline)
+    }
+
+    /// 基线中的项数
+    pub fn len(&self) -> usize {
+        self.entries.values().map(BTreeSet::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 命中 `(file_hash, value)` 是否在基线中（value 为原始命中值）
+    pub(crate) fn contains(&self, file_hash: &str, value: &str) -> bool {
+        // 先按文件过滤，只有基线中出现过的文件才计算哈希
+        self.entries.get(file_hash).is_some_and(|set| set.contains(&hex_sha256(value.as_bytes())))
+    }
+
+    /// 加入一条命中（value 为原始命中值）
+    pub(crate) fn insert(&mut self, file_hash: &str, value: &str) {
+        self.entries.entry(file_hash.to_string()).or_default().insert(hex_sha256(value.as_bytes()));
+    }
+
+    /// 写出基线文件（只写 sha256；按 file_hash、sha256 排序，结果与扫描顺序无关）
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let items: Vec<BaselineEntry> = self
+            .entries
+            .iter()
+            .flat_map(|(file_hash, set)| {
+                set.iter().map(|h| BaselineEntry { file_hash: file_hash.clone(), value: None, sha256: Some(h.clone()) })
+            })
+            .collect();
+        let mut txt = serde_json::to_string_pretty(&items)?;
+        txt.push('\n');
+        std::fs::write(path, txt).with_context(|| format!("write baseline {}", path.display()))
+    }
+}
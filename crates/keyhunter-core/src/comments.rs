@@ -0,0 +1,139 @@
+//! 源码注释剥离（`ignore_comments`）
+//!
+//! 按扩展名识别语言，把注释字节等长替换为空格（换行保留）后再扫描，
+//! 偏移与行号均保持不变；抑制标记仍在原文上判定。支持的语言：
+//! - `//` 与 `/* */`：c / h / cc / cpp / cxx / hpp / hh / cs / java / kt / kts / scala / groovy / gradle /
+//!   swift / dart / go / rs / js / mjs / cjs / jsx / ts / tsx / proto / scss / less；
+//! - `#`：py / rb / sh / bash / zsh / pl / pm / r / yaml / yml / toml / conf / cfg / properties / cmake / mk /
+//!   dockerfile，以及文件名 Dockerfile / Makefile / .env / .bashrc / .zshrc / .profile；
+//! - 两者兼有：php / tf / hcl。
+//!
+//! 这是启发式剥离而非完整词法分析：
+//! - 字符串字面量（`"`，多数语言还包括 `'`，js / ts / go 包括跨行的 `` ` ``）内的注释记号不生效，
+//!   单双引号字符串在行尾强制结束，避免未闭合的引号吞掉后续内容；
+//! - `#` 仅在行首或空白之后才视为注释起点（兼容 shell / YAML 的 `a#b`、URL 片段等）；
+//! - 不识别 Python 三引号文档字符串、heredoc、正则字面量与嵌套块注释；
+//! - 分块扫描时，恰好被块边界切开的两字节记号（`//`、`/*`、`*/`）可能漏判。
+use std::path::Path;
+
+use crate::options::ScanOptions;
+
+/// 某种语言的注释语法
+#[derive(Debug, Clone, Copy)]
+struct CommentSyntax {
+    /// `//` 行注释与 `/* */` 块注释
+    slash: bool,
+    /// `#` 行注释
+    hash: bool,
+    /// 字符串字面量的定界符
+    quotes: &'static [u8],
+}
+
+const C_LIKE: CommentSyntax = CommentSyntax { slash: true, hash: false, quotes: b"\"'" };
+const JS_LIKE: CommentSyntax = CommentSyntax { slash: true, hash: false, quotes: b"\"'`" };
+/// Rust：`'` 多用于生命周期，不作为字符串定界符
+const RUST: CommentSyntax = CommentSyntax { slash: true, hash: false, quotes: b"\"" };
+const HASH: CommentSyntax = CommentSyntax { slash: false, hash: true, quotes: b"\"'" };
+const SLASH_AND_HASH: CommentSyntax = CommentSyntax { slash: true, hash: true, quotes: b"\"'" };
+
+fn syntax_for(path: &Path) -> Option<CommentSyntax> {
+    let name = path.file_name()?.to_str()?;
+    match name {
+        "Dockerfile" | "Makefile" | ".env" | ".bashrc" | ".zshrc" | ".profile" => return Some(HASH),
+        _ => {}
+    }
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    let syntax = match ext.as_str() {
+        "c" | "h" | "cc" | "cpp" | "cxx" | "hpp" | "hh" | "cs" | "java" | "kt" | "kts" | "scala" | "groovy"
+        | "gradle" | "swift" | "dart" | "proto" | "scss" | "less" => C_LIKE,
+        "go" | "js" | "mjs" | "cjs" | "jsx" | "ts" | "tsx" => JS_LIKE,
+        "rs" => RUST,
+        "py" | "rb" | "sh" | "bash" | "zsh" | "pl" | "pm" | "r" | "yaml" | "yml" | "toml" | "conf" | "cfg"
+        | "properties" | "cmake" | "mk" | "dockerfile" => HASH,
+        "php" | "tf" | "hcl" => SLASH_AND_HASH,
+        _ => return None,
+    };
+    Some(syntax)
+}
+
+/// 开启 `ignore_comments` 且扩展名可识别时返回该文件的剥离器
+pub(crate) fn comment_stripper(path: &Path, opts: &ScanOptions) -> Option<CommentStripper> {
+    if !opts.ignore_comments { return None; }
+    syntax_for(path).map(CommentStripper::new)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Code,
+    LineComment,
+    BlockComment,
+    /// 字符串字面量内（定界符）
+    Str(u8),
+    /// 字符串内反斜杠之后的一个字节
+    Escape(u8),
+}
+
+/// 流式注释剥离器：状态跨 `strip` 调用延续，分块扫描时按读取顺序对每段新字节各调用一次
+pub(crate) struct CommentStripper {
+    syntax: CommentSyntax,
+    state: State,
+    /// 上一个已处理的字节（文件开头视为行首）
+    prev: u8,
+}
+
+impl CommentStripper {
+    fn new(syntax: CommentSyntax) -> Self {
+        Self { syntax, state: State::Code, prev: b'\n' }
+    }
+
+    /// 原地把 `buf` 中的注释字节替换为空格（保留换行）
+    pub(crate) fn strip(&mut self, buf: &mut [u8]) {
+        let mut i = 0usize;
+        while i < buf.len() {
+            let b = buf[i];
+            let next = buf.get(i + 1).copied();
+            match self.state {
+                State::Code => {
+                    if self.syntax.slash && b == b'/' && matches!(next, Some(b'/') | Some(b'*')) {
+                        self.state = if next == Some(b'/') { State::LineComment } else { State::BlockComment };
+                        buf[i] = b' ';
+                        buf[i + 1] = b' ';
+                        self.prev = b' ';
+                        i += 2;
+                        continue;
+                    }
+                    if self.syntax.hash && b == b'#' && self.prev.is_ascii_whitespace() {
+                        self.state = State::LineComment;
+                        buf[i] = b' ';
+                    } else if self.syntax.quotes.contains(&b) {
+                        self.state = State::Str(b);
+                    }
+                }
+                State::LineComment => {
+                    if b == b'\n' { self.state = State::Code; } else { buf[i] = b' '; }
+                }
+                State::BlockComment => {
+                    if b == b'*' && next == Some(b'/') {
+                        self.state = State::Code;
+                        buf[i] = b' ';
+                        buf[i + 1] = b' ';
+                        self.prev = b' ';
+                        i += 2;
+                        continue;
+                    }
+                    if b != b'\n' { buf[i] = b' '; }
+                }
+                State::Str(q) => {
+                    if b == b'\\' {
+                        self.state = State::Escape(q);
+                    } else if b == q || (b == b'\n' && q != b'`') {
+                        self.state = State::Code;
+                    }
+                }
+                State::Escape(q) => self.state = State::Str(q),
+            }
+            self.prev = buf[i];
+            i += 1;
+        }
+    }
+}
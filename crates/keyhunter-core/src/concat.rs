@@ -0,0 +1,103 @@
+//! 字符串常量拼接重建（启发式）
+//!
+//! 源码中常见通过拼接构造密钥以规避连续匹配，例如：
+//! `token = "ghp_" + "abcd…"`、Python 的隐式拼接 `"sk-" "abc"`、参数列表 `f("sec", "ret")`。
+//! 本模块将“仅由空白 / `+` / `,` 分隔的相邻引号字面量”合并为一个逻辑字符串后再运行预筛扫描，
+//! 命中的偏移统一记为第一个字面量内容的起始偏移。
+//! 说明：
+//! - 字面量不跨行（遇到未转义换行即视为非字面量）；转义序列按原样保留，不做反转义；
+//! - 仅当至少两个字面量相邻时才产生逻辑字符串；
+//! - 语言无关的启发式，默认关闭（`--join-string-literals`）。
+use crate::engine_bytes::scan_buffer_with_prefilter;
+use crate::findings::FindingPublic as Finding;
+use crate::prefilter::PrefilterPlan;
+
+/// 单个逻辑字符串的最大长度（防止病态输入拼出超长缓冲）
+const MAX_JOINED_LEN: usize = 8 * 1024;
+/// 单个字面量的最大长度
+const MAX_LITERAL_LEN: usize = 4 * 1024;
+
+/// 在缓冲区中重建相邻字面量拼接，并对重建结果执行预筛扫描
+/// - `base_offset`：缓冲区在原文件中的起始偏移
+/// - 不去重，由调用方统一去重
+pub(crate) fn scan_joined_literals(buf: &[u8], base_offset: usize, file_hash: &str, plan: &PrefilterPlan) -> Vec<Finding> {
+    let mut findings: Vec<Finding> = Vec::new();
+
+    for (first_start, joined) in joined_literal_runs(buf) {
+        for mut f in scan_buffer_with_prefilter(&joined, 0, file_hash, plan) {
+            f.start_offset = base_offset + first_start;
+            findings.push(f);
+        }
+    }
+
+    findings
+}
+
+/// 提取相邻字面量序列：返回 (首个字面量内容起始偏移, 拼接后的内容)
+fn joined_literal_runs(buf: &[u8]) -> Vec<(usize, Vec<u8>)> {
+    let mut runs: Vec<(usize, Vec<u8>)> = Vec::new();
+    // 当前序列：(首个内容起点, 拼接内容, 字面量个数)
+    let mut cur: Option<(usize, Vec<u8>, usize)> = None;
+    let mut i = 0usize;
+
+    while i < buf.len() {
+        if !is_quote(buf[i]) { i += 1; continue; }
+        let (cs, ce, next) = match parse_literal(buf, i) {
+            Some(v) => v,
+            None => {
+                flush_run(&mut cur, &mut runs);
+                i += 1;
+                continue;
+            }
+        };
+        match cur.as_mut() {
+            Some(c) if c.1.len() + (ce - cs) <= MAX_JOINED_LEN => {
+                c.1.extend_from_slice(&buf[cs..ce]);
+                c.2 += 1;
+            }
+            _ => {
+                flush_run(&mut cur, &mut runs);
+                cur = Some((cs, buf[cs..ce].to_vec(), 1));
+            }
+        }
+
+        // 跳过分隔符（空白 / + / ,），若紧接着是下一个字面量则继续拼接
+        let mut j = next;
+        while j < buf.len() && (buf[j].is_ascii_whitespace() || buf[j] == b'+' || buf[j] == b',') { j += 1; }
+        if j < buf.len() && is_quote(buf[j]) {
+            i = j;
+        } else {
+            flush_run(&mut cur, &mut runs);
+            i = next;
+        }
+    }
+    flush_run(&mut cur, &mut runs);
+
+    runs
+}
+
+/// 结束当前序列：至少包含两个字面量时才保留
+fn flush_run(cur: &mut Option<(usize, Vec<u8>, usize)>, runs: &mut Vec<(usize, Vec<u8>)>) {
+    if let Some((start, joined, count)) = cur.take() {
+        if count >= 2 { runs.push((start, joined)); }
+    }
+}
+
+fn is_quote(b: u8) -> bool { b == b'"' || b == b'\'' }
+
+/// 解析从 `open` 处开始的引号字面量，返回 (内容起点, 内容终点, 闭合引号之后的位置)
+/// - 支持反斜杠转义；遇到未转义换行或超长则判定失败
+fn parse_literal(buf: &[u8], open: usize) -> Option<(usize, usize, usize)> {
+    let q = buf[open];
+    let start = open + 1;
+    let mut i = start;
+    while i < buf.len() {
+        let b = buf[i];
+        if b == b'\\' { i += 2; continue; }
+        if b == b'\n' || b == b'\r' { return None; }
+        if b == q { return Some((start, i, i + 1)); }
+        if i - start > MAX_LITERAL_LEN { return None; }
+        i += 1;
+    }
+    None
+}
@@ -0,0 +1,118 @@
+//! 无 PEM 头的 base64 DER 私钥检测（`kind = "der"` 规则）
+//!
+//! 部分工具直接输出 base64 编码的 DER 私钥，不带 `-----BEGIN ...-----` 标记，锚点预筛无法覆盖。
+//! 该检测器查找连续的 base64 块（`[A-Za-z0-9+/]` 加最多两个 `=`），长度不低于 min_length 时
+//! 解码块首部做轻量 DER 结构校验后报告整个块：
+//! - 外层为 SEQUENCE（0x30），其长度字段与整块解码长度一致；
+//! - 首个元素为单字节 INTEGER 版本号 0 或 1（PKCS#1 / PKCS#8 / SEC1 私钥均满足）。
+//!
+//! 说明：
+//! - 噪声高于锚点规则，需在规则文件中显式声明 `kind = "der"` 才启用；
+//! - 块内不允许换行（按行折叠的 base64 不会被识别）；
+//! - 分块扫描时跨越块边界的 base64 块会因截断无法通过校验（块大小 4 MiB，实际影响很小）。
+use std::path::Path;
+
+use crate::findings::{FindingPublic as Finding, FindingSource};
+use crate::rules::RuleSpec;
+
+/// 默认最小 base64 块长度（字符数，约 750 字节 DER，覆盖 RSA 私钥）
+pub(crate) const DEFAULT_DER_MIN_LENGTH: usize = 1000;
+
+/// der 规则参数
+#[derive(Debug, Clone)]
+pub(crate) struct DerParams {
+    pub min_length: usize,
+}
+
+/// 在缓冲区中查找适用于该文件的 der 规则命中（非 der 规则被忽略）
+/// - `base_offset`：缓冲区在原文件中的起始偏移
+/// - 不去重：同一 value 的每次出现都返回，由调用方在记录原始命中位置后统一去重
+pub(crate) fn scan_der_blocks(buf: &[u8], base_offset: usize, file_hash: &str, path: &Path, rules: &[RuleSpec]) -> Vec<Finding> {
+    let der_rules: Vec<(&RuleSpec, &DerParams)> = rules
+        .iter()
+        .filter(|r| r.applies_to(path))
+        .filter_map(|r| r.der.as_ref().map(|p| (r, p)))
+        .collect();
+    let min_length = match der_rules.iter().map(|(_, p)| p.min_length).min() { Some(n) => n, None => return Vec::new() };
+
+    let mut findings: Vec<Finding> = Vec::new();
+    let mut i = 0usize;
+    while i < buf.len() {
+        if !is_base64_char(buf[i]) { i += 1; continue; }
+        let start = i;
+        while i < buf.len() && is_base64_char(buf[i]) { i += 1; }
+        let mut end = i;
+        while end < buf.len() && end - i < 2 && buf[end] == b'=' { end += 1; }
+        i = end;
+
+        let block = &buf[start..end];
+        if block.len() < min_length || !looks_like_der_private_key(block) { continue; }
+        if let Some((spec, _)) = der_rules.iter().find(|(_, p)| block.len() >= p.min_length) {
+            findings.push(Finding {
+                file_hash: file_hash.to_string(),
+                value: String::from_utf8_lossy(block).to_string(),
+                start_offset: base_offset + start,
+                rule_id: spec.id.clone(),
+                validated: None,
+                lines: None,
+                context: None,
+                confidence: None,
+                group_index: None,
+                source: FindingSource::default(),
+                path: None,
+            });
+        }
+    }
+
+    findings
+}
+
+fn is_base64_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'+' || b == b'/'
+}
+
+/// 轻量 DER 校验：仅解码块首部（外层 SEQUENCE 头 + 版本号），并用 base64 长度推算整块解码长度
+fn looks_like_der_private_key(block: &[u8]) -> bool {
+    let padding = block.iter().rev().take_while(|&&b| b == b'=').count();
+    let chars = block.len() - padding;
+    // 无填充时余 1 个字符不是合法 base64
+    if chars % 4 == 1 { return false; }
+    let decoded_len = chars / 4 * 3 + (chars % 4).saturating_sub(1);
+
+    let head = match decode_base64_prefix(&block[..chars.min(12)]) { Some(h) => h, None => return false };
+    if head.len() < 5 || head[0] != 0x30 { return false; }
+    // 外层 SEQUENCE 长度（短格式或 1~3 字节长格式）
+    let (len, hdr) = match head[1] {
+        n if n < 0x80 => (n as usize, 2),
+        0x81 => (head[2] as usize, 3),
+        0x82 => (((head[2] as usize) << 8) | head[3] as usize, 4),
+        0x83 => (((head[2] as usize) << 16) | ((head[3] as usize) << 8) | head[4] as usize, 5),
+        _ => return false,
+    };
+    if hdr + len != decoded_len || head.len() < hdr + 3 { return false; }
+    // 版本号：INTEGER，长度 1，值 0 或 1
+    head[hdr] == 0x02 && head[hdr + 1] == 0x01 && head[hdr + 2] <= 1
+}
+
+/// 解码 base64 前缀（长度需为 4 的倍数或为完整块尾部），非法字符返回 None
+fn decode_base64_prefix(chars: &[u8]) -> Option<Vec<u8>> {
+    fn val(b: u8) -> Option<u32> {
+        match b {
+            b'A'..=b'Z' => Some((b - b'A') as u32),
+            b'a'..=b'z' => Some((b - b'a') as u32 + 26),
+            b'0'..=b'9' => Some((b - b'0') as u32 + 52),
+            b'+' => Some(62),
+            b'/' => Some(63),
+            _ => None,
+        }
+    }
+    let mut out = Vec::with_capacity(chars.len() / 4 * 3 + 2);
+    for group in chars.chunks(4) {
+        let mut acc = 0u32;
+        for &c in group { acc = (acc << 6) | val(c)?; }
+        acc <<= 6 * (4 - group.len() as u32);
+        let bytes = acc.to_be_bytes();
+        out.extend_from_slice(&bytes[1..group.len()]);
+    }
+    Some(out)
+}
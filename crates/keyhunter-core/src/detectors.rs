@@ -1,77 +1,47 @@
-//! 检测器集合（Bytes/Utf8）
+//! 检测器集合（UTF-8 引擎）
 use anyhow::Result;
-use crate::rules::RuleSpec;
+use crate::rules::{has_mixed_priorities, RuleSpec};
 
-/// 字节级检测器集合（demo：少量高置信规则）
-pub(crate) struct DetectorSetBytes {
-    pub(crate) patterns: Vec<regex::bytes::Regex>,
-}
-
-/// UTF-8 检测器集合（与上面规则等价，便于切换引擎）
+/// UTF-8 检测器集合
 pub(crate) struct DetectorSetUtf8 {
     pub(crate) patterns: Vec<regex::Regex>,
-}
-
-impl DetectorSetBytes {
-    /// 从规则条目构建字节级检测器集合
-    pub(crate) fn from_specs(specs: &[RuleSpec]) -> Result<Self> {
-        let mut patterns = Vec::new();
-        for r in specs {
-            if let Some(pat) = r.pattern() {
-                // 对于 bytes 引擎，直接编译为 bytes::Regex
-                if let Ok(rx) = regex::bytes::Regex::new(pat) {
-                    patterns.push(rx);
-                }
-            }
-        }
-        Ok(Self { patterns })
-    }
-
-    /// 旧的内置规则（演示用），现已由文件驱动，保留注释以供参考
-    #[allow(dead_code)]
-    pub(crate) fn default_rules_demo_only() -> Self {
-        // let patterns = vec![
-        //     regex::bytes::Regex::new(r"sk-[A-Za-z0-9]{20,}").unwrap(),
-        //     regex::bytes::Regex::new(r"gh[oprsu]_[A-Za-z0-9_]{36,}").unwrap(),
-        //     regex::bytes::Regex::new(r"xox[baprs]-[A-Za-z0-9-]{10,}").unwrap(),
-        //     regex::bytes::Regex::new(r"sk_(?:live|test)_[A-Za-z0-9]{20,}").unwrap(),
-        //     regex::bytes::Regex::new(r"(?:A3T|AKIA|ASIA)[A-Z0-9]{16}").unwrap(),
-        // ];
-        // Self { patterns }
-        Self { patterns: Vec::new() }
-    }
+    /// 与 patterns 一一对应的规则规格（id 与匹配选项）
+    pub(crate) specs: Vec<RuleSpec>,
+    /// 非正则规则（env / der / url，由专用检测器处理）
+    pub(crate) non_regex_rules: Vec<RuleSpec>,
+    /// 规则间声明了不同的优先级：正则规则按优先级降序排列并认领命中区间（见 `findings::SpanClaims`）
+    pub(crate) prioritized: bool,
 }
 
 impl DetectorSetUtf8 {
     /// 从规则条目构建 UTF-8 检测器集合
-    pub(crate) fn from_specs(specs: &[RuleSpec]) -> Result<Self> {
+    /// - `size_limit`：单条正则编译大小上限（见 `ScanOptions::regex_size_limit`），超出的规则告警后跳过
+    pub(crate) fn from_specs(specs: &[RuleSpec], size_limit: Option<usize>) -> Result<Self> {
         let mut patterns = Vec::new();
-        for r in specs {
+        let mut kept = Vec::new();
+        // 按优先级降序（稳定排序，同优先级保持加载顺序）
+        let mut ordered: Vec<&RuleSpec> = specs.iter().collect();
+        ordered.sort_by_key(|r| std::cmp::Reverse(r.priority));
+        for r in ordered {
             if let Some(pat) = r.pattern() {
-                if let Ok(rx) = regex::Regex::new(pat) {
-                    patterns.push(rx);
+                match regex::RegexBuilder::new(pat).size_limit(size_limit.unwrap_or(usize::MAX)).build() {
+                    Ok(rx) => {
+                        patterns.push(rx);
+                        kept.push(r.clone());
+                    }
+                    Err(regex::Error::CompiledTooBig(limit)) => {
+                        tracing::warn!(rule = %r.id, limit, "rule pattern exceeds the compiled size limit, rule disabled");
+                    }
+                    Err(e) => {
+                        tracing::warn!(rule = %r.id, error = %e, "rule pattern failed to compile for the UTF-8 engine, rule disabled");
+                    }
                 }
             }
         }
-        Ok(Self { patterns })
-    }
-
-    /// 旧的内置规则（演示用），现已由文件驱动，保留注释以供参考
-    #[allow(dead_code)]
-    pub(crate) fn default_rules_demo_only() -> Self {
-        // let patterns = vec![
-        //     regex::Regex::new(r"sk-[A-Za-z0-9]{20,}").unwrap(),
-        //     regex::Regex::new(r"gh[oprsu]_[A-Za-z0-9_]{36,}").unwrap(),
-        //     regex::Regex::new(r"xox[baprs]-[A-Za-z0-9-]{10,}").unwrap(),
-        //     regex::Regex::new(r"sk_(?:live|test)_[A-Za-z0-9]{20,}").unwrap(),
-        //     regex::Regex::new(r"(?:A3T|AKIA|ASIA)[A-Z0-9]{16}").unwrap(),
-        // ];
-        // Self { patterns }
-        Self { patterns: Vec::new() }
+        let non_regex_rules: Vec<RuleSpec> = specs.iter().filter(|r| !r.is_regex()).cloned().collect();
+        if patterns.is_empty() && non_regex_rules.is_empty() {
+            tracing::warn!(rules = specs.len(), "no active rules: no pattern compiled, scan will report no findings");
+        }
+        Ok(Self { patterns, specs: kept, non_regex_rules, prioritized: has_mixed_priorities(specs) })
     }
 }
-
-// 供其他模块使用的类型别名（可直引）
-pub(crate) use DetectorSetBytes as DetectorSetBytesPublic;
-pub(crate) use DetectorSetUtf8 as DetectorSetUtf8Public;
-
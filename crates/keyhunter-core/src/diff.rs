@@ -0,0 +1,56 @@
+//! 两次扫描结果的差异（diff 子命令）
+//!
+//! 结果文件（result.json，JSON 数组；或 json-v1 信封，取其中的 findings）逐项读取为 `OwnedOutputItem`
+//! （结果中没有的 rule_id / 偏移取空值），
+//! 以 `(file_hash, value)` 为键比较两次结果（`with_rule_id` 时键中加上 rule_id）：
+//! 只出现在新结果中的为新增，只出现在旧结果中的为消失；同一键在一份结果中出现多次时保留第一项。
+//! 输出按键排序，与结果文件中的顺序无关。
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::types::OwnedOutputItem;
+
+/// 两次结果的差异
+#[derive(Debug, Clone, Serialize)]
+pub struct ResultDiff {
+    /// 只出现在新结果中的命中
+    pub added: Vec<OwnedOutputItem>,
+    /// 只出现在旧结果中的命中
+    pub removed: Vec<OwnedOutputItem>,
+}
+
+/// 结果文件的两种形式：命中数组或 json-v1 信封
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ResultFile {
+    Array(Vec<OwnedOutputItem>),
+    Envelope { findings: Vec<OwnedOutputItem> },
+}
+
+/// 读取结果文件（JSON 数组或 json-v1 信封）
+pub fn load_results(path: &Path) -> Result<Vec<OwnedOutputItem>> {
+    let txt = std::fs::read_to_string(path).with_context(|| format!("read {}", path.display()))?;
+    let file: ResultFile = serde_json::from_str(&txt).with_context(|| format!("parse {} as a result array", path.display()))?;
+    Ok(match file {
+        ResultFile::Array(items) | ResultFile::Envelope { findings: items } => items,
+    })
+}
+
+/// 比较两次结果，见模块文档
+pub fn diff_results(old: &[OwnedOutputItem], new: &[OwnedOutputItem], with_rule_id: bool) -> ResultDiff {
+    let index = |items: &[OwnedOutputItem]| {
+        let mut m: BTreeMap<(String, String, String), OwnedOutputItem> = BTreeMap::new();
+        for it in items {
+            let rule = if with_rule_id { it.rule_id.clone() } else { String::new() };
+            m.entry((it.file_hash.clone(), it.value.clone(), rule)).or_insert_with(|| it.clone());
+        }
+        m
+    };
+    let (old, new) = (index(old), index(new));
+    let only = |a: &BTreeMap<_, OwnedOutputItem>, b: &BTreeMap<_, OwnedOutputItem>| {
+        a.iter().filter(|(k, _)| !b.contains_key(*k)).map(|(_, v)| v.clone()).collect()
+    };
+    ResultDiff { added: only(&new, &old), removed: only(&old, &new) }
+}
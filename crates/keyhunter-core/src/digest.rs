@@ -0,0 +1,241 @@
+//! 文件内容摘要（`HashMode::Sha256` / `HashMode::Md5`）：以文件内容的十六进制摘要作为 file_hash
+//!
+//! - 摘要覆盖整个文件（不受 `head_bytes` / `tail_bytes` 影响），内容相同的文件得到相同的 file_hash；
+//! - 扫描读取整个文件时在同一次读取中计算摘要（`HashingReader`）；只读取了文件一部分（局部扫描、
+//!   二进制判定后提前结束、压缩包）时，另行流式读取一遍计算（`file_digest`）；
+//! - 压缩包按容器文件本身计算摘要，条目的 file_hash 为 `<摘要>!<条目路径>`；
+//! - MD5 只用于与既有清单比对，不作安全用途；依赖中没有 MD5 实现，此处按 RFC 1321 实现。
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use crate::options::HashMode;
+
+/// 流式读取文件时的缓冲区大小
+const READ_BUF: usize = 64 * 1024;
+
+/// 增量摘要计算器（记录已输入的字节数，供调用方确认摘要是否覆盖了整个文件）
+pub(crate) struct ContentHasher {
+    state: HasherState,
+    len: u64,
+}
+
+enum HasherState {
+    Sha256(Sha256),
+    Md5(Md5),
+}
+
+impl ContentHasher {
+    /// 按 `hash_mode` 创建；非内容摘要模式（文件名 / 相对路径）时为 None
+    pub(crate) fn new(mode: HashMode) -> Option<Self> {
+        let state = match mode {
+            HashMode::Name | HashMode::Relpath => return None,
+            HashMode::Sha256 => HasherState::Sha256(Sha256::new()),
+            HashMode::Md5 => HasherState::Md5(Md5::new()),
+        };
+        Some(Self { state, len: 0 })
+    }
+
+    /// 丢弃已输入的内容，从头开始（同一文件被重新读取时）
+    pub(crate) fn reset(&mut self) {
+        self.state = match self.state {
+            HasherState::Sha256(_) => HasherState::Sha256(Sha256::new()),
+            HasherState::Md5(_) => HasherState::Md5(Md5::new()),
+        };
+        self.len = 0;
+    }
+
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        self.len += data.len() as u64;
+        match &mut self.state {
+            HasherState::Sha256(h) => h.update(data),
+            HasherState::Md5(h) => h.update(data),
+        }
+    }
+
+    /// 已输入的字节数
+    pub(crate) fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// 结束计算，返回小写十六进制摘要
+    pub(crate) fn finish(self) -> String {
+        let digest: Vec<u8> = match self.state {
+            HasherState::Sha256(h) => h.finalize().to_vec(),
+            HasherState::Md5(h) => h.finalize().to_vec(),
+        };
+        digest.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+/// 读取时顺带计算摘要的读取器（扫描读取文件时使用，避免为摘要单独再读一遍）
+pub(crate) struct HashingReader<'h, R> {
+    inner: R,
+    hasher: Option<&'h mut ContentHasher>,
+}
+
+impl<'h, R> HashingReader<'h, R> {
+    /// `hasher` 为 None 时仅透传；否则先清空其中已有的内容
+    pub(crate) fn new(inner: R, mut hasher: Option<&'h mut ContentHasher>) -> Self {
+        if let Some(h) = hasher.as_deref_mut() { h.reset(); }
+        Self { inner, hasher }
+    }
+}
+
+impl<R: Read> Read for HashingReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if let Some(h) = self.hasher.as_deref_mut() { h.update(&buf[..n]); }
+        Ok(n)
+    }
+}
+
+/// 流式计算文件内容摘要；非内容摘要模式时为 None
+pub(crate) fn file_digest(path: &Path, mode: HashMode) -> std::io::Result<Option<String>> {
+    let Some(mut hasher) = ContentHasher::new(mode) else { return Ok(None) };
+    let mut file = File::open(path)?;
+    let mut buf = vec![0u8; READ_BUF];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 { break; }
+        hasher.update(&buf[..n]);
+    }
+    Ok(Some(hasher.finish()))
+}
+
+/// 内存内容的摘要；非内容摘要模式时为 None
+pub(crate) fn bytes_digest(content: &[u8], mode: HashMode) -> Option<String> {
+    let mut hasher = ContentHasher::new(mode)?;
+    hasher.update(content);
+    Some(hasher.finish())
+}
+
+/// MD5（RFC 1321）增量计算
+pub(crate) struct Md5 {
+    state: [u32; 4],
+    /// 未满 64 字节的待处理数据
+    pending: Vec<u8>,
+    /// 已输入的总字节数
+    len: u64,
+}
+
+/// 每轮的循环左移位数
+const MD5_SHIFTS: [u32; 64] = [
+    7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22,
+    5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20,
+    4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23,
+    6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+];
+
+/// 每轮的加法常数 K[i] = floor(|sin(i + 1)| * 2^32)
+const MD5_K: [u32; 64] = [
+    0xd76a_a478, 0xe8c7_b756, 0x2420_70db, 0xc1bd_ceee,
+    0xf57c_0faf, 0x4787_c62a, 0xa830_4613, 0xfd46_9501,
+    0x6980_98d8, 0x8b44_f7af, 0xffff_5bb1, 0x895c_d7be,
+    0x6b90_1122, 0xfd98_7193, 0xa679_438e, 0x49b4_0821,
+    0xf61e_2562, 0xc040_b340, 0x265e_5a51, 0xe9b6_c7aa,
+    0xd62f_105d, 0x0244_1453, 0xd8a1_e681, 0xe7d3_fbc8,
+    0x21e1_cde6, 0xc337_07d6, 0xf4d5_0d87, 0x455a_14ed,
+    0xa9e3_e905, 0xfcef_a3f8, 0x676f_02d9, 0x8d2a_4c8a,
+    0xfffa_3942, 0x8771_f681, 0x6d9d_6122, 0xfde5_380c,
+    0xa4be_ea44, 0x4bde_cfa9, 0xf6bb_4b60, 0xbebf_bc70,
+    0x289b_7ec6, 0xeaa1_27fa, 0xd4ef_3085, 0x0488_1d05,
+    0xd9d4_d039, 0xe6db_99e5, 0x1fa2_7cf8, 0xc4ac_5665,
+    0xf429_2244, 0x432a_ff97, 0xab94_23a7, 0xfc93_a039,
+    0x655b_59c3, 0x8f0c_cc92, 0xffef_f47d, 0x8584_5dd1,
+    0x6fa8_7e4f, 0xfe2c_e6e0, 0xa301_4314, 0x4e08_11a1,
+    0xf753_7e82, 0xbd3a_f235, 0x2ad7_d2bb, 0xeb86_d391,
+];
+
+impl Md5 {
+    fn new() -> Self {
+        Self { state: [0x6745_2301, 0xefcd_ab89, 0x98ba_dcfe, 0x1032_5476], pending: Vec::with_capacity(64), len: 0 }
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        self.len += data.len() as u64;
+        if !self.pending.is_empty() {
+            let take = (64 - self.pending.len()).min(data.len());
+            self.pending.extend_from_slice(&data[..take]);
+            data = &data[take..];
+            if self.pending.len() < 64 { return; }
+            let block: [u8; 64] = self.pending[..].try_into().expect("64-byte block");
+            self.compress(&block);
+            self.pending.clear();
+        }
+        let mut blocks = data.chunks_exact(64);
+        for block in &mut blocks {
+            self.compress(block.try_into().expect("64-byte block"));
+        }
+        self.pending.extend_from_slice(blocks.remainder());
+    }
+
+    fn finalize(mut self) -> [u8; 16] {
+        let bit_len = self.len.wrapping_mul(8);
+        // 填充：0x80，再补 0 至长度 ≡ 56 (mod 64)，最后是 64 位小端的比特长度
+        let mut tail = vec![0x80u8];
+        tail.resize((119 - (self.len % 64) as usize) % 64 + 1, 0);
+        tail.extend_from_slice(&bit_len.to_le_bytes());
+        let len = self.len;
+        self.update(&tail);
+        self.len = len;
+        let mut out = [0u8; 16];
+        for (i, word) in self.state.iter().enumerate() {
+            out[i * 4..i * 4 + 4].copy_from_slice(&word.to_le_bytes());
+        }
+        out
+    }
+
+    fn compress(&mut self, block: &[u8; 64]) {
+        let mut m = [0u32; 16];
+        for (w, bytes) in m.iter_mut().zip(block.chunks_exact(4)) {
+            *w = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        let [mut a, mut b, mut c, mut d] = self.state;
+        for (i, &shift) in MD5_SHIFTS.iter().enumerate() {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let rotated = a.wrapping_add(f).wrapping_add(MD5_K[i]).wrapping_add(m[g]).rotate_left(shift);
+            (a, d, c) = (d, c, b);
+            b = b.wrapping_add(rotated);
+        }
+        for (s, v) in self.state.iter_mut().zip([a, b, c, d]) {
+            *s = s.wrapping_add(v);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn md5_matches_rfc1321_vectors() {
+        let md5 = |s: &str| bytes_digest(s.as_bytes(), HashMode::Md5).unwrap();
+        assert_eq!(md5(""), "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(md5("abc"), "900150983cd24fb0d6963f7d28e17f72");
+        assert_eq!(md5("message digest"), "f96b697d7cb7938d525a2f31aaf161d0");
+        assert_eq!(
+            md5("12345678901234567890123456789012345678901234567890123456789012345678901234567890"),
+            "57edf4a22be3c955ac49da2e2107b67a"
+        );
+    }
+
+    #[test]
+    fn hashing_reader_matches_bytes_digest() {
+        let content: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        for mode in [HashMode::Sha256, HashMode::Md5] {
+            let mut hasher = ContentHasher::new(mode).unwrap();
+            hasher.update(b"stale");
+            let mut reader = HashingReader::new(&content[..], Some(&mut hasher));
+            std::io::copy(&mut reader, &mut std::io::sink()).unwrap();
+            assert_eq!(hasher.len(), content.len() as u64);
+            assert_eq!(hasher.finish(), bytes_digest(&content, mode).unwrap());
+        }
+    }
+}
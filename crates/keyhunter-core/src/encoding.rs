@@ -0,0 +1,146 @@
+//! 文件编码探测（Auto 引擎按文件选择扫描引擎）
+//!
+//! 只看文件开头的抽样（默认 8 KiB）：
+//! - 以 UTF-8 BOM 开头 -> Utf8Bom；以 UTF-16 BOM 开头 -> Utf16；
+//! - 含 NUL 字节 -> Other（二进制或 UTF-16/32 无 BOM；无 BOM 的 UTF-16 由字节引擎按 `detect_utf16` 识别并转码）；
+//! - 全为 ASCII -> Ascii（两种引擎语义一致）；
+//! - 合法 UTF-8（允许末尾被抽样截断的不完整序列）且含非 ASCII 字符 -> Utf8；
+//! - 其余 -> Other。
+//!
+//! 抽样之后出现的非法编码无法预知，由调用方在 UTF-8 引擎失败时回退处理。
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// 抽样长度（字节）
+pub(crate) const SAMPLE_LEN: usize = 8192;
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+const UTF16LE_BOM: &[u8] = b"\xFF\xFE";
+const UTF16BE_BOM: &[u8] = b"\xFE\xFF";
+/// 无 BOM 时判定为 UTF-16 所需的 ASCII 码元占比
+const UTF16_ASCII_RATIO: f32 = 0.75;
+
+/// 抽样判定的文件编码
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Encoding {
+    Utf8Bom,
+    Utf16,
+    Utf8,
+    Ascii,
+    Other,
+}
+
+pub(crate) fn detect_encoding(sample: &[u8]) -> Encoding {
+    if sample.starts_with(UTF8_BOM) { return Encoding::Utf8Bom; }
+    if sample.starts_with(UTF16LE_BOM) || sample.starts_with(UTF16BE_BOM) { return Encoding::Utf16; }
+    if memchr::memchr(0, sample).is_some() { return Encoding::Other; }
+    if sample.is_ascii() { return Encoding::Ascii; }
+    match std::str::from_utf8(sample) {
+        Ok(_) => Encoding::Utf8,
+        // 不完整的尾部序列：抽样恰好切断了多字节字符
+        Err(e) if e.error_len().is_none() => Encoding::Utf8,
+        Err(_) => Encoding::Other,
+    }
+}
+
+/// 抽样是否有把握为 UTF-8 文本且 Unicode 语义可能影响结果（纯 ASCII 时两种引擎等价，不算）
+pub(crate) fn prefers_utf8_engine(sample: &[u8]) -> bool {
+    matches!(detect_encoding(sample), Encoding::Utf8Bom | Encoding::Utf8)
+}
+
+/// 读取文件开头至多 `SAMPLE_LEN` 字节
+pub(crate) fn read_sample(path: &Path) -> std::io::Result<Vec<u8>> {
+    let mut sample = Vec::with_capacity(SAMPLE_LEN);
+    File::open(path)?.take(SAMPLE_LEN as u64).read_to_end(&mut sample)?;
+    Ok(sample)
+}
+
+/// UTF-16 字节序
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum Utf16Order {
+    Le,
+    Be,
+}
+
+impl Utf16Order {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Utf16Order::Le => "utf-16le",
+            Utf16Order::Be => "utf-16be",
+        }
+    }
+
+    fn unit(self, pair: &[u8]) -> u16 {
+        match self {
+            Utf16Order::Le => u16::from_le_bytes([pair[0], pair[1]]),
+            Utf16Order::Be => u16::from_be_bytes([pair[0], pair[1]]),
+        }
+    }
+}
+
+/// 抽样是否为 UTF-16 文本，返回字节序：
+/// - 以 UTF-16 BOM 开头；
+/// - 或无 BOM，但按某一字节序解读时至少 75% 的码元为可打印 ASCII（含 tab/CR/LF，即“隔字节为 NUL”）。
+///
+/// 以非拉丁文字为主且无 BOM 的 UTF-16 不满足后者，仍按二进制判定处理。
+pub(crate) fn detect_utf16(sample: &[u8]) -> Option<Utf16Order> {
+    if sample.starts_with(UTF16LE_BOM) { return Some(Utf16Order::Le); }
+    if sample.starts_with(UTF16BE_BOM) { return Some(Utf16Order::Be); }
+    let units = sample.len() / 2;
+    if units < 2 { return None; }
+    let is_text = |b: u8| matches!(b, 0x09 | 0x0A | 0x0D) || (0x20..=0x7E).contains(&b);
+    let (mut le, mut be) = (0, 0);
+    for pair in sample.chunks_exact(2) {
+        if pair[1] == 0 && is_text(pair[0]) { le += 1; }
+        if pair[0] == 0 && is_text(pair[1]) { be += 1; }
+    }
+    let min = (units as f32 * UTF16_ASCII_RATIO).ceil() as usize;
+    if le >= min {
+        Some(Utf16Order::Le)
+    } else if be >= min {
+        Some(Utf16Order::Be)
+    } else {
+        None
+    }
+}
+
+/// UTF-16 内容转码为 UTF-8 的结果
+pub(crate) struct Transcoded {
+    pub(crate) text: Vec<u8>,
+    /// 转码后每个字节对应字符在原始内容中的起始偏移
+    origin: Vec<usize>,
+    source_len: usize,
+}
+
+impl Transcoded {
+    /// 转码后内容中的偏移还原为原始内容中的字节偏移（内容末尾对应原始内容长度）
+    pub(crate) fn original_offset(&self, offset: usize) -> usize {
+        self.origin.get(offset).copied().unwrap_or(self.source_len)
+    }
+}
+
+/// 按字节序把 UTF-16 内容转码为 UTF-8：去掉开头的 BOM，不成对的代理项替换为 U+FFFD，末尾多出的单字节丢弃
+pub(crate) fn transcode_utf16(buf: &[u8], order: Utf16Order) -> Transcoded {
+    let bom = match order {
+        Utf16Order::Le => UTF16LE_BOM,
+        Utf16Order::Be => UTF16BE_BOM,
+    };
+    let mut pos = if buf.starts_with(bom) { bom.len() } else { 0 };
+    let units = buf[pos..].chunks_exact(2).map(|pair| order.unit(pair));
+    let mut text = Vec::with_capacity(buf.len() / 2);
+    let mut origin = Vec::with_capacity(buf.len() / 2);
+    let mut tmp = [0u8; 4];
+    for c in char::decode_utf16(units) {
+        let (c, width) = match c {
+            Ok(c) => (c, c.len_utf16() * 2),
+            Err(_) => (char::REPLACEMENT_CHARACTER, 2),
+        };
+        let encoded = c.encode_utf8(&mut tmp);
+        text.extend_from_slice(encoded.as_bytes());
+        origin.extend(std::iter::repeat_n(pos, encoded.len()));
+        pos += width;
+    }
+    Transcoded { text, origin, source_len: buf.len() }
+}
@@ -1,185 +1,356 @@
 //! 字节级扫描引擎（小文件整读 + 大文件分块）
 use anyhow::Result;
 use std::collections::HashSet;
-use std::fs::File;
 use std::io::{BufReader, Read};
 use std::path::Path;
-
-use crate::detectors::DetectorSetBytes;
-use crate::findings::FindingPublic as Finding;
-use crate::prefilter::{PrefilterPlan, WINDOW_AFTER, WINDOW_BEFORE, get_or_compile_meta_regex};
+use std::time::Instant;
+
+use crate::base64::scan_base64_blobs;
+use crate::concat::scan_joined_literals;
+use crate::engine_strings::scan_binary_strings;
+use crate::block::{max_block_len, scan_marker_blocks};
+use crate::der::scan_der_blocks;
+use crate::url::scan_url_credentials;
+use crate::keyword_entropy::scan_keyword_entropy;
+use crate::env::scan_env_lines;
+use crate::location::{annotate_context_by, annotate_lines, count_lines};
+use crate::suppress::retain_unsuppressed;
+use crate::comments::comment_stripper;
+use crate::digest::ContentHasher;
+use crate::region::{open_region, read_region, Region};
+use crate::encoding::{detect_utf16, transcode_utf16, SAMPLE_LEN};
+use crate::findings::{dedup_keep_earliest, dedup_key, sort_findings_stable, DedupByValue, FileScan, FindingPublic as Finding, FindingSource, RawHits, SpanClaims};
+use crate::options::{BinaryMode, ScanEngine, ScanOptions};
+use crate::rules::RuleSpec;
+use crate::prefilter::{pem_anchor_kind, PrefilterPlan, get_or_compile_meta_regex};
 use regex_automata as ra;
 use ra::Input;
 
 /// 小文件阈值（字节）。小文件整读，超出则分块扫描。
-pub(crate) const SMALL_FILE_MAX: usize = 1 * 1024 * 1024; // 1 MiB
-/// 分块大小与重叠字节数（覆盖常见密钥长度/跨块情况）
+pub(crate) const SMALL_FILE_MAX: usize = 1024 * 1024; // 1 MiB
+/// 分块大小与重叠字节数（覆盖常见密钥长度/跨块情况）；Bytes 引擎的重叠区另按锚点窗口放大，见 `chunk_overlap`
 pub(crate) const CHUNK_SIZE: usize = 4 * 1024 * 1024; // 4 MiB
 pub(crate) const CHUNK_OVERLAP: usize = 512; // 512 bytes
+/// 内存预算下分块大小的下限（预算过小时不再继续缩小，避免块数过多）
+pub(crate) const MIN_CHUNK_SIZE: usize = 64 * 1024; // 64 KiB
+
+/// 分块扫描的实际块大小（Bytes / UTF-8 引擎共用）
+///
+/// 分块路径每个并行 worker 只持有一个复用缓冲区（块 + 重叠区），峰值内存约为：
+/// `workers × (chunk_size + CHUNK_OVERLAP)`，其中 workers 为扫描线程数。
+/// 设置 `memory_budget` 时按 `budget / workers - CHUNK_OVERLAP` 缩小块大小，并限制在
+/// `[MIN_CHUNK_SIZE, CHUNK_SIZE]` 内；未设置时为 CHUNK_SIZE。
+/// 注：小文件（≤ SMALL_FILE_MAX）整读，不受预算约束；命中结果等其他内存也不计入预算；
+/// Bytes 引擎的重叠区按 `chunk_overlap` 放大（默认窗口下约 36 KiB），超出 CHUNK_OVERLAP 的部分也不计入预算。
+pub(crate) fn effective_chunk_size(opts: &ScanOptions) -> usize {
+    let budget = match opts.memory_budget { Some(b) => b, None => return CHUNK_SIZE };
+    let workers = match opts.engine {
+        ScanEngine::Bytes | ScanEngine::Auto => opts.threads.unwrap_or_else(num_cpus::get).max(1),
+        ScanEngine::Utf8 => 1,
+    };
+    (budget / workers).saturating_sub(CHUNK_OVERLAP).clamp(MIN_CHUNK_SIZE, CHUNK_SIZE)
+}
 
-/// 分块扫描大文件（Bytes 引擎）
-pub(crate) fn scan_file_bytes_chunked(path: &Path, file_hash: &str, detectors: &DetectorSetBytes) -> Result<Vec<Finding>> {
-    let file = File::open(path)?;
-    let mut reader = BufReader::new(file);
-    let mut findings: Vec<Finding> = Vec::new();
-    let mut seen: HashSet<String> = HashSet::new();
-
-    let mut buf = vec![0u8; CHUNK_SIZE];
-    let mut carry: Vec<u8> = Vec::new();
-    let mut file_offset: usize = 0; // 当前块在文件中的起始偏移（不含 carry）
-
-    loop {
-        let n = reader.read(&mut buf)?;
-        if n == 0 { break; }
-        let mut chunk: Vec<u8> = Vec::with_capacity(carry.len() + n);
-        if !carry.is_empty() { chunk.extend_from_slice(&carry); }
-        chunk.extend_from_slice(&buf[..n]);
-
-        // 在 chunk 上运行检测器，匹配到的偏移需要映射回文件偏移
-        for re in &detectors.patterns {
-            for caps in re.captures_iter(&chunk) {
-                let (start, end) = match caps.get(1) {
-                    Some(m) => (m.start(), m.end()),
-                    None => caps.get(0).map(|m| (m.start(), m.end())).unwrap_or((0, 0)),
-                };
-                if end <= start { continue; }
-                let raw = &chunk[start..end];
-                let value = String::from_utf8_lossy(raw).to_string();
-                if seen.insert(value.clone()) {
-                    // 计算全局偏移： (file_offset - carry_len) + start
-                    let base = file_offset.saturating_sub(carry.len());
-                    let global_start = base + start;
-                    findings.push(Finding { file_hash: file_hash.to_string(), value, start_offset: global_start });
-                }
-            }
-        }
-
-        // 更新 carry：保留当前 chunk 的末尾重叠区域
-        let keep = CHUNK_OVERLAP.min(carry.len() + n);
-        let total_len = carry.len() + n;
-        if keep > 0 {
-            carry = chunk[total_len - keep..total_len].to_vec();
-        } else {
-            carry.clear();
-        }
-        // 移动文件偏移：增加本次实际推进的字节数（即 n）
-        file_offset = file_offset.saturating_add(n);
-    }
-
-    Ok(findings)
+/// 使用预筛计划进行小文件扫描（字节引擎）
+/// `hasher` 为 Some 时读取的同时计算内容摘要
+pub(crate) fn scan_file_bytes_prefilter(path: &Path, file_hash: &str, plan: &PrefilterPlan, opts: &ScanOptions, hasher: Option<&mut ContentHasher>) -> Result<FileScan> {
+    // 仅读取扫描区域（head_bytes / tail_bytes；未设置时为整个文件）
+    let (buf, region) = read_region(path, opts, hasher)?;
+    Ok(scan_region_bytes(&buf, &region, path, file_hash, plan, opts))
 }
 
-/// 按“字节级”方式扫描单个文件
-/// - 直接读取所有字节并在 `regex::bytes` 上匹配
-/// - 命中值输出时使用 `from_utf8_lossy` 进行有损转换，保证 JSON 可写
-/// - 单文件内基于 value 去重，防止重复输出
-pub(crate) fn scan_file_bytes(path: &Path, file_hash: &str, detectors: &DetectorSetBytes) -> Result<Vec<Finding>> {
-    let file = File::open(path)?;
-    let mut reader = BufReader::new(file);
-    let mut buf = Vec::new();
-    reader.read_to_end(&mut buf)?;
-
-    let mut seen: HashSet<String> = HashSet::new();
-    let mut findings: Vec<Finding> = Vec::new();
+/// 扫描已读入内存的区域内容（`buf` 即 `region` 覆盖的字节），偏移还原为文件内的绝对偏移
+pub(crate) fn scan_region_bytes(buf: &[u8], region: &Region, path: &Path, file_hash: &str, plan: &PrefilterPlan, opts: &ScanOptions) -> FileScan {
+    let mut scan = scan_bytes_prefilter(buf, path, file_hash, plan, opts).shift_offsets(region.start as usize);
+    scan.file_size = region.file_len;
+    scan
+}
 
-    for re in &detectors.patterns {
-        // 使用捕获组：若存在第1个捕获组，则优先作为“真实密钥值”；否则退回整个匹配
-        for caps in re.captures_iter(&buf) {
-            // 选择匹配片段（优先 group(1)）
-            let (start, end) = match caps.get(1) {
-                Some(m) => (m.start(), m.end()),
-                None => caps.get(0).map(|m| (m.start(), m.end())).unwrap_or((0, 0)),
-            };
-            if end <= start { continue; }
-
-            let raw = &buf[start..end];
-            // 将字节转换为字符串（有损），确保可写入 JSON
-            let value = String::from_utf8_lossy(raw).to_string();
-
-            if seen.insert(value.clone()) {
-                findings.push(Finding { file_hash: file_hash.to_string(), value, start_offset: start });
+/// 对整块内存内容执行与小文件相同的扫描（文件整读后、或标准输入内容）
+/// - `path`：逻辑路径，仅用于路径作用域规则的匹配
+pub(crate) fn scan_bytes_prefilter(buf: &[u8], path: &Path, file_hash: &str, plan: &PrefilterPlan, opts: &ScanOptions) -> FileScan {
+    // UTF-16 文本（有 BOM，或开头抽样呈“隔字节为 NUL”模式）不按二进制处理：
+    // 转码为 UTF-8 后按文本扫描，命中偏移还原为原始字节偏移；行号与上下文基于转码后的文本
+    if let Some(order) = detect_utf16(&buf[..buf.len().min(SAMPLE_LEN)]).filter(|_| opts.detect_encoding) {
+        let transcoded = transcode_utf16(buf, order);
+        let mut scan = scan_text_prefilter(&transcoded.text, path, file_hash, plan, opts).map_offsets(|o| transcoded.original_offset(o));
+        scan.utf16 = Some(order);
+        return scan.with_scanned(buf.len());
+    }
+    // 二进制文件快速判定（保守）：
+    // - 若包含 NUL 字节，则视为二进制，直接跳过；
+    // - 或可打印字符占比过低（< `binary_printable_threshold`）也跳过。
+    // - 若启用 strings 模式，则改为提取可打印串后再扫描；raw 模式不判定，按原始字节扫描。
+    if is_probably_binary(buf, opts) {
+        match opts.binary_mode {
+            BinaryMode::Skip => return FileScan { binary_skipped: Some(buf.len() as u64), ..Default::default() },
+            BinaryMode::Strings => {
+                let findings = scan_binary_strings(buf, 0, file_hash, plan);
+                let mut raw = RawHits::default();
+                raw.record(&findings);
+                let mut findings = dedup_keep_earliest(findings, opts.no_dedup, opts.normalize_dedup);
+                if opts.with_location { annotate_lines(buf, 0, 0, &mut findings); }
+                return FileScan::new(findings, raw, opts.hit_positions).capped(opts.max_matches_per_file, false).with_scanned(buf.len());
             }
+            BinaryMode::Raw => {}
         }
     }
-
-    Ok(findings)
+    scan_text_prefilter(buf, path, file_hash, plan, opts).with_scanned(buf.len())
 }
 
-/// 使用预筛计划进行小文件扫描（字节引擎）
-pub(crate) fn scan_file_bytes_prefilter(path: &Path, file_hash: &str, plan: &PrefilterPlan) -> Result<Vec<Finding>> {
-    let file = File::open(path)?;
-    let mut reader = BufReader::new(file);
-    let mut buf = Vec::new();
-    reader.read_to_end(&mut buf)?;
-
-    // 二进制文件快速判定（保守）：
-    // - 若包含 NUL 字节，则视为二进制，直接跳过；
-    // - 或可打印字符占比过低（< 25%）也跳过。
-    if is_probably_binary(&buf) {
-        return Ok(Vec::new());
+/// 按文本扫描整块内容（已排除二进制；UTF-16 内容为转码后的文本）
+fn scan_text_prefilter(buf: &[u8], path: &Path, file_hash: &str, plan: &PrefilterPlan, opts: &ScanOptions) -> FileScan {
+    // 可选：剥离源码注释（等长替换为空格）后扫描；抑制判定与行号仍基于原文
+    let stripped = comment_stripper(path, opts).map(|mut s| {
+        let mut b = buf.to_vec();
+        s.strip(&mut b);
+        b
+    });
+    let text = stripped.as_deref().unwrap_or(buf);
+
+    let mut findings = scan_buffer_with_prefilter(text, 0, file_hash, plan);
+    let cap_reached = plan.match_cap_reached(findings.len());
+    // 路径作用域规则（不经过 AC 预筛）
+    findings.extend(scan_path_scoped(text, 0, file_hash, path, plan));
+    // env 规则（NAME=value 行，不经过 AC 预筛）
+    findings.extend(scan_env_lines(text, 0, file_hash, path, &plan.rules));
+    // der 规则（无 PEM 头的 base64 DER 私钥，不经过 AC 预筛）
+    findings.extend(scan_der_blocks(text, 0, file_hash, path, &plan.rules));
+    // url 规则（URL / 连接串中的 user:password@，不经过 AC 预筛）
+    findings.extend(scan_url_credentials(text, 0, file_hash, path, &plan.rules));
+    // block 规则（起止标记界定的多行块，不经过 AC 预筛）
+    findings.extend(scan_marker_blocks(text, 0, file_hash, path, &plan.rules));
+    // entropy 规则（关键字之后的高熵串，使用规则自身的关键字自动机）
+    findings.extend(scan_keyword_entropy(text, 0, file_hash, path, &plan.rules));
+    // 可选：相邻字符串字面量拼接重建后再扫描一次
+    if opts.join_string_literals {
+        findings.extend(scan_joined_literals(text, 0, file_hash, plan));
+    }
+    // 可选：内嵌 base64 串解码后再扫描一次
+    if opts.decode_base64 {
+        findings.extend(scan_base64_blobs(text, 0, file_hash, plan));
+    }
+    // 可选：行内抑制注释（命中所在行或上一行含标记则丢弃）
+    if opts.inline_suppression {
+        retain_unsuppressed(buf, 0, &mut findings, &opts.suppression_marker);
     }
+    // 去重前记录原始命中位置（计入 candidates_total，及 hit_positions 报告）
+    let mut raw = RawHits::default();
+    raw.record(&findings);
+    // 文件内去重：同一 value 保留偏移最小者
+    let mut findings = dedup_keep_earliest(findings, opts.no_dedup, opts.normalize_dedup);
+    if opts.with_location { annotate_lines(buf, 0, 0, &mut findings); }
+    annotate_context_by(buf, 0, opts, &mut findings);
+    FileScan::new(findings, raw, opts.hit_positions).capped(opts.max_matches_per_file, cap_reached)
+}
 
-    Ok(scan_buffer_with_prefilter(&buf, 0, file_hash, plan))
+/// Bytes 引擎分块扫描的重叠字节数：最大锚点窗口（向前 + 向后）与 block 规则最大块长度中较大者的两倍，且不小于 CHUNK_OVERLAP
+///
+/// 命中 `[s, e)` 必落在某个锚点的窗口内，该窗口不超出 `[s - W, s + W]`（W 为最大窗口）。相邻两块重叠 2W，
+/// 以重叠区中点为界划分命中归属（按起始偏移），归属于某块的命中其锚点窗口必完整落在该块内，
+/// 结果与整读扫描一致；跨块的值也不会在两块中以不同偏移或被截断的形式各报告一次。
+/// block 规则的命中不超出 `[s, s + L]`（L 为最大块长度），同理完整落在其归属的块内。
+pub(crate) fn chunk_overlap(plan: &PrefilterPlan) -> usize {
+    let widest = plan.anchor_windows.iter().map(|(before, after)| before + after).max().unwrap_or(0);
+    (2 * widest.max(max_block_len(&plan.rules))).max(CHUNK_OVERLAP)
 }
 
 /// 使用预筛计划进行大文件分块扫描（字节引擎）
-pub(crate) fn scan_file_bytes_chunked_prefilter(path: &Path, file_hash: &str, plan: &PrefilterPlan) -> Result<Vec<Finding>> {
-    let file = File::open(path)?;
-    let mut reader = BufReader::new(file);
-    let mut findings: Vec<Finding> = Vec::new();
-    let mut seen: HashSet<String> = HashSet::new();
+/// - 相邻块重叠 `chunk_overlap` 字节，以重叠区中点为界：起始偏移在界前的命中归前一块，其余归后一块；
+///   末块之前的各块中归属下一块的命中先暂存，读到下一块时丢弃（由下一块完整重扫），到达末尾时保留；
+/// - 每块扫描后，起始偏移位于下一块重叠区之前的命中已不会再变化（后续块的命中偏移都不小于重叠区起点），
+///   按文件内稳定顺序排序后经 `emit_batch` 提前交给 Writer，避免命中极多的超大文件整体驻留内存；
+/// - 已输出的 value 记录在集合中，后续块再次出现时按文件内去重丢弃（其偏移必然更大）；
+///   `no_dedup` 时不记录：后续块的命中偏移都不小于已输出部分的边界，不会与已输出的出现重复；
+/// - 返回值仅包含最后一块中尚未输出的命中；扫描中途出错时，已输出的批次不会撤回；
+/// - `hasher` 为 Some 时读取的同时计算内容摘要。
+pub(crate) fn scan_file_bytes_chunked_prefilter(
+    path: &Path,
+    file_hash: &str,
+    plan: &PrefilterPlan,
+    opts: &ScanOptions,
+    hasher: Option<&mut ContentHasher>,
+    emit_batch: &mut dyn FnMut(Vec<Finding>) -> Result<()>,
+) -> Result<FileScan> {
+    // 仅读取扫描区域（head_bytes / tail_bytes；未设置时为整个文件），偏移从区域起点开始
+    let (reader, region) = open_region(path, opts, hasher)?;
+    let mut scan = scan_reader_chunked_prefilter(&mut BufReader::new(reader), region.start as usize, path, file_hash, plan, opts, emit_batch)?;
+    // 二进制跳过按区域大小计
+    if scan.binary_skipped.is_some() { scan.binary_skipped = Some(region.len); }
+    scan.file_size = region.file_len;
+    Ok(scan)
+}
 
-    let mut buf = vec![0u8; CHUNK_SIZE];
-    let mut carry: Vec<u8> = Vec::new();
-    let mut file_offset: usize = 0;
+/// 从任意读取器分块扫描（大文件分块路径与 `scan_reader` 共用），规则同 `scan_file_bytes_chunked_prefilter`
+/// - `start`：读取器首字节在逻辑内容中的偏移（命中偏移在此基础上累加）
+/// - `path`：逻辑路径，用于路径作用域规则与注释剥离的匹配
+/// - 判定为二进制并跳过时，`binary_skipped` 为判定前已读取的字节数（读取器总长度未知）
+pub(crate) fn scan_reader_chunked_prefilter(
+    reader: &mut dyn Read,
+    start: usize,
+    path: &Path,
+    file_hash: &str,
+    plan: &PrefilterPlan,
+    opts: &ScanOptions,
+    emit_batch: &mut dyn FnMut(Vec<Finding>) -> Result<()>,
+) -> Result<FileScan> {
+    // 文件内去重（跨块）：同一 value 保留偏移最小者（no_dedup 时仅合并同一出现）；仅保存尚未输出的命中
+    let mut findings = DedupByValue::new(opts.no_dedup, opts.normalize_dedup);
+    // 已提前输出的 value
+    let mut emitted: HashSet<String> = HashSet::new();
+    // 去重前的原始命中位置（重叠区的重复命中在 FileScan::new 中合并）
+    let mut raw = RawHits::default();
+
+    // 单一缓冲区跨迭代复用：[上一块末尾重叠区 | 本次读取的字节]，一次性分配（不再逐块清零或扩缩），
+    // 新数据直接读入重叠区之后，原地扫描已填充的前缀，结束时把末尾重叠区 copy_within 到开头
+    let chunk_size = effective_chunk_size(opts);
+    let overlap = chunk_overlap(plan);
+    // 重叠区中点到块边界的距离：命中归属的分界
+    let half = overlap / 2;
+    let mut buf: Vec<u8> = vec![0u8; chunk_size + overlap];
+    // buf 开头保留的上一块末尾重叠区长度
+    let mut carry_len: usize = 0;
+    // 上一块中归属下一块的命中（起始偏移在末尾重叠区中点之后），到达末尾时才保留
+    let mut tail: Vec<Finding> = Vec::new();
+    let mut file_offset: usize = start;
+    // 当前块之前（已丢弃前缀中）的换行数，仅 with_location 时累计
+    let mut lines_before: usize = 0;
+    // 首块判定为二进制且启用 strings 模式时，后续所有块均走可打印串提取
+    let mut binary_strings = false;
+    // 可选：注释剥离。stripped 与 chunk 逐字节对应（同样保留重叠区），新读入的字节各剥离一次
+    let mut stripper = comment_stripper(path, opts);
+    let mut stripped: Vec<u8> = Vec::new();
+    // 已提前输出的命中数与是否因单文件命中数上限提前停止
+    let mut emitted_count: usize = 0;
+    let mut truncated = false;
 
     loop {
-        let n = reader.read(&mut buf)?;
+        if opts.is_cancelled() { break; }
+        let n = reader.read(&mut buf[carry_len..carry_len + chunk_size])?;
         if n == 0 { break; }
-        let mut chunk: Vec<u8> = Vec::with_capacity(carry.len() + n);
-        if !carry.is_empty() { chunk.extend_from_slice(&carry); }
-        chunk.extend_from_slice(&buf[..n]);
+        let chunk = &buf[..carry_len + n];
+        // 还有下一块：上一块末尾暂存的命中由本块重新扫描得到
+        tail.clear();
 
-        // 对首个块做二进制判定；若疑似二进制，直接跳过整个文件。
-        if file_offset == 0 {
+        // 对首个块做二进制判定；若疑似二进制，按策略跳过整个文件或切换到 strings 模式。
+        if file_offset == start {
             // 只抽样前 8KiB，避免超大 chunk 误判
-            let sample_len = chunk.len().min(8192);
-            if is_probably_binary(&chunk[..sample_len]) {
-                return Ok(Vec::new());
+            let sample_len = chunk.len().min(SAMPLE_LEN);
+            // 超出整读阈值的 UTF-16 文本不转码（偏移还原需要逐字节对照表），仍按二进制策略处理
+            if let Some(order) = detect_utf16(&chunk[..sample_len]) {
+                tracing::debug!(file_hash, encoding = order.name(), "UTF-16 text too large to transcode, handled as binary");
             }
-        }
-
-        let base = file_offset.saturating_sub(carry.len());
-        let mut part = scan_buffer_with_prefilter(&chunk, base, file_hash, plan);
-        // 合并并确保文件内去重
-        for f in part.drain(..) {
-            if seen.insert(f.value.clone()) {
-                findings.push(f);
+            if is_probably_binary(&chunk[..sample_len], opts) {
+                match opts.binary_mode {
+                    BinaryMode::Skip => return Ok(FileScan { binary_skipped: Some(chunk.len() as u64), ..Default::default() }),
+                    BinaryMode::Strings => {
+                        binary_strings = true;
+                        stripper = None;
+                    }
+                    BinaryMode::Raw => {}
+                }
             }
         }
+        if let Some(s) = stripper.as_mut() {
+            stripped.extend_from_slice(&chunk[carry_len..]);
+            s.strip(&mut stripped[carry_len..]);
+        }
 
-        // 更新 carry 与偏移
-        let keep = CHUNK_OVERLAP.min(carry.len() + n);
-        let total_len = carry.len() + n;
-        if keep > 0 {
-            carry = chunk[total_len - keep..total_len].to_vec();
+        let base = file_offset.saturating_sub(carry_len);
+        let mut cap_reached = false;
+        let mut part = if binary_strings {
+            scan_binary_strings(chunk, base, file_hash, plan)
         } else {
-            carry.clear();
+            let text: &[u8] = if stripper.is_some() { &stripped } else { chunk };
+            let mut p = scan_buffer_with_prefilter(text, base, file_hash, plan);
+            cap_reached = plan.match_cap_reached(p.len());
+            p.extend(scan_env_lines(text, base, file_hash, path, &plan.rules));
+            p.extend(scan_der_blocks(text, base, file_hash, path, &plan.rules));
+            p.extend(scan_url_credentials(text, base, file_hash, path, &plan.rules));
+            p.extend(scan_marker_blocks(text, base, file_hash, path, &plan.rules));
+            p.extend(scan_keyword_entropy(text, base, file_hash, path, &plan.rules));
+            p.extend(scan_path_scoped(text, base, file_hash, path, plan));
+            if opts.join_string_literals {
+                p.extend(scan_joined_literals(text, base, file_hash, plan));
+            }
+            for f in &mut p { f.source = FindingSource::Chunked; }
+            if opts.decode_base64 {
+                p.extend(scan_base64_blobs(text, base, file_hash, plan));
+            }
+            p
+        };
+        // 起始偏移在开头重叠区中点之前的命中归属上一块（已在上一块中完整扫描）
+        let lo = file_offset.saturating_sub(half).max(base);
+        part.retain(|f| f.start_offset >= lo);
+        if opts.inline_suppression {
+            retain_unsuppressed(chunk, base, &mut part, &opts.suppression_marker);
+        }
+        if opts.with_location {
+            annotate_lines(chunk, base, lines_before, &mut part);
         }
+        if !binary_strings {
+            annotate_context_by(chunk, base, opts, &mut part);
+        }
+        // 起始偏移在末尾重叠区中点之后的命中暂存（锚点窗口可能被块末尾截断），是否保留取决于是否还有下一块
+        let hi = (base + chunk.len()).saturating_sub(half);
+        let (part, rest): (Vec<Finding>, Vec<Finding>) = part.into_iter().partition(|f| f.start_offset < hi);
+        tail = rest;
+        // 合并并确保文件内去重
+        raw.record(&part);
+        findings.extend(part.into_iter().filter(|f| !emitted.contains(dedup_key(&f.value, opts.normalize_dedup))));
+        // 单文件命中数上限：达到后不再读取后续块；末尾暂存的命中可能被块末尾截断，一并丢弃
+        if cap_reached || opts.max_matches_per_file.is_some_and(|cap| emitted_count + findings.len() >= cap) {
+            truncated = true;
+            tail.clear();
+            file_offset = file_offset.saturating_add(n);
+            break;
+        }
+
+        // 仅保留末尾重叠区作为下一块的前缀，并更新偏移
+        let keep = overlap.min(chunk.len());
+        if opts.with_location { lines_before += count_lines(&chunk[..chunk.len() - keep]); }
+        if stripper.is_some() { stripped.drain(..stripped.len() - keep); }
+        let len = chunk.len();
+        buf.copy_within(len - keep..len, 0);
+        carry_len = keep;
         file_offset = file_offset.saturating_add(n);
+
+        // 下一块起点之前的命中已确定，提前输出
+        let mut ready = findings.take_before(file_offset - keep);
+        if !ready.is_empty() {
+            if !opts.no_dedup { emitted.extend(ready.iter().map(|f| dedup_key(&f.value, opts.normalize_dedup).to_string())); }
+            sort_findings_stable(&mut ready);
+            emitted_count += ready.len();
+            emit_batch(ready)?;
+        }
     }
 
-    Ok(findings)
+    // 末块：其后没有下一块，暂存的命中即为最终结果
+    raw.record(&tail);
+    findings.extend(tail.into_iter().filter(|f| !emitted.contains(dedup_key(&f.value, opts.normalize_dedup))));
+    let remaining_cap = opts.max_matches_per_file.map(|cap| cap.saturating_sub(emitted_count));
+    Ok(FileScan::new(findings.into_vec(), raw, opts.hit_positions).capped(remaining_cap, truncated).with_scanned(file_offset - start))
 }
 
-/// 在给定缓冲区上执行预筛匹配，返回命中项（不排序）
-fn scan_buffer_with_prefilter(buf: &[u8], base_offset: usize, file_hash: &str, plan: &PrefilterPlan) -> Vec<Finding> {
+/// 在给定缓冲区上执行预筛匹配，返回命中项（不排序、不去重）
+pub(crate) fn scan_buffer_with_prefilter(buf: &[u8], base_offset: usize, file_hash: &str, plan: &PrefilterPlan) -> Vec<Finding> {
     let mut findings: Vec<Finding> = Vec::new();
-    let mut seen: HashSet<String> = HashSet::new();
+    // 空计划（规则文件中没有可用锚点）：直接返回，不在空自动机上扫描
+    if plan.anchors.is_empty() { return findings; }
+
+    // 超长行（见 `ScanOptions::max_line_length`）：其中的锚点命中不生成窗口，窗口延伸进超长行产生的命中最后丢弃
+    let long_lines = match plan.max_line_length {
+        Some(max) => long_lines(buf, max),
+        None => Vec::new(),
+    };
+    if !long_lines.is_empty() {
+        plan.long_lines_skipped.fetch_add(long_lines.len(), std::sync::atomic::Ordering::Relaxed);
+    }
 
     // 1) 全局 AC 扫描，收集命中位置
     let mut hits: Vec<(usize /*pos*/, usize /*anchor_id*/)> = Vec::new();
     for m in plan.ac.find_iter(buf) {
+        if in_spans(&long_lines, m.start()) { continue; }
         hits.push((m.start(), m.pattern().as_usize()));
     }
     if hits.is_empty() {
@@ -188,20 +359,31 @@ fn scan_buffer_with_prefilter(buf: &[u8], base_offset: usize, file_hash: &str, p
     }
 
     // 2) 生成窗口并合并
+    //    同时累计各命中窗口（合并前）的字节数：锚点过密时大量窗口反复覆盖同一区域，
+    //    超过 `max_window_bytes`（或 PEM 放大窗口数超过 `max_pem_windows`）后改为整个缓冲区作为单一窗口，只扫描一遍；
+    //    短于 `small_file_fullscan_threshold` 的缓冲区直接作为单一窗口
     hits.sort_by_key(|h| h.0);
     let mut windows: Vec<(usize, usize, Vec<usize>)> = Vec::new(); // (start,end, anchor_ids)
+    let mut window_bytes = 0usize;
+    let mut pem_windows = 0usize;
+    let mut all_aids: Vec<usize> = Vec::new();
+    let mut full_scan = buf.len() < plan.small_file_fullscan_threshold;
     for (pos, aid) in hits.into_iter() {
-        // 针对 PEM/私钥类锚点放大窗口，避免长块被截断导致无法匹配完整 BEGIN..END 结构
-        let anchor = plan.anchors.get(aid).map(|v| v.as_slice()).unwrap_or(&[]);
-        let is_begin = anchor.starts_with(b"-----BEGIN ");
-        let is_end   = anchor.starts_with(b"-----END ");
-        let is_priv  = anchor.windows(12).any(|w| w == b"PRIVATE KEY");
-
-        let before = if is_end || is_priv { WINDOW_BEFORE.max(2048) } else { WINDOW_BEFORE };
-        let after  = if is_begin || is_priv { WINDOW_AFTER.max(16 * 1024) } else { WINDOW_AFTER };
+        if full_scan { all_aids.push(aid); continue; }
+        // 窗口大小按锚点预先计算（规则覆盖的窗口；PEM/私钥类锚点放大，避免长块被截断导致无法匹配完整 BEGIN..END 结构）
+        let (before, after) = plan.anchor_windows[aid];
+        let (is_begin, is_end, is_priv) = pem_anchor_kind(&plan.anchors[aid]);
+        if is_begin || is_end || is_priv { pem_windows += 1; }
 
         let s = pos.saturating_sub(before);
         let e = (pos + after).min(buf.len());
+        window_bytes = window_bytes.saturating_add(e - s);
+        if plan.max_window_bytes.is_some_and(|cap| window_bytes > cap) || plan.max_pem_windows.is_some_and(|cap| pem_windows > cap) {
+            full_scan = true;
+            all_aids.extend(windows.drain(..).flat_map(|w| w.2));
+            all_aids.push(aid);
+            continue;
+        }
         if let Some(last) = windows.last_mut() {
             if s <= last.1 { // 重叠，合并
                 last.1 = last.1.max(e);
@@ -211,60 +393,181 @@ fn scan_buffer_with_prefilter(buf: &[u8], base_offset: usize, file_hash: &str, p
         }
         windows.push((s, e, vec![aid]));
     }
+    if full_scan {
+        all_aids.sort_unstable();
+        all_aids.dedup();
+        windows.push((0, buf.len(), all_aids));
+    }
 
     // 3) 对每个窗口确定候选规则并执行精准正则提取
     for (ws, we, aids) in windows.into_iter() {
-        // 聚合规则索引
+        if plan.is_cancelled() { break; }
+        let window = &buf[ws..we];
+        // 聚合规则索引（宽泛锚点需窗口内出现次级字面量才运行对应规则）
         let mut rule_set: HashSet<usize> = HashSet::new();
         for aid in aids {
             if let Some(rules) = plan.anchor_to_rules.get(aid) {
-                for &ri in rules.iter() { rule_set.insert(ri); }
+                for &ri in rules.iter() {
+                    if plan.rule_allowed_in_window(aid, ri, window) { rule_set.insert(ri); }
+                }
             }
         }
         if rule_set.is_empty() { continue; }
-        let window = &buf[ws..we];
 
-        for ri in rule_set.into_iter() {
-            if let Some(rx) = get_or_compile_meta_regex(plan, ri) {
-                // 使用 regex-automata 0.4 meta 引擎执行匹配并提取捕获
-                let re = &*rx;
-                let mut caps = re.create_captures();
-                let mut at = 0usize;
-                loop {
-                    // 在 [at..] 范围内继续查找下一个匹配
-                    let input = Input::new(window).span(at..window.len());
-                    re.captures(input, &mut caps);
-                    let m0 = match caps.get_group(0) { Some(sp) => sp, None => break };
-                    let (start, end) = if let Some(g1) = caps.get_group(1) {
-                        (g1.start, g1.end)
-                    } else {
-                        (m0.start, m0.end)
-                    };
-                    if end <= start { at = m0.end.saturating_add(1); continue; }
-                    let raw = &window[start..end];
-                    let value = String::from_utf8_lossy(raw).to_string();
-                    if seen.insert(value.clone()) {
-                        let global_start = base_offset + ws + start;
-                        findings.push(Finding { file_hash: file_hash.to_string(), value, start_offset: global_start });
-                    }
-                    // 推进光标，防止零宽循环
-                    at = if m0.end > at { m0.end } else { at.saturating_add(1) };
-                }
+        // 按优先级降序、同优先级按规则加载顺序运行，遍历顺序与 HashSet 无关；优先级不同时高优先级规则先认领命中区间
+        let mut rule_order: Vec<usize> = rule_set.into_iter().collect();
+        rule_order.sort_unstable_by_key(|&ri| (std::cmp::Reverse(plan.rules[ri].priority), ri));
+        let mut claims = SpanClaims::default();
+        for ri in rule_order {
+            let before = findings.len();
+            let line_start = ws == 0 || buf[ws - 1] == b'\n';
+            run_rule_in_window(plan, ri, window, line_start, base_offset + ws, file_hash, &mut findings);
+            if plan.prioritized { claims.settle(&mut findings, before, plan.rules[ri].priority); }
+            plan.record_window(ri, findings.len() > before);
+            if we - ws < buf.len() {
+                for f in &mut findings[before..] { f.source = FindingSource::Window; }
             }
         }
+        // 单文件命中数上限：以完整窗口为单位停止，截断前的命中集合与规则遍历顺序无关
+        if plan.match_cap_reached(findings.len()) { break; }
+    }
+
+    if !long_lines.is_empty() {
+        findings.retain(|f| !in_spans(&long_lines, f.start_offset - base_offset));
+    }
+    // 同一 value 可能被多个窗口 / 规则命中：不在此去重，由调用方在记录原始命中位置后统一去重
+    findings
+}
+
+/// 长度（字节，不含换行符）超过 `max` 的行的区间 [start, end)，按起点升序
+fn long_lines(buf: &[u8], max: usize) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut start = 0usize;
+    for end in memchr::memchr_iter(b'\n', buf).chain(std::iter::once(buf.len())) {
+        if end - start > max { spans.push((start, end)); }
+        start = end + 1;
     }
+    spans
+}
 
+/// `pos` 是否落在某个区间内（`spans` 按起点升序且互不重叠）
+fn in_spans(spans: &[(usize, usize)], pos: usize) -> bool {
+    let i = spans.partition_point(|&(_, end)| end <= pos);
+    spans.get(i).is_some_and(|&(start, _)| start <= pos)
+}
+
+/// 对路径匹配的路径作用域规则整缓冲区运行精准正则（这些规则不参与 AC 预筛）
+pub(crate) fn scan_path_scoped(buf: &[u8], base_offset: usize, file_hash: &str, path: &Path, plan: &PrefilterPlan) -> Vec<Finding> {
+    let mut findings: Vec<Finding> = Vec::new();
+    for &ri in plan.path_scoped.iter() {
+        if plan.rules[ri].applies_to(path) {
+            run_rule_in_window(plan, ri, buf, true, base_offset, file_hash, &mut findings);
+        }
+    }
     findings
 }
 
+/// 在窗口内运行单条规则的精准正则并收集命中（不去重，由调用方统一去重）
+/// - `line_start`：窗口起点是否位于行首（缓冲区开头或前一字节为 `\n`），供 `anchor_line_start` 规则判定
+/// - `window_offset`：窗口在原文件中的起始偏移
+/// - 关键字邻近规则只在窗口内各关键字的赋值运算符之后查找（见 `proximity` 模块）
+pub(crate) fn run_rule_in_window(
+    plan: &PrefilterPlan,
+    ri: usize,
+    window: &[u8],
+    line_start: bool,
+    window_offset: usize,
+    file_hash: &str,
+    findings: &mut Vec<Finding>,
+) {
+    let rx = match get_or_compile_meta_regex(plan, ri) { Some(rx) => rx, None => return };
+    // 使用 regex-automata 0.4 meta 引擎执行匹配并提取捕获
+    let re = &*rx;
+    let mut caps = re.create_captures();
+    let spec = &plan.rules[ri];
+    // 匹配时间上限：在两次查找之间检查，超时后放弃本窗口上的剩余匹配
+    let deadline = plan.rule_timeout.map(|t| Instant::now() + t);
+    let timed_out = || {
+        let out = deadline.is_some_and(|d| Instant::now() >= d);
+        if out { plan.record_timeout(ri, file_hash, window_offset); }
+        out
+    };
+    // 关键字邻近规则：每个赋值运算符之后查找一次，匹配须在 proximity 字节内开始
+    if let Some(p) = &spec.proximity {
+        for from in p.assignment_ends(window) {
+            if timed_out() { return; }
+            re.captures(Input::new(window).span(from..window.len()), &mut caps);
+            if caps.get_group(0).is_some_and(|m0| m0.start - from <= p.proximity) {
+                push_captures(&caps, spec, window, window_offset, file_hash, findings);
+            }
+        }
+        return;
+    }
+    // overlapping 规则：从本次匹配起点 +1 继续，允许后续匹配与之重叠
+    let mut at = 0usize;
+    while at <= window.len() {
+        if at > 0 && timed_out() { return; }
+        // 在 [at..] 范围内继续查找下一个匹配
+        let input = Input::new(window).span(at..window.len());
+        re.captures(input, &mut caps);
+        let m0 = match caps.get_group(0) { Some(sp) => sp, None => break };
+        // group(1) 与整个匹配均为空：没有可报告的值
+        if m0.is_empty() && caps.get_group(1).is_none_or(|g| g.is_empty()) { at = m0.end.saturating_add(1); continue; }
+        // anchor_line_start：整个匹配须从行首开始，否则丢弃（光标照常推进）
+        if !spec.anchor_line_start || (if m0.start == 0 { line_start } else { window[m0.start - 1] == b'\n' }) {
+            push_captures(&caps, spec, window, window_offset, file_hash, findings);
+        }
+        // 推进光标，防止零宽循环
+        at = if spec.overlapping {
+            m0.start.saturating_add(1).max(at.saturating_add(1))
+        } else if m0.end > at {
+            m0.end
+        } else {
+            at.saturating_add(1)
+        };
+    }
+}
+
+/// 按规则的分组选项把一次匹配转为命中：
+/// emit_all_groups 时每个非空捕获组各一条命中（按分组序号）；
+/// 否则优先 group(1)，group(1) 未参与匹配或匹配为空（可选子组）时退回整个匹配
+fn push_captures(caps: &ra::util::captures::Captures, spec: &RuleSpec, window: &[u8], window_offset: usize, file_hash: &str, findings: &mut Vec<Finding>) {
+    let Some(m0) = caps.get_group(0) else { return };
+    let mut spans: Vec<(usize, usize, Option<usize>)> = Vec::new();
+    if spec.emit_all_groups {
+        spans.extend((1..caps.group_len()).filter_map(|i| caps.get_group(i).filter(|g| !g.is_empty()).map(|g| (g.start, g.end, Some(i)))));
+    }
+    if spans.is_empty() {
+        let sp = caps.get_group(1).filter(|g| !g.is_empty()).unwrap_or(m0);
+        if sp.is_empty() { return; }
+        spans.push((sp.start, sp.end, None));
+    }
+    for (start, end, group_index) in spans {
+        let value = String::from_utf8_lossy(&window[start..end]).to_string();
+        findings.push(Finding {
+            file_hash: file_hash.to_string(),
+            value,
+            start_offset: window_offset + start,
+            rule_id: spec.id.clone(),
+            validated: None,
+            lines: None,
+            context: None,
+            confidence: None,
+            group_index,
+            source: FindingSource::default(),
+            path: None,
+        });
+    }
+}
+
 /// 判定缓冲区是否“明显是二进制”
 /// 策略（保守，尽量不误杀文本）：
 /// - 只要包含任何 NUL 字节（0x00）即认为二进制；
-/// - 否则计算可打印 ASCII 比例（包含 tab/CR/LF），低于 25% 则认为二进制。
-fn is_probably_binary(buf: &[u8]) -> bool {
+/// - 否则计算可打印 ASCII 比例（包含 tab/CR/LF），低于 `binary_printable_threshold`（缺省 25%）则认为二进制。
+fn is_probably_binary(buf: &[u8], opts: &ScanOptions) -> bool {
     if buf.is_empty() { return false; }
-    if buf.iter().any(|&b| b == 0) { return true; }
+    if buf.contains(&0) { return true; }
     let printable = buf.iter().filter(|&&b| matches!(b, 0x09 | 0x0A | 0x0D) || (0x20..=0x7E).contains(&b)).count();
     let ratio = printable as f32 / (buf.len() as f32);
-    ratio < 0.25
+    ratio < opts.binary_printable_threshold
 }
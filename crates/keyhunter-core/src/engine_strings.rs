@@ -0,0 +1,70 @@
+//! 二进制可打印串扫描引擎（类似 `strings` 工具）
+//!
+//! 对判定为二进制的文件（ELF/PE 等），提取其中连续的可打印 ASCII/UTF-8 片段，
+//! 仅在这些片段上运行预筛 + 精准正则，命中偏移映射回原始文件。
+use crate::engine_bytes::scan_buffer_with_prefilter;
+use crate::findings::{FindingPublic as Finding, FindingSource};
+use crate::prefilter::PrefilterPlan;
+
+/// 可打印串的最小长度（与 `strings` 默认值一致）
+pub(crate) const MIN_STRING_RUN: usize = 4;
+
+/// 在二进制缓冲区中提取可打印串并扫描
+/// - `base_offset`：缓冲区在原文件中的起始偏移，用于还原全局偏移
+/// - 不去重，由调用方统一去重
+pub(crate) fn scan_binary_strings(buf: &[u8], base_offset: usize, file_hash: &str, plan: &PrefilterPlan) -> Vec<Finding> {
+    let mut findings: Vec<Finding> = Vec::new();
+
+    for (start, end) in extract_string_runs(buf, MIN_STRING_RUN) {
+        findings.extend(scan_buffer_with_prefilter(&buf[start..end], base_offset + start, file_hash, plan));
+    }
+    for f in &mut findings { f.source = FindingSource::Strings; }
+
+    findings
+}
+
+/// 提取可打印串区间 `[start, end)`：
+/// - 可打印 ASCII（0x20..=0x7E）与 tab；
+/// - 合法的多字节 UTF-8 序列整体视为可打印；
+/// - 其余字节（控制字符、NUL、非法 UTF-8）作为分隔符。
+fn extract_string_runs(buf: &[u8], min_len: usize) -> Vec<(usize, usize)> {
+    let mut runs = Vec::new();
+    let mut run_start: Option<usize> = None;
+    let mut i = 0usize;
+
+    while i < buf.len() {
+        let step = printable_len_at(buf, i);
+        if step > 0 {
+            if run_start.is_none() { run_start = Some(i); }
+            i += step;
+        } else {
+            if let Some(s) = run_start.take() {
+                if i - s >= min_len { runs.push((s, i)); }
+            }
+            i += 1;
+        }
+    }
+    if let Some(s) = run_start {
+        if buf.len() - s >= min_len { runs.push((s, buf.len())); }
+    }
+
+    runs
+}
+
+/// 返回位置 `i` 处可打印字符的字节长度；不可打印则返回 0
+fn printable_len_at(buf: &[u8], i: usize) -> usize {
+    let b = buf[i];
+    if b == 0x09 || (0x20..=0x7E).contains(&b) { return 1; }
+    // 多字节 UTF-8：依据首字节确定长度，并校验后续字节
+    let need = match b {
+        0xC2..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF4 => 4,
+        _ => return 0,
+    };
+    if i + need > buf.len() { return 0; }
+    match std::str::from_utf8(&buf[i..i + need]) {
+        Ok(_) => need,
+        Err(_) => 0,
+    }
+}
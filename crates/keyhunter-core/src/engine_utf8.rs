@@ -1,41 +1,248 @@
 //! UTF-8 字符串扫描引擎
 use anyhow::Result;
-use std::collections::HashSet;
-use std::fs::File;
 use std::io::{BufReader, Read};
 use std::path::Path;
 
 use crate::detectors::DetectorSetUtf8;
-use crate::findings::FindingPublic as Finding;
+use crate::engine_bytes::{effective_chunk_size, CHUNK_OVERLAP};
+use crate::block::{max_block_len, scan_marker_blocks};
+use crate::der::scan_der_blocks;
+use crate::url::scan_url_credentials;
+use crate::keyword_entropy::scan_keyword_entropy;
+use crate::env::scan_env_lines;
+use crate::location::{annotate_context_by, annotate_lines, count_lines};
+use crate::findings::{dedup_keep_earliest, DedupByValue, FileScan, FindingPublic as Finding, FindingSource, RawHits, SpanClaims};
+use crate::options::ScanOptions;
+use crate::rules::RuleSpec;
+use crate::suppress::retain_unsuppressed;
+use crate::comments::comment_stripper;
+use crate::digest::ContentHasher;
+use crate::region::{open_region, read_region, utf8_lead_skip, Region};
 
 /// 按“UTF-8 字符串”方式扫描单个文件
 /// - 适合需要 UTF-8 语义的检测器（demo 保持与 Bytes 等价规则）
 /// - 单文件内基于 value 去重
-pub(crate) fn scan_file_utf8(path: &Path, file_hash: &str, detectors: &DetectorSetUtf8) -> Result<Vec<Finding>> {
-    let file = File::open(path)?;
-    let mut reader = BufReader::new(file);
-    let mut buf = String::new();
-    reader.read_to_string(&mut buf)?;
+/// - `hasher` 为 Some 时读取的同时计算内容摘要
+pub(crate) fn scan_file_utf8(path: &Path, file_hash: &str, detectors: &DetectorSetUtf8, opts: &ScanOptions, hasher: Option<&mut ContentHasher>) -> Result<FileScan> {
+    // 仅读取扫描区域（head_bytes / tail_bytes；未设置时为整个文件）
+    let (buf, region) = read_region(path, opts, hasher)?;
+    scan_region_utf8(&buf, &region, path, file_hash, detectors, opts)
+}
+
+/// 扫描已读入内存的区域内容（`buf` 即 `region` 覆盖的字节）：丢弃被区域边界切开的字符，
+/// 偏移还原为文件内的绝对偏移；区域内含非法 UTF-8 时返回 `InvalidData` 错误
+pub(crate) fn scan_region_utf8(buf: &[u8], region: &Region, path: &Path, file_hash: &str, detectors: &DetectorSetUtf8, opts: &ScanOptions) -> Result<FileScan> {
+    let (start, end) = region.utf8_bounds(buf);
+    let text = std::str::from_utf8(&buf[start..end])
+        .map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidData, "stream did not contain valid UTF-8"))?;
+    let mut scan = scan_str_utf8(text, path, file_hash, detectors, opts).shift_offsets(region.start as usize + start);
+    scan.file_size = region.file_len;
+    Ok(scan)
+}
 
-    let mut seen: HashSet<String> = HashSet::new();
+/// 对整段文本执行与小文件相同的扫描（文件整读后、或标准输入内容）
+/// - `path`：逻辑路径，仅用于路径作用域规则的匹配
+pub(crate) fn scan_str_utf8(text: &str, path: &Path, file_hash: &str, detectors: &DetectorSetUtf8, opts: &ScanOptions) -> FileScan {
+    // 可选：剥离源码注释后扫描；注释起止均为 ASCII 字节，整段替换为空格后仍是合法 UTF-8
+    let stripped = comment_stripper(path, opts).map(|mut s| {
+        let mut b = text.as_bytes().to_vec();
+        s.strip(&mut b);
+        String::from_utf8(b).expect("comment stripping preserves UTF-8 validity")
+    });
     let mut findings: Vec<Finding> = Vec::new();
+    scan_text_utf8(stripped.as_deref().unwrap_or(text), 0, file_hash, path, detectors, &mut findings);
+    if opts.inline_suppression {
+        retain_unsuppressed(text.as_bytes(), 0, &mut findings, &opts.suppression_marker);
+    }
+
+    // 去重前记录原始命中位置
+    let mut raw = RawHits::default();
+    raw.record(&findings);
+    // 文件内去重：同一 value 保留偏移最小者
+    let mut findings = dedup_keep_earliest(findings, opts.no_dedup, opts.normalize_dedup);
+    if opts.with_location { annotate_lines(text.as_bytes(), 0, 0, &mut findings); }
+    annotate_context_by(text.as_bytes(), 0, opts, &mut findings);
+    FileScan::new(findings, raw, opts.hit_positions).with_scanned(text.len())
+}
+
+/// 分块扫描大文件（UTF-8 引擎），与 Bytes 引擎分块路径对齐：
+/// - 固定块大小 + 末尾重叠（CHUNK_OVERLAP，有 block 规则时不小于最大块长度），保证跨块匹配不丢失；
+/// - 块边界不会切断多字节 UTF-8 序列：不完整的尾部字节留到下一块拼接；
+/// - 重叠区起点向后对齐到字符边界；
+/// - 与整读路径一致：遇到非法 UTF-8 直接返回错误（整读的 `read_to_string` 行为）。
+/// - `hasher` 为 Some 时读取的同时计算内容摘要。
+pub(crate) fn scan_file_utf8_chunked(path: &Path, file_hash: &str, detectors: &DetectorSetUtf8, opts: &ScanOptions, hasher: Option<&mut ContentHasher>) -> Result<FileScan> {
+    // 仅读取扫描区域（head_bytes / tail_bytes；未设置时为整个文件），偏移从区域起点开始
+    let (reader, region) = open_region(path, opts, hasher)?;
+    let mut reader = BufReader::new(reader);
+    // 文件内去重（跨块）：同一 value 保留偏移最小者
+    let mut findings = DedupByValue::new(opts.no_dedup, opts.normalize_dedup);
+    // 去重前的原始命中位置
+    let mut raw = RawHits::default();
+
+    // pending = 上一块的重叠区 + 本次读取的字节（可能含不完整的尾部序列）；跨迭代复用，直接读入尾部
+    let chunk_size = effective_chunk_size(opts);
+    let overlap = CHUNK_OVERLAP.max(max_block_len(&detectors.non_regex_rules));
+    let mut pending: Vec<u8> = Vec::with_capacity(chunk_size + overlap);
+    // pending[0] 在文件中的偏移
+    let mut pending_offset: usize = region.start as usize;
+    // pending 之前（已丢弃前缀中）的换行数，仅 with_location 时累计
+    let mut lines_before: usize = 0;
+    // 可选：注释剥离。stripped 与 pending 逐字节对应，新读入的字节各剥离一次
+    let mut stripper = comment_stripper(path, opts);
+    let mut stripped: Vec<u8> = Vec::new();
+
+    loop {
+        if opts.is_cancelled() { break; }
+        let carry_len = pending.len();
+        pending.resize(carry_len + chunk_size, 0);
+        let n = reader.read(&mut pending[carry_len..])?;
+        pending.truncate(carry_len + n);
+        if n == 0 { break; }
+        // tail 区域开头被切开的字符残余直接丢弃
+        if region.cut_start && pending_offset == region.start as usize {
+            let skip = utf8_lead_skip(&pending);
+            pending.drain(..skip);
+            pending_offset += skip;
+        }
+        if let Some(s) = stripper.as_mut() {
+            stripped.extend_from_slice(&pending[carry_len..]);
+            s.strip(&mut stripped[carry_len..]);
+        }
+
+        // 截取到最后一个完整字符边界；中间出现非法序列则报错
+        let valid = match std::str::from_utf8(&pending) {
+            Ok(_) => pending.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(e) => return Err(e.into()),
+        };
+        let text = std::str::from_utf8(&pending[..valid])?;
+        let mut part: Vec<Finding> = Vec::new();
+        if stripper.is_some() {
+            let scan_text = std::str::from_utf8(&stripped[..valid])?;
+            scan_text_utf8(scan_text, pending_offset, file_hash, path, detectors, &mut part);
+        } else {
+            scan_text_utf8(text, pending_offset, file_hash, path, detectors, &mut part);
+        }
+        for f in &mut part { f.source = FindingSource::Chunked; }
+        if opts.inline_suppression {
+            // 仅对本块命中做抑制判定（其行上下文位于当前块内）
+            retain_unsuppressed(text.as_bytes(), pending_offset, &mut part, &opts.suppression_marker);
+        }
+        if opts.with_location {
+            annotate_lines(text.as_bytes(), pending_offset, lines_before, &mut part);
+        }
+        annotate_context_by(text.as_bytes(), pending_offset, opts, &mut part);
+        raw.record(&part);
+        findings.extend(part);
 
-    for re in &detectors.patterns {
-        // 同样优先使用第1个捕获组，兼容部分规则末尾存在分隔符/换行等上下文
-        for caps in re.captures_iter(&buf) {
-            let (start, end) = match caps.get(1) {
-                Some(m) => (m.start(), m.end()),
-                None => caps.get(0).map(|m| (m.start(), m.end())).unwrap_or((0, 0)),
-            };
-            if end <= start { continue; }
-
-            let value = buf[start..end].to_string();
-            if seen.insert(value.clone()) {
-                findings.push(Finding { file_hash: file_hash.to_string(), value, start_offset: start });
+        // 保留末尾重叠区（起点对齐到字符边界）与不完整尾部，供下一块拼接
+        let mut keep_from = valid.saturating_sub(overlap);
+        while keep_from < valid && !text.is_char_boundary(keep_from) { keep_from += 1; }
+        if opts.with_location { lines_before += count_lines(&pending[..keep_from]); }
+        if stripper.is_some() { stripped.drain(..keep_from); }
+        pending.drain(..keep_from);
+        pending_offset += keep_from;
+    }
+
+    // 文件以不完整的 UTF-8 序列结尾：与整读路径保持一致，视为非法 UTF-8（head 区域被截断时除外）
+    if !region.cut_end { std::str::from_utf8(&pending)?; }
+
+    let scanned = pending_offset + pending.len() - region.start as usize;
+    let mut scan = FileScan::new(findings.into_vec(), raw, opts.hit_positions).with_scanned(scanned);
+    scan.file_size = region.file_len;
+    Ok(scan)
+}
+
+/// 在 UTF-8 文本上运行全部检测器，命中偏移加上 `base_offset` 还原为文件偏移
+fn scan_text_utf8(
+    text: &str,
+    base_offset: usize,
+    file_hash: &str,
+    path: &Path,
+    detectors: &DetectorSetUtf8,
+    findings: &mut Vec<Finding>,
+) {
+    // 规则已按优先级降序排列（见 `DetectorSetUtf8::from_specs`）；整段文本视为一个窗口认领命中区间，路径作用域规则不参与
+    let mut claims = SpanClaims::default();
+    for (re, spec) in detectors.patterns.iter().zip(detectors.specs.iter()) {
+        // 路径作用域：仅对匹配的文件运行
+        if !spec.applies_to(path) { continue; }
+        let before = findings.len();
+        run_regex_rule(re, spec, text, base_offset, file_hash, findings);
+        if detectors.prioritized && spec.path.is_none() { claims.settle(findings, before, spec.priority); }
+    }
+
+    // 非正则规则：env（NAME=value 行）、der（base64 DER 私钥）、url（URL 内嵌凭据）、entropy（关键字之后的高熵串）、block（标记界定的多行块）
+    findings.extend(scan_env_lines(text.as_bytes(), base_offset, file_hash, path, &detectors.non_regex_rules));
+    findings.extend(scan_der_blocks(text.as_bytes(), base_offset, file_hash, path, &detectors.non_regex_rules));
+    findings.extend(scan_url_credentials(text.as_bytes(), base_offset, file_hash, path, &detectors.non_regex_rules));
+    findings.extend(scan_keyword_entropy(text.as_bytes(), base_offset, file_hash, path, &detectors.non_regex_rules));
+    findings.extend(scan_marker_blocks(text.as_bytes(), base_offset, file_hash, path, &detectors.non_regex_rules));
+}
+
+/// 在 UTF-8 文本上运行单条正则规则
+fn run_regex_rule(re: &regex::Regex, spec: &RuleSpec, text: &str, base_offset: usize, file_hash: &str, findings: &mut Vec<Finding>) {
+    // 关键字邻近规则：只在各关键字的赋值运算符之后查找一次，匹配须在 proximity 字节内开始（见 `proximity` 模块）
+    if let Some(p) = &spec.proximity {
+        for from in p.assignment_ends(text.as_bytes()) {
+            if let Some(caps) = re.captures_at(text, from).filter(|c| c.get(0).is_some_and(|m| m.start() - from <= p.proximity)) {
+                push_captures(&caps, spec, text, base_offset, file_hash, findings);
             }
         }
+        return;
+    }
+    // 同样优先使用第1个捕获组，兼容部分规则末尾存在分隔符/换行等上下文
+    // overlapping 规则：每次从上次匹配起点 +1 继续查找（允许重叠），否则从匹配终点继续
+    let mut at = 0usize;
+    while at <= text.len() {
+        let caps = match re.captures_at(text, at) { Some(c) => c, None => break };
+        let m0 = caps.get(0).expect("group 0 always present");
+        at = if spec.overlapping {
+            next_char_boundary(text, m0.start() + 1)
+        } else if m0.end() > m0.start() {
+            m0.end()
+        } else {
+            next_char_boundary(text, m0.end() + 1)
+        };
+        // anchor_line_start：整个匹配须从行首（文本开头或 `\n` 之后）开始，否则丢弃
+        if spec.anchor_line_start && m0.start() > 0 && text.as_bytes()[m0.start() - 1] != b'\n' { continue; }
+        push_captures(&caps, spec, text, base_offset, file_hash, findings);
     }
+}
 
-    Ok(findings)
+/// 按规则的分组选项把一次匹配转为命中：
+/// emit_all_groups 时每个非空捕获组各一条命中（按分组序号）；否则优先 group(1)，group(1) 匹配为空时退回整个匹配
+fn push_captures(caps: &regex::Captures, spec: &RuleSpec, text: &str, base_offset: usize, file_hash: &str, findings: &mut Vec<Finding>) {
+    let m0 = caps.get(0).expect("group 0 always present");
+    let mut spans: Vec<(usize, usize, Option<usize>)> = Vec::new();
+    if spec.emit_all_groups {
+        spans.extend((1..caps.len()).filter_map(|i| caps.get(i).filter(|m| !m.is_empty()).map(|m| (m.start(), m.end(), Some(i)))));
+    }
+    if spans.is_empty() {
+        let m = caps.get(1).filter(|m| !m.is_empty()).unwrap_or(m0);
+        if m.is_empty() { return; }
+        spans.push((m.start(), m.end(), None));
+    }
+    for (start, end, group_index) in spans {
+        findings.push(Finding {
+            file_hash: file_hash.to_string(),
+            value: text[start..end].to_string(),
+            start_offset: base_offset + start,
+            rule_id: spec.id.clone(),
+            validated: None,
+            lines: None,
+            context: None,
+            confidence: None,
+            group_index,
+            source: FindingSource::default(),
+            path: None,
+        });
+    }
 }
 
+/// 返回不小于 `i` 的下一个字符边界（超出末尾时返回 len+1 以终止循环）
+fn next_char_boundary(text: &str, mut i: usize) -> usize {
+    while i < text.len() && !text.is_char_boundary(i) { i += 1; }
+    if i > text.len() { text.len() + 1 } else { i }
+}
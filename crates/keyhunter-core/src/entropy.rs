@@ -0,0 +1,102 @@
+//! 香农熵计算（env 规则的熵门限）
+//!
+//! 熵按规则的 `entropy_alphabet` 选择的符号空间统计：
+//! - bytes：逐字节统计（缺省）；非 ASCII 字符的每个 UTF-8 字节各算一个符号；
+//! - ascii：逐字符（码点）统计，非 ASCII 字符整体算一个符号，避免多字节字符抬高或扭曲熵值；
+//! - base64：仅统计 base64 字母表（含 URL 安全变体 `-` `_`）中的字符，忽略 `=` 填充与分隔符；
+//! - hex：仅统计十六进制数字（大小写视为同一符号），忽略 `0x` 前缀与 `-` `:` 等分隔符。
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// 熵计算的符号空间
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum EntropyAlphabet {
+    #[default]
+    Bytes,
+    Ascii,
+    Base64,
+    Hex,
+}
+
+/// 按符号空间计算香农熵（bits/符号）；没有可统计的符号时为 0
+pub(crate) fn shannon_entropy(data: &[u8], alphabet: EntropyAlphabet) -> f64 {
+    match alphabet {
+        EntropyAlphabet::Bytes => entropy_of(data.iter().copied()),
+        EntropyAlphabet::Ascii => entropy_of(String::from_utf8_lossy(data).chars()),
+        EntropyAlphabet::Base64 => entropy_of(data.iter().copied().filter(|&b| b.is_ascii_alphanumeric() || matches!(b, b'+' | b'/' | b'-' | b'_'))),
+        EntropyAlphabet::Hex => entropy_of(hex_digits(data).map(|b| b.to_ascii_lowercase())),
+    }
+}
+
+/// 十六进制数字（跳过开头的 `0x` 前缀）
+fn hex_digits(data: &[u8]) -> impl Iterator<Item = u8> + '_ {
+    let body = data.strip_prefix(b"0x").or_else(|| data.strip_prefix(b"0X")).unwrap_or(data);
+    body.iter().copied().filter(u8::is_ascii_hexdigit)
+}
+
+fn entropy_of<T: Ord>(symbols: impl Iterator<Item = T>) -> f64 {
+    let mut counts: BTreeMap<T, usize> = BTreeMap::new();
+    let mut total = 0usize;
+    for s in symbols {
+        *counts.entry(s).or_insert(0) += 1;
+        total += 1;
+    }
+    if total == 0 { return 0.0; }
+    let len = total as f64;
+    counts
+        .values()
+        .map(|&c| {
+            let p = c as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn bytes_counts_every_utf8_byte() {
+        assert!(close(shannon_entropy(b"aaaa", EntropyAlphabet::Bytes), 0.0));
+        assert!(close(shannon_entropy(b"abcd", EntropyAlphabet::Bytes), 2.0));
+        // "é" 为两个不同的字节 C3 A9
+        assert!(close(shannon_entropy("éé".as_bytes(), EntropyAlphabet::Bytes), 1.0));
+        assert!(close(shannon_entropy("aé".as_bytes(), EntropyAlphabet::Bytes), 3f64.log2()));
+    }
+
+    #[test]
+    fn ascii_counts_each_char_once() {
+        assert!(close(shannon_entropy("éé".as_bytes(), EntropyAlphabet::Ascii), 0.0));
+        assert!(close(shannon_entropy("aé".as_bytes(), EntropyAlphabet::Ascii), 1.0));
+        assert!(close(shannon_entropy("密钥密钥".as_bytes(), EntropyAlphabet::Ascii), 1.0));
+    }
+
+    #[test]
+    fn base64_ignores_padding_and_separators() {
+        assert!(close(shannon_entropy(b"ab==", EntropyAlphabet::Base64), 1.0));
+        assert!(close(shannon_entropy(b"a-b_", EntropyAlphabet::Base64), 2.0));
+        assert!(close(shannon_entropy(b"AB+/\n.", EntropyAlphabet::Base64), 2.0));
+    }
+
+    #[test]
+    fn hex_folds_case_and_skips_prefix() {
+        assert!(close(shannon_entropy(b"0x0123456789abcdef", EntropyAlphabet::Hex), 4.0));
+        assert!(close(shannon_entropy(b"0XAaBb", EntropyAlphabet::Hex), 1.0));
+        assert!(close(shannon_entropy(b"DE-ad", EntropyAlphabet::Hex), 1.5));
+    }
+
+    #[test]
+    fn no_symbols_is_zero() {
+        for alphabet in [EntropyAlphabet::Bytes, EntropyAlphabet::Ascii, EntropyAlphabet::Base64, EntropyAlphabet::Hex] {
+            assert!(close(shannon_entropy(b"", alphabet), 0.0));
+        }
+        assert!(close(shannon_entropy(b"==..", EntropyAlphabet::Base64), 0.0));
+        assert!(close(shannon_entropy(b"0x", EntropyAlphabet::Hex), 0.0));
+    }
+}
@@ -0,0 +1,100 @@
+//! 环境变量转储检测（`kind = "env"` 规则）
+//!
+//! 针对 `env` / `printenv` 输出或 `.env` 文件中的 `NAME=value` 行：
+//! 变量名包含任一关键字（大小写不敏感，如 TOKEN/SECRET/KEY/PASSWORD），
+//! 且值满足最小长度与最小香农熵时，报告该值。
+//! 说明：
+//! - 允许行首的 `export ` 前缀与 `=` 前的空白；变量名需为 `[A-Za-z_][A-Za-z0-9_]*`；
+//! - 值去除首尾空白与成对引号，偏移指向去引号后的值起点；
+//! - 与正则规则互补，不经过 AC 预筛。
+use std::path::Path;
+
+use crate::entropy::{shannon_entropy, EntropyAlphabet};
+use crate::findings::{FindingPublic as Finding, FindingSource};
+use crate::rules::RuleSpec;
+
+/// 默认关键字（规则未声明 keywords 时使用）
+pub(crate) const DEFAULT_ENV_KEYWORDS: &[&str] = &["TOKEN", "SECRET", "KEY", "PASSWORD"];
+/// 默认最小值长度
+pub(crate) const DEFAULT_ENV_MIN_LENGTH: usize = 16;
+/// 默认最小香农熵（bits/字节）
+pub(crate) const DEFAULT_ENV_MIN_ENTROPY: f64 = 3.0;
+
+/// env 规则参数
+#[derive(Debug, Clone)]
+pub(crate) struct EnvParams {
+    /// 变量名关键字（已转为大写）
+    pub keywords: Vec<String>,
+    pub min_length: usize,
+    pub min_entropy: f64,
+    /// 熵计算的符号空间（缺省 bytes）
+    pub entropy_alphabet: EntropyAlphabet,
+}
+
+/// 在缓冲区中逐行运行适用于该文件的 env 规则（非 env 规则被忽略）
+/// - `base_offset`：缓冲区在原文件中的起始偏移
+/// - 不去重：同一 value 的每次出现都返回，由调用方在记录原始命中位置后统一去重
+pub(crate) fn scan_env_lines(buf: &[u8], base_offset: usize, file_hash: &str, path: &Path, rules: &[RuleSpec]) -> Vec<Finding> {
+    let mut findings: Vec<Finding> = Vec::new();
+    let env_rules: Vec<(&RuleSpec, &EnvParams)> = rules
+        .iter()
+        .filter(|r| r.applies_to(path))
+        .filter_map(|r| r.env.as_ref().map(|p| (r, p)))
+        .collect();
+    if env_rules.is_empty() { return Vec::new(); }
+
+    let mut line_start = 0usize;
+    for line in buf.split(|&b| b == b'\n') {
+        let offset = line_start;
+        line_start += line.len() + 1;
+        let (name, vs, ve) = match parse_env_line(line) { Some(v) => v, None => continue };
+        let value = &line[vs..ve];
+        let upper = String::from_utf8_lossy(name).to_ascii_uppercase();
+        for (spec, params) in env_rules.iter() {
+            if !params.keywords.iter().any(|k| upper.contains(k.as_str())) { continue; }
+            if value.len() < params.min_length || shannon_entropy(value, params.entropy_alphabet) < params.min_entropy { continue; }
+            findings.push(Finding {
+                file_hash: file_hash.to_string(),
+                value: String::from_utf8_lossy(value).to_string(),
+                start_offset: base_offset + offset + vs,
+                rule_id: spec.id.clone(),
+                validated: None,
+                lines: None,
+                context: None,
+                confidence: None,
+                group_index: None,
+                source: FindingSource::default(),
+                path: None,
+            });
+            break;
+        }
+    }
+
+    findings
+}
+
+/// 解析单行 `[export ]NAME=value`，返回 (变量名, 值起点, 值终点)（相对行首）
+fn parse_env_line(line: &[u8]) -> Option<(&[u8], usize, usize)> {
+    let trim_start = line.iter().position(|b| !b.is_ascii_whitespace())?;
+    let mut i = trim_start;
+    if line[i..].starts_with(b"export ") { i += b"export ".len(); }
+    let name_start = i;
+    if i >= line.len() || !(line[i].is_ascii_alphabetic() || line[i] == b'_') { return None; }
+    while i < line.len() && (line[i].is_ascii_alphanumeric() || line[i] == b'_') { i += 1; }
+    let name = &line[name_start..i];
+    // 兼容 .env 中 `NAME = value` 的写法
+    while i < line.len() && matches!(line[i], b' ' | b'\t') { i += 1; }
+    if i >= line.len() || line[i] != b'=' { return None; }
+
+    // 值：去除首尾空白（含 CR）与成对引号
+    let mut vs = i + 1;
+    let mut ve = line.len();
+    while vs < ve && line[vs].is_ascii_whitespace() { vs += 1; }
+    while ve > vs && line[ve - 1].is_ascii_whitespace() { ve -= 1; }
+    if ve - vs >= 2 && matches!(line[vs], b'"' | b'\'') && line[ve - 1] == line[vs] {
+        vs += 1;
+        ve -= 1;
+    }
+    if ve <= vs { return None; }
+    Some((name, vs, ve))
+}
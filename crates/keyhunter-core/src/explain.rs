@@ -0,0 +1,97 @@
+//! 规则集内省（供规则调试 / explain 子命令使用）
+//!
+//! 加载规则并构建预筛计划后，报告每条规则抽取到的锚点，以及是否无锚点
+//! （无锚点的规则不会被 AC 预筛触发，在 Bytes 引擎下实际不会运行）。
+use anyhow::Result;
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::options::ScanOptions;
+use crate::prefilter::{build_prefilter_plan, PrefilterPlan};
+use crate::rules::load_configured_rule_specs;
+use crate::types::Severity;
+
+/// 单条规则的预筛信息
+#[derive(Debug, Clone, Serialize)]
+pub struct RulePlanEntry {
+    /// 规则 id
+    pub id: String,
+    /// 可读名称
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// 规则类型：regex / env / der / url / entropy / block（非正则规则由专用检测器处理，不使用锚点）
+    pub kind: String,
+    /// 严重级别
+    pub severity: Severity,
+    /// 正则模式（非正则规则为 None）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    /// 所属命名规则集（仅配置了命名规则集时）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ruleset: Option<String>,
+    /// 抽取到的锚点（按计划中的锚点顺序）；block 规则为起止标记
+    pub anchors: Vec<String>,
+    /// 路径作用域 glob（有作用域的规则不进入预筛，在匹配的文件上整文件运行）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// 是否无锚点（预筛不会触发该规则）
+    pub anchorless: bool,
+}
+
+/// 预筛计划报告
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanReport {
+    /// 全局去重后的锚点数量
+    pub anchor_count: usize,
+    /// 按规则文件顺序排列的规则信息
+    pub rules: Vec<RulePlanEntry>,
+}
+
+impl PlanReport {
+    /// 无锚点规则数量
+    pub fn anchorless_count(&self) -> usize {
+        self.rules.iter().filter(|r| r.anchorless).count()
+    }
+}
+
+/// 按 `opts.rules_path`（缺省为默认规则文件）或命名规则集加载规则并构建预筛计划
+pub fn load_prefilter_plan(opts: &ScanOptions) -> Result<Arc<PrefilterPlan>> {
+    let specs = load_configured_rule_specs(opts)?;
+    Ok(build_prefilter_plan(&specs, opts))
+}
+
+/// 描述预筛计划：每条规则的锚点列表与无锚点标记
+pub fn describe_plan(plan: &PrefilterPlan) -> PlanReport {
+    // 反转 锚点 -> 规则 映射，得到 规则 -> 锚点
+    let mut rule_anchors: Vec<Vec<String>> = vec![Vec::new(); plan.rules.len()];
+    for (aid, rules) in plan.anchor_to_rules.iter().enumerate() {
+        let anchor = String::from_utf8_lossy(&plan.anchors[aid]).to_string();
+        for &ri in rules.iter() {
+            rule_anchors[ri].push(anchor.clone());
+        }
+    }
+
+    let rules = plan
+        .rules
+        .iter()
+        .zip(rule_anchors)
+        .map(|(spec, anchors)| match &spec.block {
+            Some(b) => (spec, vec![b.begin.clone(), b.end.clone()]),
+            None => (spec, anchors),
+        })
+        .map(|(spec, anchors)| RulePlanEntry {
+            id: spec.id.clone(),
+            name: spec.name.clone(),
+            kind: spec.kind_name().to_string(),
+            severity: spec.severity,
+            pattern: spec.pattern().map(str::to_string),
+            ruleset: spec.ruleset.clone(),
+            path: spec.path.as_ref().map(|g| g.glob().glob().to_string()),
+            // env / der / url 规则由专用检测器处理、路径作用域规则整文件运行，均不依赖锚点
+            anchorless: anchors.is_empty() && spec.is_regex() && spec.path.is_none(),
+            anchors,
+        })
+        .collect();
+
+    PlanReport { anchor_count: plan.anchors.len(), rules }
+}
@@ -1,20 +1,251 @@
-//! 命中项与排序（内部使用）
+//! 命中项、去重与排序（内部使用）
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
 
-/// 单次命中的内部表示
-#[derive(Debug, Clone)]
+use crate::encoding::Utf16Order;
+use crate::state::FileStamp;
+use crate::types::SkipReason;
+
+/// 单次命中的内部表示（可序列化，供增量扫描状态文件保存，见 `state` 模块）
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct Finding {
     pub(crate) file_hash: String,
     pub(crate) value: String,
+    /// 命中起始字节偏移，相对于被扫描的逻辑内容起点：
+    /// 普通文件即文件本身；解压 / 归档条目则为解压后条目内容内的偏移，而非容器文件中的偏移
     pub(crate) start_offset: usize,
+    /// 产生该命中的规则 id
+    pub(crate) rule_id: String,
+    /// 校验结果：仅在规则声明了校验器且为标注模式时由 Writer 端填写
+    pub(crate) validated: Option<bool>,
+    /// 行号范围 (start_line, end_line)，从 1 开始；仅 `with_location` 时由引擎填写
+    pub(crate) lines: Option<(usize, usize)>,
+    /// 命中前后若干行 / 若干字节的上下文（命中值已脱敏）；仅 `context_lines` / `context_bytes` 时由引擎填写
+    pub(crate) context: Option<String>,
+    /// 置信度（见 `score` 模块）；仅 `with_score` 时由 Writer 端填写
+    pub(crate) confidence: Option<f32>,
+    /// 产生该命中的捕获组序号；仅 `emit_all_groups` 规则按分组输出时为 Some
+    pub(crate) group_index: Option<usize>,
+    /// 产生该命中的扫描路径（诊断用，仅 `with_source` 时输出）
+    pub(crate) source: FindingSource,
+    /// 文件相对所在输入根目录的路径（`/` 分隔）；仅 `emit_path` 时由 Writer 端填写
+    pub(crate) path: Option<String>,
+}
+
+/// 产生命中的扫描路径（排查分块边界、窗口等偏移问题时区分结果来源）
+/// - SmallFile：整读的缓冲区上对整个缓冲区运行（小文件整缓冲区扫描、窗口预算回退、非正则检测器、UTF-8 引擎整读）
+/// - Window：整读的缓冲区上按锚点窗口运行
+/// - Chunked：大文件分块路径（块内同样可能按窗口运行，统一记为分块）
+/// - Strings：二进制文件的可打印串扫描（`BinaryMode::Strings`，整读与分块均是）
+/// - Base64：内嵌 base64 串解码后的内容（`decode_base64`，整读与分块均是）
+///
+/// 当前构建没有归档 / 解压路径，因此没有对应的来源。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum FindingSource {
+    #[default]
+    SmallFile,
+    Window,
+    Chunked,
+    Strings,
+    Base64,
+}
+
+impl FindingSource {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            FindingSource::SmallFile => "small_file",
+            FindingSource::Window => "window",
+            FindingSource::Chunked => "chunked",
+            FindingSource::Strings => "strings",
+            FindingSource::Base64 => "base64",
+        }
+    }
+}
+
+/// 文件内按 value 去重的收集器：同一 value 保留 start_offset 最小的命中（偏移相同保留 rule_id 较小者），
+/// 使上报偏移与规则 id 与窗口 / 规则 / 分块的遍历顺序无关。
+/// 按出现去重（`per_occurrence`，见 `ScanOptions::no_dedup`）时以 (value, start_offset) 为键：
+/// 同一 value 的每次出现都保留，仅合并重叠窗口 / 分块重叠区 / 多条规则对同一出现的重复命中。
+/// `normalize`（见 `ScanOptions::normalize_dedup`）时以去掉首尾空白与引号后的值为键，输出的仍是原始值
+#[derive(Default)]
+pub(crate) struct DedupByValue {
+    /// (value, 偏移) -> items 中的下标；按 value 去重时偏移恒为 0
+    index: HashMap<(String, usize), usize>,
+    items: Vec<Finding>,
+    per_occurrence: bool,
+    normalize: bool,
+}
+
+impl DedupByValue {
+    pub(crate) fn new(per_occurrence: bool, normalize: bool) -> Self {
+        Self { per_occurrence, normalize, ..Default::default() }
+    }
+
+    fn key(&self, f: &Finding) -> (String, usize) {
+        (dedup_key(&f.value, self.normalize).to_string(), if self.per_occurrence { f.start_offset } else { 0 })
+    }
+
+    pub(crate) fn push(&mut self, f: Finding) {
+        let key = self.key(&f);
+        match self.index.get(&key) {
+            Some(&i) => {
+                let cur = &self.items[i];
+                if (f.start_offset, &f.rule_id) < (cur.start_offset, &cur.rule_id) { self.items[i] = f; }
+            }
+            None => {
+                self.index.insert(key, self.items.len());
+                self.items.push(f);
+            }
+        }
+    }
+
+    pub(crate) fn extend(&mut self, findings: impl IntoIterator<Item = Finding>) {
+        for f in findings { self.push(f); }
+    }
+
+    pub(crate) fn into_vec(self) -> Vec<Finding> {
+        self.items
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// 取出 start_offset 小于 `offset` 的命中，其余保留（分块扫描中提前输出已确定的命中）
+    pub(crate) fn take_before(&mut self, offset: usize) -> Vec<Finding> {
+        let (ready, rest): (Vec<Finding>, Vec<Finding>) = std::mem::take(&mut self.items).into_iter().partition(|f| f.start_offset < offset);
+        self.index = rest.iter().enumerate().map(|(i, f)| (self.key(f), i)).collect();
+        self.items = rest;
+        ready
+    }
+}
+
+/// 文件内去重之前的原始命中位置 (start_offset, rule_id)
+#[derive(Default)]
+pub(crate) struct RawHits(Vec<(usize, String)>);
+
+impl RawHits {
+    pub(crate) fn record(&mut self, findings: &[Finding]) {
+        self.0.extend(findings.iter().map(|f| (f.start_offset, f.rule_id.clone())));
+    }
+}
+
+/// 单个文件（缓冲区）的扫描结果
+#[derive(Debug, Default)]
+pub(crate) struct FileScan {
+    pub(crate) findings: Vec<Finding>,
+    /// 判定为二进制并按 `BinaryMode::Skip` 整体跳过时为被跳过的字节数（文件大小）
+    pub(crate) binary_skipped: Option<u64>,
+    /// 判定为 UTF-16 文本并转码为 UTF-8 后扫描时为其字节序（命中偏移已还原为原始字节偏移）
+    pub(crate) utf16: Option<Utf16Order>,
+    /// 文件（内容）总大小
+    pub(crate) file_size: u64,
+    /// 实际送入检测器的字节数：整个文件、局部扫描的区域或取消前已读取的部分；按二进制跳过时为 0
+    pub(crate) scanned_bytes: u64,
+    /// 原始命中数（文件内去重之前，分块重叠区的重复命中只计一次）
+    pub(crate) candidates: usize,
+    /// 原始命中位置明细（按偏移升序），仅 `hit_positions` 开启时保留
+    pub(crate) hits: Option<Vec<(usize, String)>>,
+    /// 压缩包中未能扫描的条目（`<压缩包路径>!<条目路径>` 与原因），见 `archives` 模块
+    pub(crate) skipped_entries: Vec<(PathBuf, SkipReason)>,
+    /// 文件内容摘要（`HashMode::Sha256` / `Md5` 时为 Some），即该文件命中的 file_hash
+    pub(crate) content_hash: Option<String>,
+    /// 达到单文件命中数上限（`ScanOptions::max_matches_per_file`）后提前停止，命中被截断
+    pub(crate) truncated: bool,
+    /// 扫描前的文件大小与修改时间（仅 `state_path` 时记录，见 `state` 模块）
+    pub(crate) stamp: Option<FileStamp>,
+    /// 文件未变化，结果复用自上次的状态文件而非重新扫描
+    pub(crate) reused: bool,
+}
+
+impl FileScan {
+    /// 由去重后的命中与原始命中位置构造；`keep_hits` 为 false 时仅保留计数
+    pub(crate) fn new(findings: Vec<Finding>, raw: RawHits, keep_hits: bool) -> Self {
+        let mut hits = raw.0;
+        hits.sort_unstable();
+        hits.dedup();
+        Self { findings, candidates: hits.len(), hits: keep_hits.then_some(hits), ..Default::default() }
+    }
+
+    /// 并入压缩包中一个条目的扫描结果：命中与计数累加；各条目的偏移互不可比，不保留原始命中位置明细
+    pub(crate) fn absorb(&mut self, entry: FileScan) {
+        self.findings.extend(entry.findings);
+        if let Some(bytes) = entry.binary_skipped { *self.binary_skipped.get_or_insert(0) += bytes; }
+        self.utf16 = self.utf16.or(entry.utf16);
+        self.scanned_bytes += entry.scanned_bytes;
+        self.candidates += entry.candidates;
+        self.truncated |= entry.truncated;
+        self.skipped_entries.extend(entry.skipped_entries);
+    }
+
+    /// 记录送入检测器的字节数（见 `scanned_bytes`）
+    pub(crate) fn with_scanned(mut self, bytes: usize) -> Self {
+        self.scanned_bytes = bytes as u64;
+        self
+    }
+
+    /// 应用单文件命中数上限：超过 `cap` 时按文件内稳定顺序只保留前 `cap` 条；
+    /// `reached` 表示扫描已因达到上限而提前停止，同样记为截断
+    pub(crate) fn capped(mut self, cap: Option<usize>, reached: bool) -> Self {
+        let Some(cap) = cap else { return self };
+        if self.findings.len() > cap {
+            sort_findings_stable(&mut self.findings);
+            self.findings.truncate(cap);
+            self.truncated = true;
+        }
+        self.truncated |= reached;
+        self
+    }
+
+    /// 命中偏移整体加上 `by`（局部扫描时把区域内偏移还原为文件偏移）
+    pub(crate) fn shift_offsets(mut self, by: usize) -> Self {
+        if by == 0 { return self; }
+        for f in &mut self.findings { f.start_offset += by; }
+        if let Some(hits) = &mut self.hits {
+            for h in hits { h.0 += by; }
+        }
+        self
+    }
+
+    /// 命中偏移逐个按 `map` 换算（转码扫描时把转码后偏移还原为原始偏移；`map` 须单调不减）
+    pub(crate) fn map_offsets(mut self, map: impl Fn(usize) -> usize) -> Self {
+        for f in &mut self.findings { f.start_offset = map(f.start_offset); }
+        if let Some(hits) = &mut self.hits {
+            for h in hits { h.0 = map(h.0); }
+        }
+        self
+    }
 }
 
-/// 对单文件命中进行稳定排序：起始偏移升序 → 长度降序 → 值字典序升序
-pub(crate) fn sort_findings_stable(findings: &mut Vec<Finding>) {
+/// 去重键：`normalize` 时去掉首尾的空白与引号（`"` `'` `` ` ``），使 `ghp_abc` 与 `"ghp_abc"` 视为同一值；
+/// 去掉后为空时保留原值
+pub(crate) fn dedup_key(value: &str, normalize: bool) -> &str {
+    if !normalize { return value; }
+    let trimmed = value.trim_matches(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '`'));
+    if trimmed.is_empty() { value } else { trimmed }
+}
+
+/// 按 value 去重，保留每个 value 偏移最小的命中；`per_occurrence` 时仅合并同一出现的重复命中；
+/// `normalize` 时按 `dedup_key` 归一化后的值去重
+pub(crate) fn dedup_keep_earliest(findings: Vec<Finding>, per_occurrence: bool, normalize: bool) -> Vec<Finding> {
+    let mut d = DedupByValue::new(per_occurrence, normalize);
+    d.extend(findings);
+    d.into_vec()
+}
+
+/// 对单文件命中进行稳定排序：起始偏移升序 → 长度降序 → 值字典序升序 → 规则 id 升序
+/// 最后一级保证不同规则在同一偏移产生相同值时，输出顺序与规则遍历顺序无关
+pub(crate) fn sort_findings_stable(findings: &mut [Finding]) {
     findings.sort_by(|a, b| {
         use std::cmp::Ordering;
         match a.start_offset.cmp(&b.start_offset) {
             Ordering::Equal => match b.value.len().cmp(&a.value.len()) {
-                Ordering::Equal => a.value.cmp(&b.value),
+                Ordering::Equal => match a.value.cmp(&b.value) {
+                    Ordering::Equal => a.rule_id.cmp(&b.rule_id),
+                    o => o,
+                },
                 o => o,
             },
             o => o,
@@ -22,6 +253,66 @@ pub(crate) fn sort_findings_stable(findings: &mut Vec<Finding>) {
     });
 }
 
+/// 文件内被包含命中的抑制（见 `ScanOptions::suppress_overlaps`）：命中区间为 `[start_offset, start_offset + value 字节长度)`，
+/// 完全落在同一 file_hash 已保留命中的区间内时丢弃；区间相同时保留规则名次靠前（`rank` 较小）的一条。
+/// 分块扫描中命中按批次先行输出，因此跨批次保留每个 file_hash 已保留命中的最远结束偏移，文件结束时 `reset`
+#[derive(Default)]
+pub(crate) struct OverlapFilter {
+    reach: HashMap<String, usize>,
+}
+
+impl OverlapFilter {
+    /// 过滤一批命中（须已按 `sort_findings_stable` 排序，且起始偏移不早于此前各批次），返回丢弃的条数
+    pub(crate) fn retain(&mut self, findings: &mut Vec<Finding>, rank: impl Fn(&str) -> usize) -> usize {
+        let before = findings.len();
+        let mut kept: Vec<Finding> = Vec::with_capacity(before);
+        for f in findings.drain(..) {
+            let end = f.start_offset + f.value.len();
+            // 区间相同的命中在排序中相邻（同一起点），只需回看同一起点的已保留命中
+            let twin = kept
+                .iter()
+                .rev()
+                .take_while(|k| k.start_offset == f.start_offset)
+                .position(|k| k.file_hash == f.file_hash && k.value.len() == f.value.len())
+                .map(|i| kept.len() - 1 - i);
+            if let Some(i) = twin {
+                if rank(&f.rule_id) < rank(&kept[i].rule_id) { kept[i] = f; }
+                continue;
+            }
+            let reach = self.reach.entry(f.file_hash.clone()).or_insert(0);
+            if end <= *reach { continue; }
+            *reach = end;
+            kept.push(f);
+        }
+        *findings = kept;
+        before - findings.len()
+    }
+
+    /// 当前文件结束（不同文件可能有相同的 file_hash）
+    pub(crate) fn reset(&mut self) {
+        self.reach.clear();
+    }
+}
+
+/// 按规则优先级认领命中区间（见 `RuleEntry::priority`）：同一窗口内规则按优先级降序运行，
+/// 规则的命中与已被更高优先级规则认领的区间重叠时丢弃，保留的命中区间记为该优先级认领；
+/// 优先级相同的规则互不影响
+#[derive(Default)]
+pub(crate) struct SpanClaims(Vec<(usize, usize, i32)>);
+
+impl SpanClaims {
+    /// 结算一条规则新产生的命中（`findings[from..]`），规则优先级为 `priority`
+    pub(crate) fn settle(&mut self, findings: &mut Vec<Finding>, from: usize, priority: i32) {
+        let fresh = findings.split_off(from);
+        for f in fresh {
+            let (start, end) = (f.start_offset, f.start_offset + f.value.len());
+            if self.0.iter().any(|&(s, e, p)| p > priority && start < e && s < end) { continue; }
+            self.0.push((start, end, priority));
+            findings.push(f);
+        }
+    }
+}
+
 // 供其他模块使用
 pub(crate) use Finding as FindingPublic;
 
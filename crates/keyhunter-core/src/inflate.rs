@@ -0,0 +1,204 @@
+//! DEFLATE 解压（RFC 1951），供压缩包扫描（zip 条目、gzip）在内存中还原内容
+//!
+//! 按规范式 Huffman 码逐位解码（实现思路同 zlib 的 puff），不追求吞吐；
+//! 输出超过上限时立即报错，避免解压炸弹耗尽内存。
+use anyhow::{bail, Result};
+
+/// 最大码长
+const MAX_BITS: usize = 15;
+
+/// 长度码 257..285 的基础长度与额外位数
+const LEN_BASE: [u16; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+const LEN_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+/// 距离码 0..29 的基础距离与额外位数
+const DIST_BASE: [u16; 30] = [1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577];
+const DIST_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+/// 动态块中码长码的传输顺序
+const CL_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+/// 规范式 Huffman 码表：各码长的码数与按码排列的符号
+struct Huffman {
+    count: [u16; MAX_BITS + 1],
+    symbol: Vec<u16>,
+}
+
+impl Huffman {
+    /// 由各符号的码长构建（码长 0 表示不使用该符号）；超额分配的码长集合视为损坏
+    fn new(lengths: &[u8]) -> Result<Self> {
+        let mut count = [0u16; MAX_BITS + 1];
+        for &l in lengths { count[l as usize] += 1; }
+        let mut left: i32 = 1;
+        for &c in &count[1..] {
+            left = (left << 1) - c as i32;
+            if left < 0 { bail!("invalid deflate stream: over-subscribed code lengths"); }
+        }
+        let mut offs = [0u16; MAX_BITS + 2];
+        for len in 1..=MAX_BITS { offs[len + 1] = offs[len] + count[len]; }
+        let mut symbol = vec![0u16; lengths.len()];
+        for (sym, &l) in lengths.iter().enumerate() {
+            if l != 0 {
+                symbol[offs[l as usize] as usize] = sym as u16;
+                offs[l as usize] += 1;
+            }
+        }
+        Ok(Self { count, symbol })
+    }
+}
+
+/// 解压状态：输入位流与输出缓冲
+struct Inflater<'a> {
+    input: &'a [u8],
+    pos: usize,
+    bit_buf: u32,
+    bit_cnt: u32,
+    out: Vec<u8>,
+    limit: usize,
+}
+
+impl Inflater<'_> {
+    /// 读取 `n` 位（低位在前）
+    fn bits(&mut self, n: u32) -> Result<u32> {
+        while self.bit_cnt < n {
+            let Some(&b) = self.input.get(self.pos) else { bail!("invalid deflate stream: unexpected end of input") };
+            self.pos += 1;
+            self.bit_buf |= (b as u32) << self.bit_cnt;
+            self.bit_cnt += 8;
+        }
+        let v = self.bit_buf & ((1u32 << n) - 1);
+        self.bit_buf >>= n;
+        self.bit_cnt -= n;
+        Ok(v)
+    }
+
+    /// 逐位解码一个符号
+    fn decode(&mut self, h: &Huffman) -> Result<u16> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..=MAX_BITS {
+            code |= self.bits(1)? as i32;
+            let count = h.count[len] as i32;
+            if code - count < first {
+                return Ok(h.symbol[(index + (code - first)) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        bail!("invalid deflate stream: bad huffman code")
+    }
+
+    fn push(&mut self, b: u8) -> Result<()> {
+        if self.out.len() >= self.limit { bail!(LimitExceeded); }
+        self.out.push(b);
+        Ok(())
+    }
+
+    /// 未压缩块
+    fn stored(&mut self) -> Result<()> {
+        // 丢弃当前字节内剩余的位
+        self.bit_buf = 0;
+        self.bit_cnt = 0;
+        let Some(hdr) = self.input.get(self.pos..self.pos + 4) else { bail!("invalid deflate stream: truncated stored block") };
+        let len = u16::from_le_bytes([hdr[0], hdr[1]]);
+        let nlen = u16::from_le_bytes([hdr[2], hdr[3]]);
+        if len != !nlen { bail!("invalid deflate stream: stored block length mismatch"); }
+        self.pos += 4;
+        let Some(data) = self.input.get(self.pos..self.pos + len as usize) else { bail!("invalid deflate stream: truncated stored block") };
+        if self.out.len() + data.len() > self.limit { bail!(LimitExceeded); }
+        self.out.extend_from_slice(data);
+        self.pos += len as usize;
+        Ok(())
+    }
+
+    /// 按给定码表解码一个压缩块
+    fn codes(&mut self, lit: &Huffman, dist: &Huffman) -> Result<()> {
+        loop {
+            let sym = self.decode(lit)? as usize;
+            match sym {
+                0..=255 => self.push(sym as u8)?,
+                256 => return Ok(()),
+                257..=285 => {
+                    let i = sym - 257;
+                    let len = LEN_BASE[i] as usize + self.bits(LEN_EXTRA[i] as u32)? as usize;
+                    let d = self.decode(dist)? as usize;
+                    if d >= 30 { bail!("invalid deflate stream: bad distance code"); }
+                    let distance = DIST_BASE[d] as usize + self.bits(DIST_EXTRA[d] as u32)? as usize;
+                    if distance > self.out.len() { bail!("invalid deflate stream: distance too far back"); }
+                    if self.out.len() + len > self.limit { bail!(LimitExceeded); }
+                    let start = self.out.len() - distance;
+                    // 允许重叠复制（distance < len 时重复最近的字节）
+                    for k in 0..len {
+                        let b = self.out[start + k];
+                        self.out.push(b);
+                    }
+                }
+                _ => bail!("invalid deflate stream: bad literal/length code"),
+            }
+        }
+    }
+
+    /// 固定 Huffman 码块
+    fn fixed(&mut self) -> Result<()> {
+        let mut lengths = [0u8; 288];
+        lengths[..144].fill(8);
+        lengths[144..256].fill(9);
+        lengths[256..280].fill(7);
+        lengths[280..].fill(8);
+        let lit = Huffman::new(&lengths)?;
+        let dist = Huffman::new(&[5u8; 30])?;
+        self.codes(&lit, &dist)
+    }
+
+    /// 动态 Huffman 码块
+    fn dynamic(&mut self) -> Result<()> {
+        let nlen = self.bits(5)? as usize + 257;
+        let ndist = self.bits(5)? as usize + 1;
+        let ncode = self.bits(4)? as usize + 4;
+        if nlen > 286 || ndist > 30 { bail!("invalid deflate stream: bad code counts"); }
+        let mut cl = [0u8; 19];
+        for &idx in &CL_ORDER[..ncode] { cl[idx] = self.bits(3)? as u8; }
+        let cl_code = Huffman::new(&cl)?;
+        let mut lengths = vec![0u8; nlen + ndist];
+        let mut i = 0;
+        while i < nlen + ndist {
+            let sym = self.decode(&cl_code)?;
+            let (value, repeat) = match sym {
+                0..=15 => (sym as u8, 1),
+                16 => {
+                    if i == 0 { bail!("invalid deflate stream: repeat with no previous length"); }
+                    (lengths[i - 1], 3 + self.bits(2)? as usize)
+                }
+                17 => (0, 3 + self.bits(3)? as usize),
+                _ => (0, 11 + self.bits(7)? as usize),
+            };
+            if i + repeat > nlen + ndist { bail!("invalid deflate stream: too many code lengths"); }
+            lengths[i..i + repeat].fill(value);
+            i += repeat;
+        }
+        if lengths[256] == 0 { bail!("invalid deflate stream: missing end-of-block code"); }
+        let lit = Huffman::new(&lengths[..nlen])?;
+        let dist = Huffman::new(&lengths[nlen..])?;
+        self.codes(&lit, &dist)
+    }
+}
+
+/// 解压结果超过上限
+#[derive(Debug, thiserror::Error)]
+#[error("decompressed size exceeds the limit")]
+pub(crate) struct LimitExceeded;
+
+/// 解压一个完整的 DEFLATE 流，返回解压内容与消耗的输入字节数；
+/// 输出超过 `limit` 字节时返回 `LimitExceeded`，流损坏时返回其它错误
+pub(crate) fn inflate(input: &[u8], limit: usize) -> Result<(Vec<u8>, usize)> {
+    let mut s = Inflater { input, pos: 0, bit_buf: 0, bit_cnt: 0, out: Vec::new(), limit };
+    loop {
+        let last = s.bits(1)? == 1;
+        match s.bits(2)? {
+            0 => s.stored()?,
+            1 => s.fixed()?,
+            2 => s.dynamic()?,
+            _ => bail!("invalid deflate stream: bad block type"),
+        }
+        if last { break; }
+    }
+    Ok((s.out, s.pos))
+}
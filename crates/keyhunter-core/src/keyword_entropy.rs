@@ -0,0 +1,101 @@
+//! 关键字邻近的高熵串检测（`kind = "entropy"` 规则）
+//!
+//! 单纯按熵判定的检测器在哈希、UUID、压缩代码上误报很多。该检测器只在关键字（如 key / token /
+//! secret / password，大小写不敏感）之后才查找高熵串：
+//! - 以规则的关键字列表构建 AC 自动机定位关键字（加载规则时构建一次）；
+//! - 依次检查关键字结束后 `max_distance` 字节内开始的“值串”（以字母数字开头、由 `[A-Za-z0-9+/=_.-]`
+//!   组成的极大连续段），报告第一个长度 ≥ `min_length` 且香农熵 ≥ `min_entropy` 的值串；
+//!   紧接关键字的标识符剩余部分（如 `key_id` 中的 `id`）同样参与判定，通常因过短被跳过；
+//! - 每个关键字至多报告一个值；超过 4096 字节的值串视为数据块而非密钥，不报告。
+//!
+//! 与正则规则互补，不经过全局 AC 预筛。
+use aho_corasick::{AhoCorasick, AhoCorasickBuilder, MatchKind};
+use anyhow::Result;
+use std::path::Path;
+
+use crate::entropy::{shannon_entropy, EntropyAlphabet};
+use crate::findings::{FindingPublic as Finding, FindingSource};
+use crate::rules::RuleSpec;
+
+/// 默认关键字（规则未声明 keywords 时使用）
+pub(crate) const DEFAULT_ENTROPY_KEYWORDS: &[&str] = &["key", "token", "secret", "password"];
+/// 默认关键字与值串起点的最大距离（字节）
+pub(crate) const DEFAULT_ENTROPY_MAX_DISTANCE: usize = 32;
+/// 默认最小值长度
+pub(crate) const DEFAULT_ENTROPY_MIN_LENGTH: usize = 20;
+/// 默认最小香农熵（bits/符号）
+pub(crate) const DEFAULT_ENTROPY_MIN_ENTROPY: f64 = 3.5;
+/// 值串最大长度：更长的连续段视为数据块（base64 附件等）
+const MAX_RUN_LEN: usize = 4096;
+
+/// entropy 规则参数
+#[derive(Debug, Clone)]
+pub(crate) struct EntropyParams {
+    /// 关键字自动机（ASCII 大小写不敏感）
+    pub keywords: AhoCorasick,
+    pub max_distance: usize,
+    pub min_length: usize,
+    pub min_entropy: f64,
+    /// 熵计算的符号空间（缺省 bytes）
+    pub entropy_alphabet: EntropyAlphabet,
+}
+
+impl EntropyParams {
+    pub(crate) fn new(keywords: &[String], max_distance: usize, min_length: usize, min_entropy: f64, entropy_alphabet: EntropyAlphabet) -> Result<Self> {
+        let keywords = AhoCorasickBuilder::new()
+            .ascii_case_insensitive(true)
+            .match_kind(MatchKind::LeftmostLongest)
+            .build(keywords)?;
+        Ok(Self { keywords, max_distance, min_length, min_entropy, entropy_alphabet })
+    }
+}
+
+/// 在缓冲区中运行适用于该文件的 entropy 规则（非 entropy 规则被忽略）
+/// - `base_offset`：缓冲区在原文件中的起始偏移
+/// - 不去重：同一 value 的每次出现都返回，由调用方在记录原始命中位置后统一去重
+pub(crate) fn scan_keyword_entropy(buf: &[u8], base_offset: usize, file_hash: &str, path: &Path, rules: &[RuleSpec]) -> Vec<Finding> {
+    let mut findings: Vec<Finding> = Vec::new();
+    for spec in rules.iter().filter(|r| r.applies_to(path)) {
+        let params = match &spec.entropy { Some(p) => p, None => continue };
+        for m in params.keywords.find_iter(buf) {
+            let qualifies = |v: &[u8]| v.len() >= params.min_length && v.len() <= MAX_RUN_LEN && shannon_entropy(v, params.entropy_alphabet) >= params.min_entropy;
+            let (start, end) = match values_after(buf, m.end(), params.max_distance).find(|&(s, e)| qualifies(&buf[s..e])) {
+                Some(v) => v,
+                None => continue,
+            };
+            let value = &buf[start..end];
+            findings.push(Finding {
+                file_hash: file_hash.to_string(),
+                value: String::from_utf8_lossy(value).to_string(),
+                start_offset: base_offset + start,
+                rule_id: spec.id.clone(),
+                validated: None,
+                lines: None,
+                context: None,
+                confidence: None,
+                group_index: None,
+                source: FindingSource::default(),
+                path: None,
+            });
+        }
+    }
+    findings
+}
+
+/// 关键字结束位置 `from` 之后 `max_distance` 字节内开始的各个值串 (起点, 终点)，按位置先后；
+/// 值串长度至多统计到 `MAX_RUN_LEN + 1`（更长的一律视为过长）
+fn values_after(buf: &[u8], from: usize, max_distance: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+    let limit = from.saturating_add(max_distance).min(buf.len());
+    let mut at = from;
+    std::iter::from_fn(move || {
+        let start = at + buf.get(at..limit)?.iter().position(|b| b.is_ascii_alphanumeric())?;
+        let len = buf[start..].iter().take(MAX_RUN_LEN + 1).take_while(|&&b| is_value_byte(b)).count();
+        at = start + len;
+        Some((start, start + len))
+    })
+}
+
+/// 值串字符：字母数字与 base64 / URL 安全 base64 / 常见 token 分隔符
+fn is_value_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || matches!(b, b'+' | b'/' | b'=' | b'_' | b'.' | b'-')
+}
@@ -15,9 +15,53 @@ mod rules;
 mod prefilter;
 mod engine_bytes;
 mod engine_utf8;
+mod engine_strings;
 mod scan;
+mod metadata;
+mod sink;
+mod concat;
+mod base64;
+mod suppress;
+mod comments;
+mod explain;
+mod env;
+mod entropy;
+mod der;
+mod url;
+mod block;
+mod location;
+mod ruletest;
+mod validate;
+mod lru;
+mod anchor_cache;
+mod state;
+mod remote;
+mod encoding;
+mod region;
+mod mmap;
+mod score;
+mod audit;
+mod baseline;
+mod digest;
+mod keyword_entropy;
+mod proximity;
+mod rulecheck;
+mod diff;
+mod walkfilter;
+mod archives;
+mod inflate;
 
 // 对外暴露与原 API 保持一致
-pub use options::{ScanOptions, ScanEngine, ScanStats};
-pub use types::OutputItem;
-pub use scan::scan_and_write;
+pub use options::{ScanOptions, ScanEngine, ScanStats, BinaryMode, DedupScope, FileOrder, HashMode, OutputFormat, ProgressEvent, ProgressFn, RedactMode, SortOrder, DEFAULT_ALLOW_VALUES, DEFAULT_BINARY_PRINTABLE_THRESHOLD, DEFAULT_MAX_PEM_WINDOWS, DEFAULT_SMALL_FILE_FULLSCAN_THRESHOLD, DEFAULT_MAX_VALUE_LEN, DEFAULT_REGEX_SIZE_LIMIT, DEFAULT_REORDER_WINDOW, DEFAULT_WINDOW_AFTER, DEFAULT_WINDOW_BEFORE};
+pub use types::{CrossFileReport, HitPosition, HitPositionReport, OutputItem, OwnedOutputItem, ScanReport, ScanResult, Severity, SkipReason, SkippedFile, OUTPUT_FIELDS};
+pub use scan::{scan_and_write, scan_and_write_report, scan_bytes_and_write_report, scan_reader, scan_roots_and_write_report, scan_roots_to_dir, scan_roots_to_targets, scan_bytes_to_targets, scan_to_result};
+pub use metadata::{build_run_metadata, RunMetadata};
+pub use prefilter::{analyze_prefilter, DeadRule, PlanDiagnostics, PrefilterPlan};
+pub use audit::{audit_rules, AuditReport, RedundantRulePair};
+pub use rulecheck::{check_rules, BrokenRule};
+pub use baseline::Baseline;
+pub use diff::{diff_results, load_results, ResultDiff};
+pub use sink::{check_field_renames, mask_value, OutputTarget, JSON_SCHEMA};
+pub use explain::{describe_plan, load_prefilter_plan, PlanReport, RulePlanEntry};
+pub use validate::{ValidationMode, Validator};
+pub use ruletest::{classify, classify_with_plan, load_rule_test_cases, run_rule_examples, run_rule_tests, RuleExampleOutcome, RuleMatch, RuleTestCase, RuleTestMatch, RuleTestOutcome};
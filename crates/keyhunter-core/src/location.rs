@@ -0,0 +1,73 @@
+//! 命中的行号范围（`--with-location`）与上下文（`--context-lines` / `--context-bytes`）
+//!
+//! start_line 为命中起始偏移所在行，end_line 为 start_line 加上命中值内的换行数，
+//! 便于定位跨多行的命中（如 PEM 私钥块）；行号从 1 开始，单行命中两者相同。
+use crate::findings::FindingPublic as Finding;
+use crate::options::ScanOptions;
+use crate::sink::mask_value;
+
+/// 为缓冲区内的命中填写行号范围
+/// - `base_offset`：缓冲区在原文件中的起始偏移；`base_line`：缓冲区之前的换行数
+/// - 偏移不在缓冲区内的命中保持不变
+pub(crate) fn annotate_lines(buf: &[u8], base_offset: usize, base_line: usize, findings: &mut [Finding]) {
+    if findings.is_empty() { return; }
+    let newlines: Vec<usize> = memchr::memchr_iter(b'\n', buf).collect();
+    for f in findings.iter_mut() {
+        let pos = match f.start_offset.checked_sub(base_offset) { Some(p) if p <= buf.len() => p, _ => continue };
+        let start_line = base_line + newlines.partition_point(|&nl| nl < pos) + 1;
+        let end_line = start_line + f.value.matches('\n').count();
+        f.lines = Some((start_line, end_line));
+    }
+}
+
+/// 缓冲区中的换行数（分块扫描时累计丢弃前缀的行数）
+pub(crate) fn count_lines(buf: &[u8]) -> usize {
+    memchr::memchr_iter(b'\n', buf).count()
+}
+
+/// 按扫描选项为缓冲区内的命中填写上下文：`context_lines` 优先，其次 `context_bytes`，均未设置时不填写
+pub(crate) fn annotate_context_by(buf: &[u8], base_offset: usize, opts: &ScanOptions, findings: &mut [Finding]) {
+    if let Some(n) = opts.context_lines {
+        annotate_context(buf, base_offset, n, findings);
+    } else if opts.context_bytes > 0 {
+        annotate_context_bytes(buf, base_offset, opts.context_bytes, findings);
+    }
+}
+
+/// 为缓冲区内的命中填写上下文：命中前后各至多 `n` 字节（含命中本身），在缓冲区边界处截断；
+/// 截断处可能切开多字节字符，按 `from_utf8_lossy` 替换；上下文中的命中值按 `mask_value` 脱敏
+/// - `base_offset`：缓冲区在原文件中的起始偏移；偏移不在缓冲区内的命中保持不变
+pub(crate) fn annotate_context_bytes(buf: &[u8], base_offset: usize, n: usize, findings: &mut [Finding]) {
+    for f in findings.iter_mut() {
+        let pos = match f.start_offset.checked_sub(base_offset) { Some(p) if p <= buf.len() => p, _ => continue };
+        let end = (pos + f.value.len()).min(buf.len());
+        let text = String::from_utf8_lossy(&buf[pos.saturating_sub(n)..(end + n).min(buf.len())]);
+        f.context = Some(text.replace(f.value.as_str(), &mask_value(&f.value)));
+    }
+}
+
+/// 为缓冲区内的命中填写上下文：命中所在行（跨多行的命中取全部行）及前后各 `n` 行，
+/// 在缓冲区边界处截断；上下文中的命中值按 `mask_value` 脱敏
+/// - `base_offset`：缓冲区在原文件中的起始偏移；偏移不在缓冲区内的命中保持不变
+pub(crate) fn annotate_context(buf: &[u8], base_offset: usize, n: usize, findings: &mut [Finding]) {
+    for f in findings.iter_mut() {
+        let pos = match f.start_offset.checked_sub(base_offset) { Some(p) if p <= buf.len() => p, _ => continue };
+        let end = (pos + f.value.len()).min(buf.len());
+        // 起点：命中所在行行首，再向前 n 行
+        let mut start = memchr::memrchr(b'\n', &buf[..pos]).map(|i| i + 1).unwrap_or(0);
+        for _ in 0..n {
+            if start == 0 { break; }
+            start = memchr::memrchr(b'\n', &buf[..start - 1]).map(|i| i + 1).unwrap_or(0);
+        }
+        // 终点（不含换行）：命中末尾所在行行尾，再向后 n 行
+        let mut stop = memchr::memchr(b'\n', &buf[end..]).map(|i| end + i).unwrap_or(buf.len());
+        for _ in 0..n {
+            if stop >= buf.len() { break; }
+            stop = memchr::memchr(b'\n', &buf[stop + 1..]).map(|i| stop + 1 + i).unwrap_or(buf.len());
+        }
+        // 缓冲区以换行结尾时不把其后的空行计入上下文
+        if stop == buf.len() && stop > end && buf[stop - 1] == b'\n' { stop -= 1; }
+        let text = String::from_utf8_lossy(&buf[start..stop]);
+        f.context = Some(text.replace(f.value.as_str(), &mask_value(&f.value)));
+    }
+}
@@ -0,0 +1,53 @@
+//! 容量受限的 LRU 缓存（懒编译正则缓存使用）
+//!
+//! 接口与 `lru::LruCache` 的 `get` / `put` 子集一致；容量为 None 时不淘汰（等价于普通 HashMap）。
+//! 值以 `Arc` 形式交给调用方：淘汰只释放缓存持有的引用，已取出的克隆继续有效。
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::num::NonZeroUsize;
+
+pub(crate) struct LruCache<K, V> {
+    /// key -> (值, 最近访问序号)
+    map: HashMap<K, (V, u64)>,
+    /// 最近访问序号 -> key（最小者最久未使用）；不限容量时不维护
+    order: BTreeMap<u64, K>,
+    tick: u64,
+    capacity: Option<NonZeroUsize>,
+}
+
+impl<K: Copy + Eq + Hash, V: Clone> LruCache<K, V> {
+    pub(crate) fn new(capacity: Option<NonZeroUsize>) -> Self {
+        Self { map: HashMap::new(), order: BTreeMap::new(), tick: 0, capacity }
+    }
+
+    /// 查找并标记为最近使用
+    pub(crate) fn get(&mut self, key: &K) -> Option<V> {
+        let tick = self.next_tick();
+        let (value, last) = self.map.get_mut(key)?;
+        if self.capacity.is_some() {
+            self.order.remove(last);
+            self.order.insert(tick, *key);
+            *last = tick;
+        }
+        Some(value.clone())
+    }
+
+    /// 插入（或覆盖）并标记为最近使用；超出容量时淘汰最久未使用的条目
+    pub(crate) fn put(&mut self, key: K, value: V) {
+        let tick = self.next_tick();
+        if let Some((_, last)) = self.map.insert(key, (value, tick)) {
+            self.order.remove(&last);
+        }
+        let Some(cap) = self.capacity else { return };
+        self.order.insert(tick, key);
+        while self.map.len() > cap.get() {
+            let Some((_, oldest)) = self.order.pop_first() else { break };
+            self.map.remove(&oldest);
+        }
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+}
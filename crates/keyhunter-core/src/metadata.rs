@@ -0,0 +1,84 @@
+//! 运行元数据（审计用）
+//!
+//! 为每次扫描生成可追溯的标识：运行 ID（UUID v4）、ISO-8601 时间戳、工具版本、
+//! 规则集哈希（规则文件内容的 SHA-256）以及扫描统计。
+use anyhow::Result;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::options::{ScanOptions, ScanStats};
+use crate::remote::read_rules_source;
+use crate::rules::resolve_rules_path;
+
+/// 单次运行的元数据
+#[derive(Debug, Clone, Serialize)]
+pub struct RunMetadata {
+    /// 运行 ID（UUID v4）
+    pub run_id: String,
+    /// 扫描开始时间（UTC，ISO-8601）
+    pub timestamp: String,
+    /// 工具版本（crate 版本号）
+    pub tool_version: String,
+    /// 规则集哈希（规则文件内容 SHA-256，十六进制；命名规则集为按顺序拼接的 `名称\0内容` 的哈希）
+    pub ruleset_hash: String,
+    /// 扫描统计
+    pub stats: ScanStats,
+}
+
+/// 构建运行元数据
+/// - `started`：扫描开始时刻（由调用方在扫描前记录）
+pub fn build_run_metadata(opts: &ScanOptions, stats: &ScanStats, started: SystemTime) -> Result<RunMetadata> {
+    Ok(RunMetadata {
+        run_id: uuid::Uuid::new_v4().to_string(),
+        timestamp: format_iso8601_utc(started),
+        tool_version: env!("CARGO_PKG_VERSION").to_string(),
+        ruleset_hash: ruleset_hash(opts)?,
+        stats: stats.clone(),
+    })
+}
+
+/// 规则集哈希：规则文件内容的 SHA-256；命名规则集为按顺序拼接的 `名称\0内容` 的哈希
+pub(crate) fn ruleset_hash(opts: &ScanOptions) -> Result<String> {
+    let bytes = if opts.rule_sets.is_empty() {
+        read_rules_source(&resolve_rules_path(opts.rules_path.as_deref()), opts.rules_cache.as_deref())?.into_bytes()
+    } else {
+        let mut bytes = Vec::new();
+        for (name, path) in &opts.rule_sets {
+            bytes.extend_from_slice(name.as_bytes());
+            bytes.push(0);
+            bytes.extend(read_rules_source(path, None)?.into_bytes());
+        }
+        bytes
+    };
+    Ok(hex_sha256(&bytes))
+}
+
+/// 计算 SHA-256 并以小写十六进制返回
+pub(crate) fn hex_sha256(bytes: &[u8]) -> String {
+    let digest = Sha256::digest(bytes);
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 将系统时间格式化为 ISO-8601（UTC，秒级精度），如 `2025-11-05T08:30:00Z`
+fn format_iso8601_utc(t: SystemTime) -> String {
+    let secs = t.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0) as i64;
+    let days = secs.div_euclid(86_400);
+    let rem = secs.rem_euclid(86_400);
+    let (y, m, d) = civil_from_days(days);
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", y, m, d, rem / 3600, (rem % 3600) / 60, rem % 60)
+}
+
+/// 由 Unix 纪元天数换算公历日期（Howard Hinnant 的 civil_from_days 算法）
+fn civil_from_days(z: i64) -> (i64, u32, u32) {
+    let z = z + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let y = yoe + era * 400;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    (if m <= 2 { y + 1 } else { y }, m, d)
+}
@@ -0,0 +1,109 @@
+//! 内存映射读取（`use_mmap`）：扫描区域不小于 `MMAP_MIN` 的文件整体映射到内存，映射内容直接交给扫描，
+//! 不再复制到堆上的缓冲区
+//!
+//! - 各引擎都只映射原本整读的文件（≤ `SMALL_FILE_MAX`），超大文件仍按原有读取器分块，
+//!   内存占用不随文件大小增长；
+//! - 映射失败（不支持 mmap 的文件系统、非 Unix 平台等）时返回 None，调用方回退到读取路径；
+//! - 扫描结束后重新检查文件长度与修改时间，扫描期间文件被改写 / 截断时结果不可靠，按读取失败跳过该文件；
+//! - 映射期间文件被其他进程截断时，访问超出新长度的页面会触发 SIGBUS 终止进程，此情形无法在进程内恢复，
+//!   因此 mmap 缺省关闭，只建议用于扫描期间不会被改写的文件。
+use anyhow::Result;
+use std::fs::{File, Metadata};
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::options::ScanOptions;
+use crate::region::Region;
+
+/// 使用内存映射的最小扫描区域（字节）；更小的文件映射开销不划算，仍整读
+pub(crate) const MMAP_MIN: u64 = 64 * 1024;
+
+/// 只读映射的文件内容
+pub(crate) struct MappedRegion {
+    map: Mapping,
+    pub(crate) region: Region,
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl MappedRegion {
+    /// 映射文件并计算扫描区域；区域不在 `[MMAP_MIN, max_len]` 内或映射失败时为 None
+    pub(crate) fn open(path: &Path, opts: &ScanOptions, max_len: u64) -> Result<Option<Self>> {
+        let file = File::open(path)?;
+        let meta = file.metadata()?;
+        let region = Region::of(meta.len(), opts);
+        if region.len < MMAP_MIN || region.len > max_len { return Ok(None); }
+        match Mapping::new(&file, meta.len() as usize) {
+            Ok(map) => Ok(Some(Self { map, region, len: meta.len(), modified: meta.modified().ok() })),
+            Err(e) => {
+                tracing::debug!(file = %path.display(), error = %e, "mmap failed, falling back to read");
+                Ok(None)
+            }
+        }
+    }
+
+    /// 扫描区域的内容
+    pub(crate) fn bytes(&self) -> &[u8] {
+        self.region.slice(self.map.as_slice())
+    }
+
+    /// 扫描期间文件未被改写：长度与修改时间均与映射时相同
+    pub(crate) fn check_unchanged(&self, path: &Path) -> Result<()> {
+        let meta: Metadata = std::fs::metadata(path)?;
+        if meta.len() != self.len || meta.modified().ok() != self.modified {
+            return Err(std::io::Error::other("file changed while memory-mapped").into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+struct Mapping {
+    ptr: *mut libc::c_void,
+    len: usize,
+}
+
+// 只读私有映射，不与其他线程共享可变状态
+#[cfg(unix)]
+unsafe impl Send for Mapping {}
+#[cfg(unix)]
+unsafe impl Sync for Mapping {}
+
+#[cfg(unix)]
+impl Mapping {
+    fn new(file: &File, len: usize) -> std::io::Result<Self> {
+        use std::os::unix::io::AsRawFd;
+        // SAFETY: 只读映射一个已打开的文件描述符，长度不超过文件长度（len > 0 由 MMAP_MIN 保证）
+        let ptr = unsafe { libc::mmap(std::ptr::null_mut(), len, libc::PROT_READ, libc::MAP_PRIVATE, file.as_raw_fd(), 0) };
+        if ptr == libc::MAP_FAILED { return Err(std::io::Error::last_os_error()); }
+        Ok(Self { ptr, len })
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        // SAFETY: ptr 指向 len 字节的有效只读映射，生命周期与 self 相同
+        unsafe { std::slice::from_raw_parts(self.ptr as *const u8, self.len) }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        // SAFETY: ptr / len 来自成功的 mmap，且只解除映射一次
+        unsafe { libc::munmap(self.ptr, self.len); }
+    }
+}
+
+/// 非 Unix 平台不支持映射，始终回退到读取路径
+#[cfg(not(unix))]
+struct Mapping;
+
+#[cfg(not(unix))]
+impl Mapping {
+    fn new(_file: &File, _len: usize) -> std::io::Result<Self> {
+        Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "mmap is only supported on unix"))
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        &[]
+    }
+}
@@ -6,47 +6,173 @@
 //! - 精准正则采用懒编译 + 进程内缓存，避免启动期编译整个规则集。
 
 use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::anchor_cache;
+use crate::lru::LruCache;
 
 use aho_corasick::{AhoCorasick, AhoCorasickBuilder};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use tracing::{debug, warn};
 use regex_automata as ra;
 use ra::meta::Regex as MetaRegex;
 
 /// 归一化后的规则（来自 rules.rs 的 RuleSpec）
-use crate::rules::RuleSpec;
+use crate::rules::{has_mixed_priorities, with_ascii_semantics, RuleSpec, ASCII_SEMANTICS_PREFIX};
+use crate::options::ScanOptions;
+use crate::rulecheck::qualified_id;
 
-/// 预筛计划（线程安全，可跨线程共享）
-pub(crate) struct PrefilterPlan {
+/// 预筛计划（线程安全，可跨线程共享；对外不透明，可通过 `describe_plan` 内省）
+pub struct PrefilterPlan {
     /// 全局锚点自动机（按 anchors 的顺序构建）
     pub(crate) ac: AhoCorasick,
     /// 锚点字节序列（与 ac 模式索引一一对应）
     pub(crate) anchors: Vec<Vec<u8>>,
-    /// 锚点索引 -> 规则索引列表（每个规则索引对应 rule_patterns 的下标）
+    /// 锚点索引 -> 规则索引列表（每个规则索引对应 rules 的下标）
     pub(crate) anchor_to_rules: Vec<Vec<usize>>,
-    /// 规则原始模式文本（bytes 正则）
-    pub(crate) rule_patterns: Vec<String>,
+    /// 规则规格（含原始模式文本与元数据，下标即规则索引）
+    pub(crate) rules: Vec<RuleSpec>,
     /// 懒编译后的 regex-automata 元引擎正则缓存（key 为规则索引）
     /// 说明：此处使用 meta::Regex，支持捕获组；使用 Arc 以便跨线程轻量克隆
-    pub(crate) cache: Mutex<HashMap<usize, Arc<MetaRegex>>>,
+    /// 容量见 `ScanOptions::regex_cache_capacity`：超出时淘汰最久未使用的正则，已取出的 Arc 不受影响
+    pub(crate) cache: Mutex<LruCache<usize, Arc<MetaRegex>>>,
+    /// 编译失败（含超出 `regex_size_limit` 被拒绝）的规则索引：不再重复编译，仅首次失败时告警
+    pub(crate) rejected: Mutex<HashSet<usize>>,
+    /// 单条规则正则的 NFA 大小上限（见 `ScanOptions::regex_size_limit`）
+    pub(crate) regex_size_limit: Option<usize>,
+    /// 宽泛锚点的次级字面量守卫：(锚点索引, 规则索引) -> 次级字面量
+    /// 仅当窗口内包含该字面量时，才因该锚点运行对应规则（见 `split_broad_anchors`）
+    pub(crate) anchor_guards: HashMap<(usize, usize), Vec<u8>>,
+    /// 窗口累计字节上限（见 `ScanOptions::max_window_bytes`）
+    pub(crate) max_window_bytes: Option<usize>,
+    /// 锚点索引 -> 窗口（向前, 向后）字节数：取该锚点对应各规则窗口的最大值；
+    /// 规则未覆盖时为全局窗口（`ScanOptions::window_before` / `window_after`），PEM / 私钥类锚点再放大到
+    /// `pem_window_before` / `pem_window_after`
+    pub(crate) anchor_windows: Vec<(usize, usize)>,
+    /// PEM 放大窗口数上限（见 `ScanOptions::max_pem_windows`）
+    pub(crate) max_pem_windows: Option<usize>,
+    /// 小文件整缓冲区扫描阈值（见 `ScanOptions::small_file_fullscan_threshold`）
+    pub(crate) small_file_fullscan_threshold: usize,
+    /// 锚点与精准正则均按大小写不敏感匹配（见 `ScanOptions::anchors_ignore_case`）
+    pub(crate) case_insensitive: bool,
+    /// 带路径作用域的规则索引：不进入 AC 预筛，仅在路径匹配的文件上对整个缓冲区运行
+    pub(crate) path_scoped: Vec<usize>,
+    /// 规则索引 -> 该规则贡献的锚点数（非正则与路径作用域规则为 0）
+    pub(crate) rule_anchor_counts: Vec<usize>,
+    /// 规则索引 -> 锚点窗口统计（仅 `verbose_files` 时收集）
+    pub(crate) window_stats: Option<Vec<RuleWindowStats>>,
+    /// 取消标记（见 `ScanOptions::cancel`），在窗口之间检查
+    pub(crate) cancel: Option<Arc<AtomicBool>>,
+    /// 单条规则在单个窗口上的匹配时间上限（见 `ScanOptions::rule_timeout`）
+    pub(crate) rule_timeout: Option<Duration>,
+    /// 因超时被放弃的 (规则, 窗口) 次数（跨线程累加）
+    pub(crate) rule_timeouts: AtomicUsize,
+    /// 单个缓冲区的预筛命中数上限（见 `ScanOptions::max_matches_per_file`）
+    pub(crate) max_matches_per_file: Option<usize>,
+    /// 单行长度上限（见 `ScanOptions::max_line_length`）及被跳过的超长行数（跨线程累加）
+    pub(crate) max_line_length: Option<usize>,
+    pub(crate) long_lines_skipped: AtomicUsize,
+    /// 规则间声明了不同的优先级：窗口内按优先级降序运行规则并认领命中区间（见 `findings::SpanClaims`）
+    pub(crate) prioritized: bool,
+}
+
+/// 单条规则的锚点窗口统计（跨线程累加）
+#[derive(Default)]
+pub(crate) struct RuleWindowStats {
+    /// 因锚点命中而运行该规则的窗口数
+    windows: AtomicUsize,
+    /// 其中正则未产生任何命中的窗口数
+    misses: AtomicUsize,
 }
 
-/// 窗口参数（以 AC 命中位置为中心）
-/// 说明：在默认规则集中，多数密钥都紧邻锚点（如前缀/域名）。
-/// 将窗口收敛可显著减少精准正则的处理字节量，提升吞吐。
-/// 若发现召回下降，可在后续通过 CLI 参数暴露成可调项。
-pub(crate) const WINDOW_BEFORE: usize = 128;
-pub(crate) const WINDOW_AFTER: usize = 1024;
+impl PrefilterPlan {
+    /// 取消标记是否已置位
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(|c| c.load(Ordering::Relaxed))
+    }
+
+    /// 判断锚点 `aid` 命中的窗口是否应运行规则 `ri`（考虑宽泛锚点的次级字面量守卫）
+    pub(crate) fn rule_allowed_in_window(&self, aid: usize, ri: usize, window: &[u8]) -> bool {
+        match self.anchor_guards.get(&(aid, ri)) {
+            Some(lit) => memchr::memmem::find(window, lit).is_some(),
+            None => true,
+        }
+    }
+
+    /// 预筛命中数 `n` 是否已达到单文件上限（达到后不再处理后续窗口）
+    pub(crate) fn match_cap_reached(&self, n: usize) -> bool {
+        self.max_matches_per_file.is_some_and(|cap| n >= cap)
+    }
+
+    /// 记录规则 `ri` 在偏移 `window_offset` 处的窗口上匹配超时并被放弃
+    pub(crate) fn record_timeout(&self, ri: usize, file_hash: &str, window_offset: usize) {
+        self.rule_timeouts.fetch_add(1, Ordering::Relaxed);
+        warn!(rule = %qualified_id(&self.rules[ri]), file = file_hash, offset = window_offset, timeout_ms = self.rule_timeout.map(|t| t.as_millis() as u64), "rule exceeded its match timeout on a window, remaining matches skipped");
+    }
+
+    /// 记录规则 `ri` 在一个锚点窗口内的运行结果（未开启统计时为空操作）
+    pub(crate) fn record_window(&self, ri: usize, matched: bool) {
+        if let Some(stats) = &self.window_stats {
+            stats[ri].windows.fetch_add(1, Ordering::Relaxed);
+            if !matched { stats[ri].misses.fetch_add(1, Ordering::Relaxed); }
+        }
+    }
+}
+
+/// PEM / 私钥类锚点的种类：(`-----BEGIN ` 边界, `-----END ` 边界, 含 `PRIVATE KEY`)
+pub(crate) fn pem_anchor_kind(anchor: &[u8]) -> (bool, bool, bool) {
+    (anchor.starts_with(b"-----BEGIN "), anchor.starts_with(b"-----END "), anchor.windows(11).any(|w| w == b"PRIVATE KEY"))
+}
+
+/// 各锚点的窗口（向前, 向后）字节数，见 `PrefilterPlan::anchor_windows`
+fn anchor_windows(anchors: &[Vec<u8>], anchor_to_rules: &[Vec<usize>], specs: &[RuleSpec], opts: &ScanOptions) -> Vec<(usize, usize)> {
+    anchors
+        .iter()
+        .zip(anchor_to_rules)
+        .map(|(anchor, rules)| {
+            // PEM 放大窗口作为缺省值，规则显式声明的窗口优先
+            let (is_begin, is_end, is_priv) = pem_anchor_kind(anchor);
+            let before = if is_end || is_priv { opts.window_before.max(opts.pem_window_before) } else { opts.window_before };
+            let after = if is_begin || is_priv { opts.window_after.max(opts.pem_window_after) } else { opts.window_after };
+            let before = rules.iter().map(|&ri| specs[ri].window_before.unwrap_or(before)).max().unwrap_or(before);
+            let after = rules.iter().map(|&ri| specs[ri].window_after.unwrap_or(after)).max().unwrap_or(after);
+            (before, after)
+        })
+        .collect()
+}
 
 /// 从 RuleSpec 列表构建预筛计划
-pub(crate) fn build_prefilter_plan(specs: &[RuleSpec]) -> Arc<PrefilterPlan> {
+pub(crate) fn build_prefilter_plan(specs: &[RuleSpec], opts: &ScanOptions) -> Arc<PrefilterPlan> {
     // 1) 为每条规则抽取锚点
     let mut all_anchors: Vec<Vec<u8>> = Vec::new();
     let mut anchor_index: HashMap<Vec<u8>, usize> = HashMap::new();
     let mut tmp_map_rule_to_anchor_ids: Vec<Vec<usize>> = vec![Vec::new(); specs.len()];
+    let mut path_scoped: Vec<usize> = Vec::new();
+
+    // 需从模式中抽取锚点的规则（开销最大的一步）：批量抽取，配置了缓存目录时复用上次的结果
+    let needs_extraction = |spec: &RuleSpec| spec.path.is_none() && spec.proximity.is_none() && spec.prefixes.is_empty();
+    let patterns: Vec<&str> = specs.iter().filter(|s| needs_extraction(s)).filter_map(|s| s.pattern()).collect();
+    let mut extracted = anchor_cache::extract_all(&patterns, opts.anchor_cache_dir.as_deref(), extract_anchors_from_pattern).into_iter();
 
     for (idx, spec) in specs.iter().enumerate() {
-        let pat = match spec.pattern() { Some(p) => p, None => continue };
-        let anchors = extract_anchors_from_pattern(pat);
+        if spec.pattern().is_none() { continue; }
+        // 路径作用域规则不加入全局锚点：否则每个文件都会因其锚点产生窗口，
+        // 且仍需逐文件判断路径；改为在匹配的文件上单独整缓冲区运行（见 scan_path_scoped）
+        if spec.path.is_some() {
+            path_scoped.push(idx);
+            continue;
+        }
+        // 厂商家族前缀即锚点（与展开后的模式保持一致）；关键字邻近规则以关键字为锚点；其余规则从模式中抽取
+        let anchors = if let Some(p) = &spec.proximity {
+            p.anchors()
+        } else if spec.prefixes.is_empty() {
+            extracted.next().unwrap_or_default()
+        } else {
+            spec.prefixes.iter().map(|p| p.as_bytes().to_vec()).collect()
+        };
         if anchors.is_empty() {
             continue;
         }
@@ -72,25 +198,210 @@ pub(crate) fn build_prefilter_plan(specs: &[RuleSpec]) -> Arc<PrefilterPlan> {
         }
     }
 
+    // 2.1) 宽泛锚点诊断：单个锚点映射过多规则时，每个窗口都要运行全部规则，开销显著
+    //      若开启拆分，则为这些 (锚点, 规则) 配对选取规则自身更具体的次级字面量作为守卫
+    let mut anchor_guards: HashMap<(usize, usize), Vec<u8>> = HashMap::new();
+    for (aid, rules) in anchor_to_rules.iter().enumerate() {
+        if rules.len() <= opts.broad_anchor_threshold { continue; }
+        warn!(
+            anchor = %String::from_utf8_lossy(&all_anchors[aid]),
+            rules = rules.len(),
+            threshold = opts.broad_anchor_threshold,
+            "broad anchor maps to many rules"
+        );
+        // 次级字面量守卫按字节精确比对，与大小写不敏感匹配不兼容
+        if !opts.split_broad_anchors || opts.anchors_ignore_case { continue; }
+        for &ri in rules.iter() {
+            if let Some(lit) = secondary_literal(&specs[ri].pat, &all_anchors[aid]) {
+                anchor_guards.insert((aid, ri), lit);
+            }
+        }
+    }
+
+    // 2.2) 空计划诊断：没有任何可运行的规则时，扫描将不会产生命中
+    if all_anchors.is_empty() && path_scoped.is_empty() && specs.iter().all(|s| s.is_regex()) {
+        warn!(rules = specs.len(), "no active rules: no rule produced a prefilter anchor, scan will report no findings");
+    }
+
     // 3) 构建 AC 自动机
     let ac = AhoCorasickBuilder::new()
         .match_kind(aho_corasick::MatchKind::LeftmostLongest)
+        .ascii_case_insensitive(opts.anchors_ignore_case)
         .build(&all_anchors)
         .expect("build aho-corasick");
 
-    // 4) 收集规则模式文本
-    let mut rule_patterns = Vec::with_capacity(specs.len());
-    for s in specs {
-        rule_patterns.push(s.pat.clone());
-    }
 
-    Arc::new(PrefilterPlan {
+    let rule_anchor_counts = tmp_map_rule_to_anchor_ids.iter().map(Vec::len).collect();
+    let anchor_windows = anchor_windows(&all_anchors, &anchor_to_rules, specs, opts);
+    let plan = Arc::new(PrefilterPlan {
         ac,
         anchors: all_anchors,
         anchor_to_rules,
-        rule_patterns,
-        cache: Mutex::new(HashMap::new()),
-    })
+        rules: specs
+            .iter()
+            .map(|s| {
+                let mut s = s.clone();
+                if opts.ascii_bytes_semantics && s.is_regex() { s.pat = with_ascii_semantics(&s.pat); }
+                s
+            })
+            .collect(),
+        cache: Mutex::new(LruCache::new(opts.regex_cache_capacity)),
+        rejected: Mutex::new(HashSet::new()),
+        regex_size_limit: opts.regex_size_limit,
+        anchor_guards,
+        max_window_bytes: opts.max_window_bytes,
+        anchor_windows,
+        max_pem_windows: opts.max_pem_windows,
+        small_file_fullscan_threshold: opts.small_file_fullscan_threshold,
+        case_insensitive: opts.anchors_ignore_case,
+        path_scoped,
+        rule_anchor_counts,
+        window_stats: opts.verbose_files.then(|| specs.iter().map(|_| RuleWindowStats::default()).collect()),
+        cancel: opts.cancel.clone(),
+        rule_timeout: opts.rule_timeout,
+        rule_timeouts: AtomicUsize::new(0),
+        max_matches_per_file: opts.max_matches_per_file,
+        max_line_length: opts.max_line_length,
+        long_lines_skipped: AtomicUsize::new(0),
+        prioritized: has_mixed_priorities(specs),
+    });
+    if tracing::enabled!(tracing::Level::DEBUG) { log_rule_status(&plan); }
+    plan
+}
+
+/// 预筛计划诊断（见 `analyze_prefilter`）
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PlanDiagnostics {
+    /// 死规则：正则规则既无路径作用域、也没有抽取到任何锚点，预筛永远不会为其产生窗口，扫描中从不运行
+    pub dead_rules: Vec<DeadRule>,
+    /// 模式完全相同的规则组（每组至少两条，按规则加载顺序）：同一窗口上重复运行并产生重复命中
+    pub duplicate_patterns: Vec<Vec<String>>,
+}
+
+/// 没有任何预筛锚点的规则
+#[derive(Debug, Clone, Serialize)]
+pub struct DeadRule {
+    /// 规则索引（规则加载顺序）
+    pub index: usize,
+    /// 规则 id（配置了命名规则集时为 `集合名/id`）
+    pub rule_id: String,
+}
+
+/// 分析预筛计划，列出死规则与模式重复的规则
+pub fn analyze_prefilter(plan: &PrefilterPlan) -> PlanDiagnostics {
+    let dead_rules = plan
+        .rules
+        .iter()
+        .enumerate()
+        .filter(|&(ri, spec)| spec.is_regex() && spec.path.is_none() && plan.rule_anchor_counts[ri] == 0)
+        .map(|(index, spec)| DeadRule { index, rule_id: qualified_id(spec) })
+        .collect();
+
+    // 按模式文本分组：非正则规则按各自的检测器参数匹配，关键字邻近规则与路径作用域规则的
+    // 运行范围不只由模式决定，均不参与比较
+    let mut groups: Vec<(&str, Vec<String>)> = Vec::new();
+    let mut by_pattern: HashMap<&str, usize> = HashMap::new();
+    for spec in plan.rules.iter().filter(|s| s.is_regex() && s.proximity.is_none() && s.path.is_none()) {
+        let pat = spec.pat.as_str();
+        match by_pattern.get(pat) {
+            Some(&gi) => groups[gi].1.push(qualified_id(spec)),
+            None => {
+                by_pattern.insert(pat, groups.len());
+                groups.push((pat, vec![qualified_id(spec)]));
+            }
+        }
+    }
+    let duplicate_patterns = groups.into_iter().map(|(_, ids)| ids).filter(|ids| ids.len() > 1).collect();
+
+    PlanDiagnostics { dead_rules, duplicate_patterns }
+}
+
+/// 调试日志：逐条规则输出编译结果与贡献的锚点数，便于排查“规则从不命中”
+/// （无锚点的正则规则不会被预筛选中，编译失败的规则在懒编译路径下告警一次后跳过）。
+/// 仅在 debug 级别开启时执行；编译结果写入缓存，扫描时不再重复编译
+fn log_rule_status(plan: &PrefilterPlan) {
+    for (ri, spec) in plan.rules.iter().enumerate() {
+        if !spec.is_regex() {
+            debug!(rule = %spec.id, kind = spec.kind_name(), "rule loaded, runs without prefilter");
+            continue;
+        }
+        let compiled = match compile_meta_regex(plan, spec) {
+            Ok(rx) => {
+                plan.cache.lock().unwrap().put(ri, Arc::new(rx));
+                true
+            }
+            Err(e) => {
+                debug!(rule = %spec.id, error = %e, "rule pattern failed to compile");
+                false
+            }
+        };
+        let anchors = plan.rule_anchor_counts[ri];
+        if spec.path.is_some() {
+            debug!(rule = %spec.id, compiled, anchors, "rule loaded, path-scoped (runs on matching files without prefilter)");
+        } else if anchors == 0 {
+            debug!(rule = %spec.id, compiled, anchors, "rule loaded without prefilter anchors, it will never fire");
+        } else {
+            debug!(rule = %spec.id, compiled, anchors, "rule loaded");
+        }
+    }
+}
+
+/// 调试日志：逐条输出“锚点命中但正则未匹配”的窗口数（仅有未匹配窗口的规则），按未匹配数降序。
+/// 比例接近 100% 的规则多半是正则与锚点对不上（正则写错或锚点过宽）
+pub(crate) fn log_anchor_misses(plan: &PrefilterPlan) {
+    let stats = match &plan.window_stats { Some(s) => s, None => return };
+    let mut rows: Vec<(usize, usize, usize)> = stats
+        .iter()
+        .enumerate()
+        .map(|(ri, s)| (ri, s.windows.load(Ordering::Relaxed), s.misses.load(Ordering::Relaxed)))
+        .filter(|&(_, _, misses)| misses > 0)
+        .collect();
+    rows.sort_by(|a, b| b.2.cmp(&a.2).then(a.0.cmp(&b.0)));
+    for (ri, windows, misses) in rows {
+        debug!(rule = %plan.rules[ri].id, windows, misses, "anchor fired but rule regex did not match");
+    }
+}
+
+/// 为宽泛锚点选取规则的次级字面量（最长的、且与该锚点不同的原始字面量片段）
+/// - 大小写不敏感的规则（含 `(?i`）不设守卫，避免字面量比对漏检
+/// - 没有可用字面量时返回 None（该规则照常运行）
+fn secondary_literal(pat: &str, anchor: &[u8]) -> Option<Vec<u8>> {
+    if pat.contains("(?i") { return None; }
+    let mut lits: Vec<Vec<u8>> = extract_raw_literals(pat)
+        .into_iter()
+        .filter(|l| l.as_slice() != anchor && memchr::memmem::find(anchor, l).is_none())
+        .collect();
+    // 长度降序、字典序升序，保证选取结果确定
+    lits.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+    lits.into_iter().next()
+}
+
+/// 精选的高置信锚点：密钥值本身的前缀（命中值以其开头）与 PEM / 私钥边界
+/// 注意：尽量使用具有区分度的前缀，避免如 "SK" 这类过于宽泛的短 token。
+const CURATED_PREFIXES: &[&str] = &[
+    // 通用厂商/产品前缀
+    "sk-", "sk_", "rk_", "ghp_", "gho_", "ghr_", "ghs_", "ghu_", "github_pat_", "glpat-",
+    "xoxb-", "xoxp-", "xoxe-", "xoxs-", "xapp-",
+    "AKIA", "ASIA", "A3T", "ABIA", "ACCA", "v1.0-",
+    "doo_v1_", "dop_v1_", "dor_v1_", "EAA",
+    "HRKU-AA", "hf_", "api_org_", "lin_api_", "ntn_",
+    "PMAK-", "pnu_", "ATATT3", "SG.", "sntrys_", "sntryu_", "shpat_", "shpca_",
+    "shppa_", "shpss_", "AIza", "ya29.",
+    // PEM/私钥常见边界（避免使用通用的 "KEY"、"BEGIN"，选择更具体的片段）
+    "-----BEGIN ", "-----END ", "PRIVATE KEY", "RSA PRIVATE KEY", "EC PRIVATE KEY",
+    "OPENSSH PRIVATE KEY",
+];
+
+/// 精选的高置信锚点：域名 / 厂商关键字（出现在密钥附近，不是密钥值的一部分）
+const CURATED_KEYWORDS: &[&str] = &[
+    "hooks.slack.com", "slack.com", "cloudflare", "discord", "dropbox", "facebook",
+    "heroku", "mailgun", "telegram", "openai", "cohere",
+];
+
+/// 规则是否带有确定的密钥前缀（厂商家族前缀、模式中含精选前缀或 PEM 边界）：
+/// 这类命中由前缀本身保证置信度，不受 `min_score` 熵门限约束；仅靠关键字定位的规则不算
+pub(crate) fn has_curated_prefix(spec: &RuleSpec) -> bool {
+    !spec.prefixes.is_empty() || CURATED_PREFIXES.iter().any(|c| spec.pat.contains(c))
 }
 
 /// 从正则模式中抽取锚点（启发式）：
@@ -100,22 +411,8 @@ fn extract_anchors_from_pattern(pat: &str) -> Vec<Vec<u8>> {
     // out 收集候选锚点（字节串），避免重复
     let mut out: HashSet<Vec<u8>> = HashSet::new();
 
-    // 1) 精选的高置信锚点（覆盖主流密钥前缀/域名/标识），优先使用
-    //    注意：尽量使用具有区分度的前缀，避免如 "SK" 这类过于宽泛的短 token。
-    let curated = [
-        // 通用厂商/产品前缀
-        "sk-", "sk_", "rk_", "ghp_", "gho_", "ghr_", "ghs_", "ghu_", "github_pat_", "glpat-",
-        "xoxb-", "xoxp-", "xoxe-", "xoxs-", "xapp-", "hooks.slack.com", "slack.com",
-        "AKIA", "ASIA", "A3T", "ABIA", "ACCA", "v1.0-", "cloudflare",
-        "doo_v1_", "dop_v1_", "dor_v1_", "discord", "dropbox", "EAA", "facebook",
-        "heroku", "HRKU-AA", "hf_", "api_org_", "lin_api_", "mailgun", "ntn_",
-        "PMAK-", "pnu_", "ATATT3", "SG.", "sntrys_", "sntryu_", "shpat_", "shpca_",
-        "shppa_", "shpss_", "telegram", "AIza", "ya29.", "openai", "cohere",
-        // PEM/私钥常见边界（避免使用通用的 "KEY"、"BEGIN"，选择更具体的片段）
-        "-----BEGIN ", "-----END ", "PRIVATE KEY", "RSA PRIVATE KEY", "EC PRIVATE KEY",
-        "OPENSSH PRIVATE KEY",
-    ];
-    for c in curated.iter() {
+    // 1) 精选的高置信锚点（见 CURATED_PREFIXES / CURATED_KEYWORDS），优先使用
+    for c in CURATED_PREFIXES.iter().chain(CURATED_KEYWORDS) {
         if pat.contains(c) { out.insert(c.as_bytes().to_vec()); }
     }
 
@@ -133,26 +430,7 @@ fn extract_anchors_from_pattern(pat: &str) -> Vec<Vec<u8>> {
     //    - 仅提取不含元字符的连续片段
     //    - 过滤掉过短或过于通用的词（例如 KEY/BEGIN/END 等）
     //    - 保留包含分隔符(-_/.)的短片段，或长度>=6 的纯字母数字片段
-    let mut cur = String::new();
-    let is_meta = |ch: char| matches!(ch, '['|']'|'{'|'}'|'('|')'|'?'|'*'|'+'|'|'|'^'|'$'|'\\');
-    let allow = |ch: char| ch.is_ascii_alphanumeric() || matches!(ch, '-'|'_'|'.'|'/');
-    let mut in_class = false; // 粗略处理字符类
-    let mut chars = pat.chars().peekable();
-    while let Some(ch) = chars.next() {
-        if ch == '[' { in_class = true; flush_literal(&mut cur, &mut out); continue; }
-        if ch == ']' { in_class = false; flush_literal(&mut cur, &mut out); continue; }
-        if in_class { continue; }
-        if is_meta(ch) {
-            flush_literal(&mut cur, &mut out);
-            continue;
-        }
-        if allow(ch) {
-            cur.push(ch);
-        } else {
-            flush_literal(&mut cur, &mut out);
-        }
-    }
-    flush_literal(&mut cur, &mut out);
+    out.extend(extract_raw_literals(pat));
 
     // 3) 过滤规则：
     //    - 长度>=6 直接保留；
@@ -194,6 +472,31 @@ fn extract_anchors_from_pattern(pat: &str) -> Vec<Vec<u8>> {
     v
 }
 
+/// 抽取模式中不含元字符的连续字面量片段（长度≥3，未过滤）
+fn extract_raw_literals(pat: &str) -> HashSet<Vec<u8>> {
+    let mut out: HashSet<Vec<u8>> = HashSet::new();
+    let mut cur = String::new();
+    let is_meta = |ch: char| matches!(ch, '['|']'|'{'|'}'|'('|')'|'?'|'*'|'+'|'|'|'^'|'$'|'\\');
+    let allow = |ch: char| ch.is_ascii_alphanumeric() || matches!(ch, '-'|'_'|'.'|'/');
+    let mut in_class = false; // 粗略处理字符类
+    for ch in pat.chars() {
+        if ch == '[' { in_class = true; flush_literal(&mut cur, &mut out); continue; }
+        if ch == ']' { in_class = false; flush_literal(&mut cur, &mut out); continue; }
+        if in_class { continue; }
+        if is_meta(ch) {
+            flush_literal(&mut cur, &mut out);
+            continue;
+        }
+        if allow(ch) {
+            cur.push(ch);
+        } else {
+            flush_literal(&mut cur, &mut out);
+        }
+    }
+    flush_literal(&mut cur, &mut out);
+    out
+}
+
 fn flush_literal(cur: &mut String, out: &mut HashSet<Vec<u8>>) {
     if cur.len() >= 3 {
         out.insert(cur.as_bytes().to_vec());
@@ -203,18 +506,81 @@ fn flush_literal(cur: &mut String, out: &mut HashSet<Vec<u8>>) {
 
 /// 获取（或懒编译）指定规则索引的 bytes 正则
 pub(crate) fn get_or_compile_meta_regex(plan: &PrefilterPlan, rule_idx: usize) -> Option<Arc<MetaRegex>> {
-    if rule_idx >= plan.rule_patterns.len() { return None; }
+    if rule_idx >= plan.rules.len() { return None; }
     // 快路径：先查缓存
-    if let Some(rx) = plan.cache.lock().unwrap().get(&rule_idx).cloned() {
+    if let Some(rx) = plan.cache.lock().unwrap().get(&rule_idx) {
         return Some(rx);
     }
-    let pat = &plan.rule_patterns[rule_idx];
-    match MetaRegex::new(pat) {
+    if plan.rejected.lock().unwrap().contains(&rule_idx) { return None; }
+    let spec = &plan.rules[rule_idx];
+    match compile_meta_regex(plan, spec) {
         Ok(rx) => {
             let rx = Arc::new(rx);
-            plan.cache.lock().unwrap().insert(rule_idx, rx.clone());
+            plan.cache.lock().unwrap().put(rule_idx, rx.clone());
             Some(rx)
         }
-        Err(_) => None,
+        Err(e) => {
+            // 并发的首次编译可能各自失败，只由插入成功者告警
+            if plan.rejected.lock().unwrap().insert(rule_idx) {
+                warn!(rule = %spec.id, error = %e, "rule pattern failed to compile, rule disabled");
+            }
+            None
+        }
     }
 }
+
+/// 预热：扫描开始前编译全部正则规则并写入缓存，使首个命中各规则的文件不再承担编译开销；
+/// 任一规则编译失败立即返回错误（懒编译路径下该规则只会被静默跳过）。
+/// 缓存容量受限时，超出容量的部分会被淘汰，预热仅对最后编译的规则有效。返回编译的规则数
+pub(crate) fn precompile_all(plan: &PrefilterPlan) -> Result<usize> {
+    let mut compiled = 0usize;
+    for (ri, spec) in plan.rules.iter().enumerate() {
+        if !spec.is_regex() { continue; }
+        let rx = compile_meta_regex(plan, spec).with_context(|| format!("rule '{}': failed to compile pattern", spec.id))?;
+        plan.cache.lock().unwrap().put(ri, Arc::new(rx));
+        compiled += 1;
+    }
+    Ok(compiled)
+}
+
+/// 完整编译每条正则规则（不写入缓存）：先以 `regex::bytes` 编译，再以元引擎按扫描时的配置编译，
+/// 返回失败的 (规则 id, 错误信息)，按加载顺序（每条规则至多一条）
+pub(crate) fn compile_failures(plan: &PrefilterPlan) -> Vec<(String, String)> {
+    let size_limit = plan.regex_size_limit.unwrap_or(usize::MAX);
+    plan.rules
+        .iter()
+        .filter(|spec| spec.is_regex())
+        .filter_map(|spec| {
+            let err = match regex::bytes::RegexBuilder::new(&spec.pat).size_limit(size_limit).build() {
+                Err(e) => e.to_string(),
+                Ok(_) => compile_meta_regex(plan, spec).err()?.to_string(),
+            };
+            Some((qualified_id(spec), err))
+        })
+        .collect()
+}
+
+/// 编译规则的 bytes 正则（不经过缓存）
+fn compile_meta_regex(plan: &PrefilterPlan, spec: &RuleSpec) -> Result<MetaRegex> {
+    // 与 regex::bytes 一致：允许匹配非法 UTF-8 的模式（如 `(?-u).`）
+    // 锚点大小写不敏感时，精准正则同样按大小写不敏感编译，避免窗口内匹配失败
+    // 编译大小超出 `regex_size_limit` 时拒绝该规则（转换为说明上限的错误）
+    let build = |pat: &str| {
+        MetaRegex::builder()
+            .configure(MetaRegex::config().nfa_size_limit(plan.regex_size_limit))
+            .syntax(ra::util::syntax::Config::new().utf8(false).case_insensitive(plan.case_insensitive))
+            .build(pat)
+            .map_err(|e| match e.size_limit() {
+                Some(limit) => anyhow::anyhow!("compiled regex exceeds the size limit of {} bytes", limit),
+                None => anyhow::Error::from(e),
+            })
+    };
+    build(&spec.pat).or_else(|e| {
+        // 自动加上的 `(?-u)` 与 Unicode 专属构造（如 `\p{L}`）冲突时，回退为原模式；
+        // 超出大小上限（已不是 BuildError）时不回退
+        let Some(orig) = spec.pat.strip_prefix(ASCII_SEMANTICS_PREFIX) else { return Err(e) };
+        if !e.is::<ra::meta::BuildError>() { return Err(e); }
+        warn!(rule = %spec.id, "rule is not compatible with ASCII semantics, keeping Unicode mode");
+        build(orig)
+    })
+}
@@ -0,0 +1,74 @@
+//! 关键字邻近的赋值检测（声明了 `keyword` 的正则规则）
+//!
+//! 通用赋值（`password = "..."`、`api_key: ...`）没有可作为锚点的高置信前缀。此类规则以关键字作为预筛锚点，
+//! 在锚点窗口内只在“关键字 + 赋值运算符”之后运行规则正则（值正则）：
+//! - 关键字之后可接标识符的剩余部分（如 `db_password`、`api_key_prod`）、右引号 / 右方括号与空白；
+//! - 随后须为 `=>`、`:=`、`=` 或 `:` 之一（`==`、`::` 不算赋值）；
+//! - 值正则的匹配须在运算符结束后 `proximity` 字节内开始，每个关键字至多报告一个匹配。
+//!
+//! 正文中单独出现的关键字（如 “enter your password”）没有赋值运算符，不会运行值正则。
+use aho_corasick::{AhoCorasick, AhoCorasickBuilder, MatchKind};
+use anyhow::Result;
+
+/// 默认值起点与赋值运算符之间的最大距离（字节）
+pub(crate) const DEFAULT_PROXIMITY: usize = 8;
+
+/// 关键字邻近参数
+#[derive(Debug, Clone)]
+pub(crate) struct ProximityParams {
+    /// 关键字原文（用于生成预筛锚点）
+    pub keywords: Vec<String>,
+    /// 关键字自动机（ASCII 大小写不敏感），在窗口内定位关键字
+    pub matcher: AhoCorasick,
+    pub proximity: usize,
+}
+
+impl ProximityParams {
+    pub(crate) fn new(keywords: Vec<String>, proximity: usize) -> Result<Self> {
+        let matcher = AhoCorasickBuilder::new()
+            .ascii_case_insensitive(true)
+            .match_kind(MatchKind::LeftmostLongest)
+            .build(&keywords)?;
+        Ok(Self { keywords, matcher, proximity })
+    }
+
+    /// 预筛锚点：每个关键字的原文、全小写、全大写与首字母大写形式（去重，按出现顺序）；
+    /// 全局 AC 缺省大小写敏感，这几种写法覆盖常见的 `password` / `PASSWORD` / `Password`
+    pub(crate) fn anchors(&self) -> Vec<Vec<u8>> {
+        let mut out: Vec<Vec<u8>> = Vec::new();
+        for k in &self.keywords {
+            let lower = k.to_ascii_lowercase();
+            let mut capitalized = lower.clone();
+            if let Some(first) = capitalized.get_mut(..1) { first.make_ascii_uppercase(); }
+            for v in [k.clone(), lower, k.to_ascii_uppercase(), capitalized] {
+                let v = v.into_bytes();
+                if !out.contains(&v) { out.push(v); }
+            }
+        }
+        out
+    }
+
+    /// 缓冲区中各关键字之后赋值运算符的结束位置（值正则从此处开始查找），按位置先后
+    pub(crate) fn assignment_ends<'a>(&'a self, buf: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+        self.matcher.find_iter(buf).filter_map(move |m| assignment_after(buf, m.end()))
+    }
+}
+
+/// 关键字结束位置 `from` 之后的赋值运算符结束位置；没有赋值运算符时为 None
+fn assignment_after(buf: &[u8], from: usize) -> Option<usize> {
+    let mut i = from;
+    // 标识符剩余部分（api_key_prod、password.prod）
+    while i < buf.len() && (buf[i].is_ascii_alphanumeric() || matches!(buf[i], b'_' | b'-' | b'.')) { i += 1; }
+    // 右引号 / 右方括号（"password": 、config['password'] =）与空白
+    while i < buf.len() && matches!(buf[i], b'"' | b'\'' | b'`' | b']' | b' ' | b'\t') { i += 1; }
+    let rest = &buf[i..];
+    if rest.starts_with(b"=>") || rest.starts_with(b":=") {
+        Some(i + 2)
+    } else if rest.starts_with(b"==") || rest.starts_with(b"::") {
+        None
+    } else if rest.starts_with(b"=") || rest.starts_with(b":") {
+        Some(i + 1)
+    } else {
+        None
+    }
+}
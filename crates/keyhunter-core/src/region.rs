@@ -0,0 +1,82 @@
+//! 局部扫描（`head_bytes` / `tail_bytes`）：每个文件只读取并扫描开头或末尾的一段字节
+//!
+//! - 只读取该区域（tail 先定位到 `文件大小 - N`），不读文件其余部分；
+//! - 命中偏移仍为文件内的绝对偏移（tail：`文件大小 - 区域长度 + 区域内偏移`）；
+//! - 跨越区域边界的命中会被漏掉；行号（`with_location`）从区域起点开始计数；
+//! - UTF-8 引擎下，被区域边界切开的多字节字符整体丢弃。
+use anyhow::Result;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Take};
+use std::path::Path;
+
+use crate::digest::{ContentHasher, HashingReader};
+use crate::options::ScanOptions;
+
+/// 文件内的扫描区域
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Region {
+    /// 区域起点（文件内偏移）
+    pub(crate) start: u64,
+    /// 区域长度
+    pub(crate) len: u64,
+    /// 区域之前还有未扫描的内容（tail 截断）
+    pub(crate) cut_start: bool,
+    /// 区域之后还有未扫描的内容（head 截断）
+    pub(crate) cut_end: bool,
+    /// 文件（内容）总长度
+    pub(crate) file_len: u64,
+}
+
+impl Region {
+    /// 按 `head_bytes` / `tail_bytes` 计算长度为 `file_len` 的内容的扫描区域；均未设置时为整个文件
+    pub(crate) fn of(file_len: u64, opts: &ScanOptions) -> Self {
+        let (start, len) = match (opts.head_bytes, opts.tail_bytes) {
+            (Some(n), _) => (0, n.min(file_len)),
+            (None, Some(n)) => (file_len.saturating_sub(n), n.min(file_len)),
+            (None, None) => (0, file_len),
+        };
+        Self { start, len, cut_start: start > 0, cut_end: start + len < file_len, file_len }
+    }
+
+    /// 在内存内容上取出区域（content 长度即文件长度）
+    pub(crate) fn slice<'a>(&self, content: &'a [u8]) -> &'a [u8] {
+        &content[self.start as usize..(self.start + self.len) as usize]
+    }
+
+    /// UTF-8 引擎：去掉被区域边界切开的字符，返回 (区域内起点, 区域内终点)
+    pub(crate) fn utf8_bounds(&self, buf: &[u8]) -> (usize, usize) {
+        let start = if self.cut_start { utf8_lead_skip(buf) } else { 0 };
+        let end = if self.cut_end { start + utf8_complete_len(&buf[start..]) } else { buf.len() };
+        (start, end)
+    }
+}
+
+/// 打开文件并定位到扫描区域，返回只读该区域的读取器；`hasher` 为 Some 时读取的字节同时计入内容摘要（见 `digest` 模块）
+pub(crate) fn open_region<'h>(path: &Path, opts: &ScanOptions, hasher: Option<&'h mut ContentHasher>) -> Result<(HashingReader<'h, Take<File>>, Region)> {
+    let mut file = File::open(path)?;
+    let region = Region::of(file.metadata()?.len(), opts);
+    if region.start > 0 { file.seek(SeekFrom::Start(region.start))?; }
+    Ok((HashingReader::new(file.take(region.len), hasher), region))
+}
+
+/// 整读扫描区域（小文件路径）：文件只打开、读取一次，返回的缓冲区供编码抽样、扫描、
+/// 行号 / 上下文及 UTF-8 失败后的 Bytes 重扫共用，各环节不再重新打开文件
+pub(crate) fn read_region(path: &Path, opts: &ScanOptions, hasher: Option<&mut ContentHasher>) -> Result<(Vec<u8>, Region)> {
+    let (mut reader, region) = open_region(path, opts, hasher)?;
+    let mut buf = Vec::with_capacity(region.len as usize);
+    reader.read_to_end(&mut buf)?;
+    Ok((buf, region))
+}
+
+/// 区域开头被切开的字符残余（UTF-8 续字节，至多 3 个）
+pub(crate) fn utf8_lead_skip(buf: &[u8]) -> usize {
+    buf.iter().take(3).take_while(|&&b| b & 0xC0 == 0x80).count()
+}
+
+/// 去掉末尾不完整的多字节序列后的长度（中间的非法序列留给调用方报错）
+fn utf8_complete_len(buf: &[u8]) -> usize {
+    match std::str::from_utf8(buf) {
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        _ => buf.len(),
+    }
+}
@@ -0,0 +1,144 @@
+//! 规则文件来源：本地路径或 HTTP URL（集中分发的规则）
+//!
+//! `rules_path` 以 `http://` 或 `https://` 开头时在启动时拉取规则内容：
+//! - 连接与整个请求各自超时 10 秒，响应体上限 4 MiB，仅接受 200 响应，不跟随重定向；
+//! - Content-Type 须为 TOML / 纯文本类（`application/toml`、`text/plain` 等）或缺省，
+//!   拒绝 HTML 等明显不是规则文件的响应（如登录页、错误页）；
+//! - 配置了 `rules_cache` 时，拉取成功后写入缓存文件；拉取失败则告警并回退到缓存内容；
+//! - 同一进程内同一 URL 只拉取一次（运行元数据的规则集哈希复用同一份内容）。
+//!
+//! 集中分发的规则应使用 `https://`：`http://` 拉取的内容可被中间人篡改（如植入永不命中的规则），拉取时告警。
+use anyhow::{anyhow, bail, Context, Result};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tracing::warn;
+
+/// 连接与读写超时
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+/// 响应体上限（字节）
+const MAX_RULES_SIZE: usize = 4 * 1024 * 1024;
+/// 可接受的 Content-Type（不含参数，小写）
+const ACCEPTED_CONTENT_TYPES: &[&str] = &["application/toml", "text/toml", "text/x-toml", "text/plain", "application/octet-stream"];
+
+/// 已拉取的 URL -> 内容（进程内复用）
+static FETCHED: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+
+/// 读取规则文件内容：URL 按上述规则拉取（`cache` 为可选的本地缓存文件），否则读本地文件
+pub(crate) fn read_rules_source(path: &Path, cache: Option<&Path>) -> Result<String> {
+    let url = match path.to_str().filter(|s| is_rules_url(s)) {
+        Some(u) => u,
+        None => return Ok(std::fs::read_to_string(path)?),
+    };
+    let fetched = FETCHED.get_or_init(Default::default);
+    if let Some(txt) = fetched.lock().unwrap().get(url) {
+        return Ok(txt.clone());
+    }
+
+    if url.starts_with("http://") {
+        warn!(url, "fetching rules over plain http; use https so the rules cannot be tampered with in transit");
+    }
+    let txt = match fetch(url) {
+        Ok(txt) => {
+            if let Some(cache) = cache {
+                std::fs::write(cache, &txt).with_context(|| format!("write rules cache {}", cache.display()))?;
+            }
+            txt
+        }
+        Err(e) => {
+            let cache = match cache {
+                Some(c) if c.exists() => c,
+                _ => return Err(e.context(format!("fetch rules from {}", url))),
+            };
+            warn!(url, cache = %cache.display(), error = %e, "failed to fetch rules, using cached copy");
+            std::fs::read_to_string(cache).with_context(|| format!("read rules cache {}", cache.display()))?
+        }
+    };
+    fetched.lock().unwrap().insert(url.to_string(), txt.clone());
+    Ok(txt)
+}
+
+/// 是否为远程规则地址
+fn is_rules_url(s: &str) -> bool {
+    s.starts_with("http://") || s.starts_with("https://")
+}
+
+/// HTTP(S) GET（reqwest 阻塞客户端，rustls 提供 TLS，信任系统根证书（内部 CA 签发的证书同样可用）；不跟随重定向）
+fn fetch(url: &str) -> Result<String> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .connect_timeout(FETCH_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none())
+        .user_agent(concat!("keyhunter/", env!("CARGO_PKG_VERSION")))
+        .build()?;
+    let resp = client.get(url).header(reqwest::header::ACCEPT, "application/toml, text/plain").send()?;
+    let status = resp.status();
+    if status != reqwest::StatusCode::OK { bail!("HTTP status {}", status.as_u16()); }
+
+    if let Some(ct) = resp.headers().get(reqwest::header::CONTENT_TYPE) {
+        let ct = ct.to_str().map_err(|_| anyhow!("malformed content type"))?;
+        let mime = ct.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+        if !ACCEPTED_CONTENT_TYPES.contains(&mime.as_str()) {
+            bail!("unexpected content type '{}' (expected TOML or plain text)", mime);
+        }
+    }
+    if let Some(len) = resp.content_length() {
+        if len > MAX_RULES_SIZE as u64 { bail!("rules file too large ({} bytes, limit {})", len, MAX_RULES_SIZE); }
+    }
+    // 多读 1 字节用于判断是否超限（chunked 响应没有 Content-Length）
+    let mut body = Vec::new();
+    resp.take(MAX_RULES_SIZE as u64 + 1).read_to_end(&mut body)?;
+    if body.len() > MAX_RULES_SIZE { bail!("rules file too large (limit {} bytes)", MAX_RULES_SIZE); }
+    String::from_utf8(body).map_err(|_| anyhow!("rules file is not valid UTF-8"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::net::TcpListener;
+
+    /// 在本地端口上应答一次请求，返回 URL
+    fn serve_once(content_type: &str, body: &str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/rules.toml", listener.local_addr().unwrap());
+        let resp = format!("HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", content_type, body.len(), body);
+        std::thread::spawn(move || {
+            let (mut conn, _) = listener.accept().unwrap();
+            let mut req = [0u8; 4096];
+            let _ = conn.read(&mut req);
+            conn.write_all(resp.as_bytes()).unwrap();
+        });
+        url
+    }
+
+    #[test]
+    fn fetches_toml_over_http() {
+        let url = serve_once("application/toml; charset=utf-8", "[[rules]]\nid = \"x\"\n");
+        assert_eq!(fetch(&url).unwrap(), "[[rules]]\nid = \"x\"\n");
+    }
+
+    #[test]
+    fn rejects_html_responses() {
+        let url = serve_once("text/html", "<html>login</html>");
+        let err = fetch(&url).unwrap_err();
+        assert!(err.to_string().contains("unexpected content type"), "{:#}", err);
+    }
+
+    #[test]
+    fn falls_back_to_cache_when_fetch_fails() {
+        let dir = std::env::temp_dir().join(format!("keyhunter-remote-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let cache = dir.join("rules.cache.toml");
+        std::fs::write(&cache, "# cached\n").unwrap();
+        // 绑定后立即释放的端口：连接被拒绝
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let url = format!("http://127.0.0.1:{}/rules.toml", port);
+
+        assert_eq!(read_rules_source(Path::new(&url), Some(&cache)).unwrap(), "# cached\n");
+        assert!(read_rules_source(Path::new(&format!("{}?nocache", url)), None).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
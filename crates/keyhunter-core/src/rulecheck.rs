@@ -0,0 +1,56 @@
+//! 规则模式校验（扫描前的语法检查与 rules check 子命令）
+//!
+//! 精准正则按需懒编译，编译失败的规则只告警一次后停用；规则文件中的笔误因此表现为“没有命中”。
+//! - 扫描开始前解析全部正则规则的模式（只解析、不编译，开销很小），有语法错误时列出全部出错规则并终止；
+//! - `rules check` 额外用 `regex::bytes` 与 regex-automata 元引擎完整编译每条规则，
+//!   同时报告超出 `regex_size_limit` 而会被停用的规则。
+use anyhow::Result;
+use regex_automata::util::syntax;
+use serde::Serialize;
+
+use crate::options::ScanOptions;
+use crate::prefilter::{build_prefilter_plan, compile_failures};
+use crate::rules::{load_configured_rule_specs, RuleSpec};
+
+/// 无法使用的规则及原因
+#[derive(Debug, Clone, Serialize)]
+pub struct BrokenRule {
+    /// 规则 id（配置了命名规则集时为 `集合名/id`）
+    pub rule_id: String,
+    pub error: String,
+}
+
+/// 规则 id（配置了命名规则集时带集合名前缀）
+pub(crate) fn qualified_id(spec: &RuleSpec) -> String {
+    match &spec.ruleset { Some(set) => format!("{}/{}", set, spec.id), None => spec.id.clone() }
+}
+
+/// 解析全部正则规则的模式（字节语义，与 `regex::bytes` / 元引擎使用同一解析器），
+/// 返回全部语法错误的 (规则 id, 错误信息)；不编译，不受 `regex_size_limit` 约束
+pub(crate) fn validate_rules(specs: &[RuleSpec]) -> std::result::Result<(), Vec<(String, String)>> {
+    let config = syntax::Config::new().utf8(false);
+    let broken: Vec<(String, String)> = specs
+        .iter()
+        .filter_map(|s| {
+            let pat = s.pattern()?;
+            syntax::parse_with(pat, &config).err().map(|e| (qualified_id(s), e.to_string()))
+        })
+        .collect();
+    if broken.is_empty() { Ok(()) } else { Err(broken) }
+}
+
+/// 由 `validate_rules` 的结果构造错误：逐条列出出错的规则
+pub(crate) fn broken_rules_error(broken: Vec<(String, String)>) -> anyhow::Error {
+    let mut msg = format!("{} rule(s) have invalid patterns:", broken.len());
+    for (id, err) in broken {
+        msg.push_str(&format!("\nrule '{}':\n{}", id, err));
+    }
+    anyhow::anyhow!(msg)
+}
+
+/// 按扫描选项加载规则并完整编译每条正则规则，返回无法使用的规则（按加载顺序；全部可用时为空）
+pub fn check_rules(opts: &ScanOptions) -> Result<Vec<BrokenRule>> {
+    let specs = load_configured_rule_specs(opts)?;
+    let plan = build_prefilter_plan(&specs, opts);
+    Ok(compile_failures(&plan).into_iter().map(|(rule_id, error)| BrokenRule { rule_id, error }).collect())
+}
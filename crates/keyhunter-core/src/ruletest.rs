@@ -0,0 +1,205 @@
+//! 规则自测（供 `keyhunter rules test` 使用）与单个字符串的分类
+//!
+//! 三种用法：
+//! - `run_rule_tests`：将用户给定的正例 / 反例字符串逐条作为独立缓冲区，运行全部规则并与期望比对；
+//! - `run_rule_examples`：检查规则文件中各规则自带的 `examples` / `negatives`，只看该规则本身是否命中；
+//! - `classify` / `classify_with_plan`：对单个内存字符串运行全部规则，返回实际扫描会报告的命中及其区间
+//!   （编辑器插件、pre-commit 钩子检查剪贴板内容等，不读写文件）。
+//!
+//! 三者共用同一套匹配方式：
+//! - 正则规则直接在整个样本上运行（不经过窗口），同时报告 AC 预筛是否会触发该规则，
+//!   便于发现“正则能匹配、但锚点缺失导致实际扫描永远不会运行”的规则；
+//! - 路径作用域规则忽略 path，始终参与测试；env 规则按 `NAME=value` 行检测，der 规则按 base64 块检测，url 规则按 URL userinfo 检测。
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::engine_bytes::run_rule_in_window;
+use crate::block::scan_marker_blocks;
+use crate::der::scan_der_blocks;
+use crate::url::scan_url_credentials;
+use crate::keyword_entropy::scan_keyword_entropy;
+use crate::env::scan_env_lines;
+use crate::findings::FindingPublic as Finding;
+use crate::options::ScanOptions;
+use crate::prefilter::{build_prefilter_plan, PrefilterPlan};
+use crate::rulecheck::qualified_id;
+use crate::rules::load_configured_rule_specs;
+
+/// 单条测试用例（样本字符串作为独立缓冲区扫描）
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleTestCase {
+    pub input: String,
+    /// true：期望至少一条规则命中；false：期望没有规则命中
+    pub expect_match: bool,
+}
+
+/// 用例中命中的一条规则
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleTestMatch {
+    pub rule_id: String,
+    /// 命中值（首个匹配）
+    pub value: String,
+    /// 实际扫描时 AC 预筛是否会在该样本上触发该规则（env / der / url / 路径作用域规则不经预筛，恒为 true）
+    pub prefilter_hit: bool,
+}
+
+/// 单条用例的测试结果
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleTestOutcome {
+    pub input: String,
+    pub expect_match: bool,
+    pub matches: Vec<RuleTestMatch>,
+    pub passed: bool,
+}
+
+/// 用例文件结构（TOML）：`expect_match = [...]`、`expect_no_match = [...]`
+#[derive(Debug, Deserialize)]
+struct RuleTestFile {
+    #[serde(default)]
+    expect_match: Vec<String>,
+    #[serde(default)]
+    expect_no_match: Vec<String>,
+}
+
+/// 从 TOML 用例文件加载测试用例（正例在前、反例在后）
+pub fn load_rule_test_cases(path: &Path) -> Result<Vec<RuleTestCase>> {
+    let txt = std::fs::read_to_string(path).with_context(|| format!("read test cases {:?}", path))?;
+    let parsed: RuleTestFile = toml::from_str(&txt).with_context(|| format!("parse test cases {:?}", path))?;
+    let mut cases: Vec<RuleTestCase> = parsed
+        .expect_match
+        .into_iter()
+        .map(|input| RuleTestCase { input, expect_match: true })
+        .collect();
+    cases.extend(parsed.expect_no_match.into_iter().map(|input| RuleTestCase { input, expect_match: false }));
+    Ok(cases)
+}
+
+/// 按 `opts.rules_path` 加载规则并对每条用例运行全部规则
+pub fn run_rule_tests(opts: &ScanOptions, cases: &[RuleTestCase]) -> Result<Vec<RuleTestOutcome>> {
+    let specs = load_configured_rule_specs(opts)?;
+    let plan = build_prefilter_plan(&specs, opts);
+    Ok(cases.iter().map(|c| run_case(&plan, c)).collect())
+}
+
+/// 规则自带样本的检查结果（每个样本一条）
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleExampleOutcome {
+    pub rule_id: String,
+    pub input: String,
+    /// true：来自 `examples`（应命中）；false：来自 `negatives`（不应命中）
+    pub expect_match: bool,
+    /// 本规则在样本上的命中（若有）
+    pub matched: Option<RuleTestMatch>,
+    pub passed: bool,
+}
+
+/// 按 `opts.rules_path` 加载规则，检查每条规则自带的 `examples` / `negatives`（按规则加载顺序，正例在前）；
+/// 正例须被本规则命中且实际扫描时会被预筛触发，反例须不被本规则命中（其他规则命中与否不影响结果）
+pub fn run_rule_examples(opts: &ScanOptions) -> Result<Vec<RuleExampleOutcome>> {
+    let specs = load_configured_rule_specs(opts)?;
+    let plan = build_prefilter_plan(&specs, opts);
+    let mut outcomes = Vec::new();
+    for (ri, spec) in plan.rules.iter().enumerate() {
+        let samples = spec.examples.iter().map(|s| (s, true)).chain(spec.negatives.iter().map(|s| (s, false)));
+        for (input, expect_match) in samples {
+            let buf = input.as_bytes();
+            let matched = match_rule(&plan, ri, buf, &triggered_rules(&plan, buf));
+            let passed = if expect_match { matched.as_ref().is_some_and(|m| m.prefilter_hit) } else { matched.is_none() };
+            outcomes.push(RuleExampleOutcome { rule_id: qualified_id(spec), input: input.clone(), expect_match, matched, passed });
+        }
+    }
+    Ok(outcomes)
+}
+
+/// `classify` 的单条命中：规则与命中值在输入字符串中的字节区间
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RuleMatch {
+    pub rule_id: String,
+    pub value: String,
+    /// 命中值起始字节偏移
+    pub start: usize,
+    /// 命中值结束字节偏移（不含）：`start + value.len()`；url 规则的命中值为百分号解码后的 userinfo，区间按解码后长度计
+    pub end: usize,
+}
+
+/// 按 `opts.rules_path` 加载规则并对 `value` 分类，见 `classify_with_plan`；
+/// 多次调用时宜用 `load_prefilter_plan` 构建一次计划后调用 `classify_with_plan`
+pub fn classify(value: &str, opts: &ScanOptions) -> Result<Vec<RuleMatch>> {
+    let specs = load_configured_rule_specs(opts)?;
+    let plan = build_prefilter_plan(&specs, opts);
+    Ok(classify_with_plan(value, &plan))
+}
+
+/// 对单个内存字符串运行全部规则，返回各规则的全部命中（按起始偏移、再按规则加载顺序排列）：
+/// - 只报告实际扫描时会被预筛触发的规则（与 `scan_reader` 等扫描接口结论一致），锚点缺失的规则即使正则能匹配也不报告；
+/// - 不针对具体文件：路径作用域规则忽略 path，始终参与；
+/// - 不经过去重、校验器、白名单与长度 / 熵门限，同一值被多条规则命中时各报告一次。
+pub fn classify_with_plan(value: &str, plan: &PrefilterPlan) -> Vec<RuleMatch> {
+    let buf = value.as_bytes();
+    let triggered = triggered_rules(plan, buf);
+    let mut matches: Vec<(usize, RuleMatch)> = Vec::new();
+    for (ri, spec) in plan.rules.iter().enumerate() {
+        if spec.is_regex() && spec.path.is_none() && !triggered.contains(&ri) { continue; }
+        matches.extend(rule_findings(plan, ri, buf).into_iter().map(|f| {
+            let end = f.start_offset + f.value.len();
+            (ri, RuleMatch { rule_id: spec.id.clone(), start: f.start_offset, end, value: f.value })
+        }));
+    }
+    matches.sort_by_key(|(ri, m)| (m.start, *ri, m.end));
+    matches.dedup();
+    matches.into_iter().map(|(_, m)| m).collect()
+}
+
+/// 预筛在该样本上会触发的规则
+fn triggered_rules(plan: &PrefilterPlan, buf: &[u8]) -> HashSet<usize> {
+    let mut triggered: HashSet<usize> = HashSet::new();
+    for m in plan.ac.find_iter(buf) {
+        let aid = m.pattern().as_usize();
+        for &ri in plan.anchor_to_rules[aid].iter() {
+            if plan.rule_allowed_in_window(aid, ri, buf) { triggered.insert(ri); }
+        }
+    }
+    triggered
+}
+
+/// 在整个样本上运行规则 `ri`，返回首个命中
+fn match_rule(plan: &PrefilterPlan, ri: usize, buf: &[u8], triggered: &HashSet<usize>) -> Option<RuleTestMatch> {
+    let spec = &plan.rules[ri];
+    rule_findings(plan, ri, buf).into_iter().next().map(|f| RuleTestMatch {
+        rule_id: spec.id.clone(),
+        value: f.value,
+        prefilter_hit: !spec.is_regex() || spec.path.is_some() || triggered.contains(&ri),
+    })
+}
+
+/// 在整个样本上运行规则 `ri`（不经过窗口），返回全部命中
+fn rule_findings(plan: &PrefilterPlan, ri: usize, buf: &[u8]) -> Vec<Finding> {
+    let spec = &plan.rules[ri];
+    let mut found: Vec<Finding> = Vec::new();
+    if !spec.is_regex() {
+        // 用例不针对具体文件：忽略路径作用域
+        let mut unscoped = spec.clone();
+        unscoped.path = None;
+        let rules = std::slice::from_ref(&unscoped);
+        found = scan_env_lines(buf, 0, "", Path::new(""), rules);
+        found.extend(scan_der_blocks(buf, 0, "", Path::new(""), rules));
+        found.extend(scan_url_credentials(buf, 0, "", Path::new(""), rules));
+        found.extend(scan_keyword_entropy(buf, 0, "", Path::new(""), rules));
+        found.extend(scan_marker_blocks(buf, 0, "", Path::new(""), rules));
+    } else {
+        run_rule_in_window(plan, ri, buf, true, 0, "", &mut found);
+    }
+    found
+}
+
+fn run_case(plan: &PrefilterPlan, case: &RuleTestCase) -> RuleTestOutcome {
+    let buf = case.input.as_bytes();
+    let triggered = triggered_rules(plan, buf);
+    let matches: Vec<RuleTestMatch> = (0..plan.rules.len()).filter_map(|ri| match_rule(plan, ri, buf, &triggered)).collect();
+
+    // 正例需至少一条规则命中且实际扫描时会被预筛触发；反例要求没有任何规则命中
+    let passed = if case.expect_match { matches.iter().any(|m| m.prefilter_hit) } else { matches.is_empty() };
+    RuleTestOutcome { input: case.input.clone(), expect_match: case.expect_match, matches, passed }
+}
@@ -1,213 +1,1267 @@
 //! 扫描主流程与并行调度
-use anyhow::Result;
-use std::io::Write;
+use anyhow::{bail, Context, Result};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, Condvar, Mutex};
+use globset::GlobSet;
 use walkdir::WalkDir;
 
+use crate::archives::{scan_archive, ArchiveKind};
+use crate::baseline::Baseline;
 use crate::detectors::DetectorSetUtf8;
-use crate::engine_bytes::{scan_file_bytes_prefilter, scan_file_bytes_chunked_prefilter, SMALL_FILE_MAX};
-use crate::engine_utf8::scan_file_utf8;
-use crate::findings::{sort_findings_stable, FindingPublic as Finding};
-use crate::options::{ScanEngine, ScanOptions, ScanStats};
-use crate::rules::load_rule_specs;
-use crate::prefilter::{build_prefilter_plan, PrefilterPlan};
-
-/// 扫描目录并将结果以 JSON 数组流式写入 `out`
+use crate::encoding::{prefers_utf8_engine, read_sample, SAMPLE_LEN};
+use crate::region::{read_region, Region};
+use crate::mmap::MappedRegion;
+use crate::engine_bytes::{scan_file_bytes_prefilter, scan_region_bytes, scan_file_bytes_chunked_prefilter, scan_reader_chunked_prefilter, SMALL_FILE_MAX};
+use crate::engine_utf8::{scan_file_utf8, scan_file_utf8_chunked, scan_region_utf8};
+use crate::findings::{dedup_key, sort_findings_stable, FileScan, FindingPublic as Finding, OverlapFilter};
+use crate::options::{DedupScope, FileOrder, HashMode, OutputFormat, ProgressEvent, ProgressFn, ScanEngine, ScanOptions, ScanStats, SortOrder, DEFAULT_ALLOW_VALUES};
+use crate::types::{CrossFileReport, HitPosition, HitPositionReport, OwnedOutputItem, ScanReport, ScanResult, Severity, SkipReason, SkippedFile};
+use crate::rules::{engine_sensitive_constructs, load_configured_rule_specs, load_scoring_weights, RuleSpec};
+use crate::score::ScoringWeights;
+use crate::sink::{check_field_renames, fingerprint_value, make_multi_sink, make_sink, Collected, CollectSink, FindingSink, OutputTarget, PerFileSink, RuleSortedSink};
+use crate::rulecheck::{broken_rules_error, validate_rules};
+use crate::prefilter::{analyze_prefilter, build_prefilter_plan, has_curated_prefix, log_anchor_misses, precompile_all, PrefilterPlan};
+use crate::entropy::{shannon_entropy, EntropyAlphabet};
+use crate::validate::{resolve_validators, ValidationMode, Validator};
+use crate::digest::{bytes_digest, file_digest, ContentHasher};
+use crate::walkfilter::{build_excludes, Includes, WalkFilter};
+use crate::state::{state_key, FileStamp, PreviousState, StateRecorder};
+
+/// 扫描目录（或单个文件）并将结果按 `opts.format` 写入 `out`（默认以 JSON 数组流式写出）
 /// 稳定性保证：
-/// - 文件级：先收集文件并按文件名（md5）排序，确保输出顺序可复现
-/// - 文件内：命中项按 (start_offset 升序, value 长度降序, value 字典序升序) 排序
+/// - 文件级：先收集文件并按文件名（md5）排序，确保输出顺序可复现（多个输入根目录时按 (根目录序号, 文件名) 排序）
+/// - 文件内：命中项按 (start_offset 升序, value 长度降序, value 字典序升序, rule_id 升序) 排序
+///
+/// 命中经与 `scan_to_result` 相同的收集过程缓存到内存，扫描结束后再序列化，两者顺序一致；
+/// 需要边扫边写（命中很多的大规模扫描、`max_output_bytes`）时使用 `scan_and_write_report`
 pub fn scan_and_write(input_dir: &Path, out: &mut dyn Write, opts: &ScanOptions) -> Result<ScanStats> {
+    let mut collected = Collected::default();
+    // 输出端在扫描开始时创建（嵌入元数据的开始时刻），扫描结束后才写入
+    let mut sink = None;
+    let report = scan_with_sink(ScanInput::Dirs(vec![input_dir]), opts, |specs| {
+        sink = Some(make_sink(out, opts, specs));
+        Box::new(CollectSink::new(&mut collected, specs))
+    })?;
+    let mut sink = sink.expect("sink created by the scan");
+    // 按规则排序时收集到的命中已排好序
+    collected.write_to(sink.as_mut(), &report.stats)?;
+    Ok(report.stats)
+}
+
+/// 同 `scan_and_write`，额外返回跳过的文件与（按选项生成的）跨文件共享密钥报告
+pub fn scan_and_write_report(input_dir: &Path, out: &mut dyn Write, opts: &ScanOptions) -> Result<ScanReport> {
+    scan_with_sink(ScanInput::Dirs(vec![input_dir]), opts, |specs| make_sink(out, opts, specs))
+}
+
+/// 同 `scan_and_write_report`，依次扫描多个输入根目录（结果按根目录给出的顺序、目录内按文件名排列）；
+/// 根目录间的同名文件可用 `HashMode::Relpath` 区分
+pub fn scan_roots_and_write_report(roots: &[PathBuf], out: &mut dyn Write, opts: &ScanOptions) -> Result<ScanReport> {
+    scan_with_sink(ScanInput::Dirs(roots.iter().map(PathBuf::as_path).collect()), opts, |specs| make_sink(out, opts, specs))
+}
+
+/// 同 `scan_roots_and_write_report`，但每个已扫描文件的命中各写入 `<dir>/<file_hash>.json`（JSON 数组，
+/// `format` 不生效）；没有命中的文件仅在 `emit_empty` 时写出 `[]`。需要按文件顺序输出（不支持 `SortOrder::Rule`）
+pub fn scan_roots_to_dir(roots: &[PathBuf], dir: &Path, opts: &ScanOptions) -> Result<ScanReport> {
+    if opts.sort_by == SortOrder::Rule { bail!("per-file output cannot be combined with sorting by rule"); }
+    std::fs::create_dir_all(dir).with_context(|| format!("create output directory {}", dir.display()))?;
+    scan_with_sink(ScanInput::Dirs(roots.iter().map(PathBuf::as_path).collect()), opts, |specs| Box::new(PerFileSink::new(dir, opts, specs)))
+}
+
+/// 同 `scan_roots_and_write_report`，但同一次扫描的命中同时写入多个输出目标，各自使用目标的格式与脱敏设置
+/// （如完整 JSON 报告写入文件、脱敏表格打印到标准输出）；`opts.format` 不生效
+pub fn scan_roots_to_targets<'a>(roots: &[PathBuf], targets: Vec<OutputTarget<'a>>, opts: &'a ScanOptions) -> Result<ScanReport> {
+    check_targets(&targets, opts)?;
+    scan_with_sink(ScanInput::Dirs(roots.iter().map(PathBuf::as_path).collect()), opts, |specs| make_multi_sink(targets, opts, specs))
+}
+
+/// 同 `scan_bytes_and_write_report`，写入多个输出目标（见 `scan_roots_to_targets`）
+pub fn scan_bytes_to_targets<'a>(name: &str, content: &[u8], targets: Vec<OutputTarget<'a>>, opts: &'a ScanOptions) -> Result<ScanReport> {
+    check_targets(&targets, opts)?;
+    scan_with_sink(ScanInput::Buffer { name, content }, opts, |specs| make_multi_sink(targets, opts, specs))
+}
+
+/// 按文件分组的输出需要文件顺序，不能与按规则排序同时使用
+fn check_targets(targets: &[OutputTarget<'_>], opts: &ScanOptions) -> Result<()> {
+    if opts.sort_by == SortOrder::Rule && targets.iter().any(|t| t.format == OutputFormat::GroupedJson) {
+        bail!("grouped JSON output cannot be combined with sorting by rule");
+    }
+    Ok(())
+}
+
+/// 扫描单段内存内容（如标准输入）并按 `opts.format` 写入 `out`
+/// - `name`：逻辑文件名，用作输出中的 file_hash，并参与路径作用域规则匹配
+/// - 内容整体按小文件路径扫描（不分块）；`max_file_size` 同样生效
+pub fn scan_bytes_and_write_report(name: &str, content: &[u8], out: &mut dyn Write, opts: &ScanOptions) -> Result<ScanReport> {
+    scan_with_sink(ScanInput::Buffer { name, content }, opts, |specs| make_sink(out, opts, specs))
+}
+
+/// 扫描目录并一次性返回全部结果（命中项、统计、跳过的文件），适合库调用方直接使用
+/// 顺序与 `scan_and_write` 一致；输出格式相关选项（format、字段重映射等）不生效
+pub fn scan_to_result(input_dir: &Path, opts: &ScanOptions) -> Result<ScanResult> {
+    let mut collected = Collected::default();
+    let report = scan_with_sink(ScanInput::Dirs(vec![input_dir]), opts, |specs| Box::new(CollectSink::new(&mut collected, specs)))?;
+    Ok(ScanResult { items: collected.into_items(), stats: report.stats, skipped: report.skipped })
+}
+
+/// 从任意 `Read`（标准输入、网络流、解压流等）分块扫描，复用大文件的分块预筛逻辑（Bytes 引擎）
+/// - 读取器无需可定位，内存占用与大文件分块扫描相同（约一个块加 512 字节重叠区）；
+/// - `file_hash`：用作输出中的 file_hash，同时作为逻辑路径参与路径作用域规则与注释剥离的匹配；
+/// - `plan` 可由 `load_prefilter_plan` 构建并在多次调用间复用；`engine`、`head_bytes` / `tail_bytes`、
+///   `max_file_size` 不生效；
+/// - 返回按偏移排序的命中，与目录扫描一样经过长度上限、校验与指纹处理；读取出错时返回错误。
+pub fn scan_reader<R: Read>(mut reader: R, file_hash: &str, opts: &ScanOptions, plan: &PrefilterPlan) -> Result<Vec<OwnedOutputItem>> {
+    let mut collected = Collected::default();
+    let mut emitter = Emitter::new(Box::new(CollectSink::new(&mut collected, &plan.rules)), &plan.rules, opts)?;
+    let mut stats = ScanStats::default();
+    let path = Path::new(file_hash);
+    let mut emit_batch = |mut batch: Vec<Finding>| emitter.emit_all(&mut batch, &mut stats);
+    let scan = scan_reader_chunked_prefilter(&mut reader, 0, path, file_hash, plan, opts, &mut emit_batch)?;
+    emitter.emit_file(path, file_hash, Ok(scan), &mut stats)?;
+    drop(emitter);
+    Ok(collected.into_items())
+}
+
+/// 扫描输入：一个或多个目录（逐文件扫描）或单段内存内容
+enum ScanInput<'a> {
+    Dirs(Vec<&'a Path>),
+    Buffer { name: &'a str, content: &'a [u8] },
+}
+
+/// 扫描主流程：按规则构建输出端后逐条推送命中，返回统计、跳过的文件与可选报告
+fn scan_with_sink<'a>(
+    input: ScanInput<'_>,
+    opts: &ScanOptions,
+    make: impl FnOnce(&[RuleSpec]) -> Box<dyn FindingSink + 'a>,
+) -> Result<ScanReport> {
+    // 输出大小上限：Writer 超限后经取消标记通知扫描停止，调用方未提供取消标记时内部创建一个
+    let limited;
+    let opts = if opts.max_output_bytes.is_some() && opts.cancel.is_none() {
+        limited = ScanOptions { cancel: Some(Arc::new(std::sync::atomic::AtomicBool::new(false))), ..opts.clone() };
+        &limited
+    } else {
+        opts
+    };
     // 加载规则文件（默认 ./rules/default.toml）
-    let rules_path = opts
-        .rules_path
-        .clone()
-        .unwrap_or_else(|| PathBuf::from("./rules/default.toml"));
-    let rule_specs = load_rule_specs(&rules_path)?;
+    let rule_specs = load_configured_rule_specs(opts)?;
+    // 模式语法错误的规则在懒编译时只会告警并停用：扫描前统一检查，列出全部出错规则
+    validate_rules(&rule_specs).map_err(broken_rules_error)?;
+    let excludes = build_excludes(&opts.exclude_globs)?;
+    let includes = Includes::build(&opts.include_extensions, &opts.name_globs)?;
+    check_field_renames(&opts.output_fields)?;
+    if opts.check_rule_semantics { warn_engine_sensitive_rules(&rule_specs); }
     // 引擎初始化：按需构建
     // - Bytes：构建预筛计划（AC + 懒编译缓存），避免启动期编译整套正则
     // - Utf8：仅编译 UTF-8 规则集合
-    let (prefilter_plan, detectors_utf8): (Option<Arc<PrefilterPlan>>, Option<DetectorSetUtf8>) = match opts.engine {
-        ScanEngine::Bytes => (Some(build_prefilter_plan(&rule_specs)), None),
-        ScanEngine::Utf8 => (None, Some(DetectorSetUtf8::from_specs(&rule_specs)?)),
+    // - Auto：构建预筛计划；仅当存在语义随引擎变化的规则时才额外编译 UTF-8 规则集合
+    let (prefilter_plan, detectors_utf8): (Option<Arc<PrefilterPlan>>, Option<Arc<DetectorSetUtf8>>) = match opts.engine {
+        ScanEngine::Bytes => (Some(build_prefilter_plan(&rule_specs, opts)), None),
+        ScanEngine::Utf8 => (None, Some(Arc::new(DetectorSetUtf8::from_specs(&rule_specs, opts.regex_size_limit)?))),
+        ScanEngine::Auto => {
+            let plan = build_prefilter_plan(&rule_specs, opts);
+            if rule_specs.iter().any(|s| !engine_sensitive_constructs(&s.pat).is_empty()) {
+                (Some(plan), Some(Arc::new(DetectorSetUtf8::from_specs(&rule_specs, opts.regex_size_limit)?)))
+            } else {
+                tracing::info!("engine auto: no rule depends on Unicode semantics, all files use the bytes engine");
+                (Some(plan), None)
+            }
+        }
     };
+    if let Some(plan) = &prefilter_plan {
+        check_plan_diagnostics(plan, opts)?;
+    }
+    if let (true, Some(plan)) = (opts.precompile, prefilter_plan.as_ref()) {
+        let started = std::time::Instant::now();
+        let compiled = precompile_all(plan)?;
+        tracing::info!(rules = compiled, elapsed_ms = started.elapsed().as_millis() as u64, "rule regexes precompiled");
+    }
 
+    // 扫描结束时输出锚点窗口诊断（仅 `verbose_files`），并记入规则匹配超时次数与跳过的超长行数
+    let log_diagnostics = |stats: &mut ScanStats| {
+        if let Some(plan) = &prefilter_plan {
+            log_anchor_misses(plan);
+            stats.rule_timeouts = plan.rule_timeouts.load(std::sync::atomic::Ordering::Relaxed);
+            stats.long_lines_skipped = plan.long_lines_skipped.load(std::sync::atomic::Ordering::Relaxed);
+        }
+    };
     let mut stats = ScanStats::default();
+    // 增量扫描的状态键在开始写出之前计算：读取规则文件失败时不产生任何输出
+    let state_key = opts.state_path.as_ref().map(|_| state_key(opts)).transpose()?;
+    // 按规则全局排序：包装输出端，缓存全部命中，结束时排序后写出（按文件分组的输出需要文件顺序）
+    if opts.sort_by == SortOrder::Rule && opts.format == OutputFormat::GroupedJson {
+        bail!("grouped JSON output cannot be combined with sorting by rule");
+    }
+    // 只计数时不写出命中，无需排序缓存
+    let sink = match opts.sort_by {
+        SortOrder::File => make(&rule_specs),
+        SortOrder::Rule if opts.count_only => make(&rule_specs),
+        SortOrder::Rule => Box::new(RuleSortedSink::new(make(&rule_specs), opts.max_findings)),
+    };
+    if opts.max_output_bytes.is_some() && sink.bytes_written().is_none() {
+        bail!("output size limit requires streaming JSON output (json, json-v1, jsonl, grouped-json or sarif, sorted by file)");
+    }
+    let mut emitter = Emitter::new(sink, &rule_specs, opts)?;
+    emitter.sink.begin()?;
 
-    let mut files: Vec<PathBuf> = vec![];
-    // 遍历输入目录（数据集为单层目录，这里限制深度为 1）
-    for entry in WalkDir::new(input_dir).min_depth(1).max_depth(1) {
-        let entry = match entry { Ok(e) => e, Err(_) => continue };
-        if entry.file_type().is_file() { files.push(entry.into_path()); }
-    }
-    // 按文件名排序，确保输出顺序稳定
-    files.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
-
-    // 决策：若为 Bytes 引擎且线程数>1，则走并行调度；否则使用串行扫描
-    let threads = opts.threads.unwrap_or_else(|| num_cpus::get());
-    let use_parallel = matches!(opts.engine, ScanEngine::Bytes) && threads > 1;
-
-    if use_parallel {
-        // Bytes 引擎并行路径：必有预筛计划
-        let plan = prefilter_plan.as_ref().expect("prefilter plan not built");
-        scan_and_write_parallel_bytes(&files, out, opts, plan, &mut stats, threads)?;
-        return Ok(stats);
-    }
-
-    // 串行路径（保持原有逻辑，UTF-8 亦在此路径执行）
-    write!(out, "[")?;
-    let mut first = true;
-    for path in files {
-        let file_name = match path.file_name().and_then(|s| s.to_str()) { Some(s) => s, None => continue };
-        if let Some(max) = opts.max_file_size { if let Ok(md) = std::fs::metadata(&path) { if md.len() > max { continue; } } }
-        let res = match opts.engine {
-            ScanEngine::Bytes => {
-                let plan = prefilter_plan.as_ref().expect("prefilter plan not built");
-                match std::fs::metadata(&path) {
-                    Ok(md) => {
-                        if md.len() <= SMALL_FILE_MAX as u64 {
-                            scan_file_bytes_prefilter(&path, file_name, plan)
-                        } else {
-                            scan_file_bytes_chunked_prefilter(&path, file_name, plan)
-                        }
+    let roots = match input {
+        ScanInput::Dirs(roots) => roots,
+        // 单段内存内容：按小文件路径整体扫描一次
+        ScanInput::Buffer { name, content } => {
+            let path = Path::new(name);
+            if opts.emit_path { emitter.current_path = Some(name.to_string()); }
+            emitter.files_total = 1;
+            if opts.max_file_size.is_some_and(|max| content.len() as u64 > max) {
+                emitter.skip(path, SkipReason::TooLarge);
+                emitter.file_processed(path);
+            } else {
+                let digest = bytes_digest(content, opts.hash_mode);
+                let name = digest.as_deref().unwrap_or(name);
+                let res = scan_buffer(content, path, name, prefilter_plan.as_deref(), detectors_utf8.as_deref(), opts);
+                emitter.emit_file(path, name, res, &mut stats)?;
+            }
+            log_diagnostics(&mut stats);
+            emitter.finish(&mut stats)?;
+            return Ok(emitter.into_report(stats));
+        }
+    };
+    let files = collect_input_files(&roots, opts, excludes.as_ref(), includes.as_ref(), &mut emitter);
+    emitter.files_total = files.len();
+    // 增量扫描：读取上一次的状态（扫描线程只读共享），本次的状态由 Writer 端按输出顺序记录
+    let previous = match (&opts.state_path, state_key) {
+        (Some(path), Some(key)) => {
+            emitter.state = Some(StateRecorder::new(path.clone(), key.clone()));
+            PreviousState::load(path, &key).map(Arc::new)
+        }
+        _ => None,
+    };
+
+    // 决策：线程数>1 时走并行调度（各引擎共用同一调度，单文件扫描逻辑与串行一致）；否则使用串行扫描
+    let threads = opts.threads.unwrap_or_else(num_cpus::get);
+
+    if threads > 1 {
+        // 规则集合与扫描选项跨线程共享（`Regex` 与预筛计划均为 Sync）
+        let (plan, det, prev) = (prefilter_plan.clone(), detectors_utf8.clone(), previous.clone());
+        let worker_opts = Arc::new(opts.clone());
+        let scan_file = move |path: &Path, file_name: &str, emit_batch: &mut dyn FnMut(Vec<Finding>) -> Result<()>| {
+            scan_one_file(path, file_name, plan.as_deref(), det.as_deref(), prev.as_deref(), &worker_opts, emit_batch)
+        };
+        scan_and_write_parallel(&files, &mut emitter, opts, scan_file, &mut stats, threads)?;
+        log_diagnostics(&mut stats);
+        emitter.finish(&mut stats)?;
+        return Ok(emitter.into_report(stats));
+    }
+
+    // 串行路径
+    for InputFile { path, file_hash, rel_path } in files {
+        if opts.is_cancelled() { break; }
+        emitter.current_path = rel_path;
+        let file_name = match file_hash.as_deref() {
+            Some(s) => s,
+            None => {
+                emitter.skip(&path, SkipReason::Error("invalid file name".into()));
+                emitter.file_processed(&path);
+                continue;
+            }
+        };
+        if let Some(max) = file_size_limit(&path, opts.max_file_size, opts.scan_archives) {
+            if std::fs::metadata(&path).is_ok_and(|md| md.len() > max) {
+                emitter.skip(&path, SkipReason::TooLarge);
+                emitter.file_processed(&path);
+                continue;
+            }
+        }
+        // 分块扫描中已确定的命中先行输出（文件内顺序不变）
+        let mut emit_batch = |mut batch: Vec<Finding>| emitter.emit_all(&mut batch, &mut stats);
+        let res = scan_one_file(&path, file_name, prefilter_plan.as_deref(), detectors_utf8.as_deref(), previous.as_deref(), opts, &mut emit_batch);
+        emitter.emit_file(&path, file_name, res, &mut stats)?;
+    }
+    log_diagnostics(&mut stats);
+    emitter.finish(&mut stats)?;
+    Ok(emitter.into_report(stats))
+}
+
+/// 待扫描的文件
+struct InputFile {
+    path: PathBuf,
+    /// 输出中的 file_hash（按 `hash_mode` 计算）；文件名不是合法 UTF-8 时为 None
+    file_hash: Option<String>,
+    /// 输出中的 path（相对输入根目录的路径，仅 `emit_path` 时计算）
+    rel_path: Option<String>,
+}
+
+/// 收集各输入根目录下待扫描的文件（深度见 `max_depth`；输入根为文件时即该文件本身），按 (根目录序号, 相对路径) 排序，再按 `sample` 抽样、按 `max_files` 截断；
+/// 按 `exclude_globs` / `respect_gitignore` 排除的条目与不满足 `include_extensions` / `name_globs` 的文件不计入；遍历出错的条目（如目录不可读）记为跳过
+fn collect_input_files(roots: &[&Path], opts: &ScanOptions, excludes: Option<&GlobSet>, includes: Option<&Includes>, emitter: &mut Emitter) -> Vec<InputFile> {
+    let mut files: Vec<InputFile> = vec![];
+    for (root_idx, root) in roots.iter().enumerate() {
+        let mut root_files: Vec<PathBuf> = vec![];
+        // 遍历输入目录（缺省深度为 1：数据集为单层目录）；输入为单个文件时只产生该文件本身（深度 0）
+        let is_file = std::fs::metadata(root).is_ok_and(|md| md.is_file());
+        let mut walker = WalkDir::new(root).min_depth(if is_file { 0 } else { 1 }).follow_links(opts.follow_symlinks);
+        if let Some(depth) = opts.max_depth { walker = walker.max_depth(depth); }
+        let mut filter = WalkFilter::new(root, excludes.cloned(), opts.respect_gitignore);
+        // 跟随符号链接时已进入的目录（规范化路径）：多个链接指向同一目录时只进入一次
+        let mut visited_dirs: HashSet<PathBuf> = HashSet::new();
+        if opts.follow_symlinks {
+            if let Ok(canonical) = std::fs::canonicalize(root) { visited_dirs.insert(canonical); }
+        }
+        let walk = walker.into_iter().filter_entry(|e| {
+            if filter.is_excluded(e.path(), e.file_type().is_dir()) { return false; }
+            if opts.follow_symlinks && e.file_type().is_dir() {
+                if let Ok(canonical) = std::fs::canonicalize(e.path()) {
+                    if !visited_dirs.insert(canonical) {
+                        tracing::debug!(path = %e.path().display(), "directory already visited through another symlink, skipped");
+                        return false;
                     }
-                    Err(_) => Err(anyhow::anyhow!("metadata failed")),
                 }
             }
-            ScanEngine::Utf8 => {
-                let det = detectors_utf8.as_ref().expect("utf8 detectors not built");
-                scan_file_utf8(&path, file_name, det)
-            }
+            true
+        });
+        for entry in walk {
+            let entry = match entry {
+                Ok(e) => e,
+                // 指向祖先目录的符号链接：跳过该链接，不记为跳过的文件
+                Err(e) if e.loop_ancestor().is_some() => {
+                    tracing::debug!(path = %e.path().unwrap_or(root).display(), "symlink loop, skipped");
+                    continue;
+                }
+                Err(e) => {
+                    emitter.skip(e.path().unwrap_or(root), SkipReason::ReadError(e.to_string()));
+                    continue;
+                }
+            };
+            // 包含条件只作用于遍历到的文件：直接作为输入给出的文件不受影响
+            let included = entry.path() == *root || includes.is_none_or(|inc| inc.matches(entry.path(), root));
+            if entry.file_type().is_file() && included { root_files.push(entry.into_path()); }
+        }
+        // 根目录内按相对路径（逐级比较，单层时即文件名）排序，确保输出顺序稳定；
+        // 不同子目录下的同名文件也有确定的先后
+        root_files.sort();
+        files.extend(root_files.into_iter().map(|path| {
+            let file_hash = file_hash_of(&path, root, root_idx, roots.len(), opts.hash_mode);
+            let rel_path = if opts.emit_path { file_hash_of(&path, root, root_idx, roots.len(), HashMode::Relpath) } else { None };
+            InputFile { path, file_hash, rel_path }
+        }));
+    }
+    if (roots.len() > 1 || opts.max_depth != Some(1)) && opts.hash_mode == HashMode::Name {
+        let mut seen = std::collections::HashSet::new();
+        let dups = files.iter().filter_map(|f| f.file_hash.as_deref()).filter(|h| !seen.insert(*h)).count();
+        if dups > 0 {
+            tracing::warn!(duplicates = dups, "same file name in several directories, file_hash is ambiguous (use --hash relpath)");
+        }
+    }
+    // 随机抽样：在排序后按种子选取，扫描子集可复现
+    if let Some(n) = opts.sample {
+        if files.len() > n {
+            tracing::info!(total = files.len(), sample = n, seed = opts.seed, "scanning a random sample of the input files");
+            let keep = sample_indices(files.len(), n, opts.seed);
+            files = files.into_iter().enumerate().filter(|(i, _)| keep.contains(i)).map(|(_, f)| f).collect();
+        }
+    }
+    // 文件数上限：在排序后截断，扫描子集可复现
+    if let Some(max) = opts.max_files {
+        if files.len() > max {
+            tracing::warn!(total = files.len(), max_files = max, "file count limit reached, scanning the first files only");
+            files.truncate(max);
+        }
+    }
+    files
+}
+
+/// 从 `0..len` 中按种子确定性地选出 `n` 个下标（部分 Fisher-Yates 洗牌，SplitMix64 随机数）
+fn sample_indices(len: usize, n: usize, seed: u64) -> HashSet<usize> {
+    let mut state = seed;
+    let mut next = || {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    };
+    let mut idx: Vec<usize> = (0..len).collect();
+    for i in 0..n.min(len) {
+        // [i, len) 内均匀取一个下标（乘法取高位，避免取模偏差）
+        let j = i + ((next() as u128 * (len - i) as u128) >> 64) as usize;
+        idx.swap(i, j);
+    }
+    idx.truncate(n);
+    idx.into_iter().collect()
+}
+
+/// 按 `hash_mode` 计算文件的 file_hash：文件名，或相对根目录的路径（多个根目录时加 `<序号>/` 前缀）
+fn file_hash_of(path: &Path, root: &Path, root_idx: usize, root_count: usize, mode: HashMode) -> Option<String> {
+    match mode {
+        // 内容摘要在扫描时计算，此处的文件名只用于日志
+        HashMode::Name | HashMode::Sha256 | HashMode::Md5 => path.file_name()?.to_str().map(str::to_string),
+        HashMode::Relpath => {
+            // 输入根为文件本身时，相对路径取文件名
+            let rel = if path == root { Path::new(path.file_name()?) } else { path.strip_prefix(root).ok()? };
+            let rel = rel.iter().map(|c| c.to_str()).collect::<Option<Vec<_>>>()?.join("/");
+            Some(if root_count > 1 { format!("{}/{}", root_idx, rel) } else { rel })
+        }
+    }
+}
+
+/// 按 `opts.engine` 扫描单个文件（串行与并行路径共用）：小文件整读，超大文件分块，
+/// 分块扫描中已确定的命中经 `emit_batch` 先行输出；`scan_archives` 时压缩包逐条目在内存中扫描；
+/// `use_mmap` 时不小于 `MMAP_MIN` 且在整读范围内的文件整体映射后扫描。所选引擎需要的规则集合必须已构建；
+/// 增量扫描时先记录文件大小与修改时间，与上一次的状态（`previous`）一致则直接复用上次的结果
+fn scan_one_file(
+    path: &Path,
+    file_name: &str,
+    plan: Option<&PrefilterPlan>,
+    detectors_utf8: Option<&DetectorSetUtf8>,
+    previous: Option<&PreviousState>,
+    opts: &ScanOptions,
+    emit_batch: &mut dyn FnMut(Vec<Finding>) -> Result<()>,
+) -> Result<FileScan> {
+    let stamp = opts.state_path.as_ref().and_then(|_| FileStamp::of(path));
+    if let (Some(prev), Some(stamp)) = (previous, stamp) {
+        if let Some(scan) = prev.reuse(path, stamp, file_name) { return Ok(scan); }
+    }
+    let Some(mut hasher) = ContentHasher::new(opts.hash_mode) else {
+        let mut scan = scan_file_contents(path, file_name, plan, detectors_utf8, opts, None, emit_batch)?;
+        scan.stamp = stamp;
+        return Ok(scan);
+    };
+    // 内容摘要模式：扫描读取文件的同时计算摘要，命中先以文件名作为 file_hash，结束后改写为摘要。
+    // 摘要在读完整个文件之前未知，分块路径先行输出的批次暂存到扫描结束（按原顺序排在其余命中之前）
+    let file_len = std::fs::metadata(path)?.len();
+    let mut early: Vec<Finding> = Vec::new();
+    let mut scan = scan_file_contents(path, file_name, plan, detectors_utf8, opts, Some(&mut hasher), &mut |batch| {
+        early.extend(batch);
+        Ok(())
+    })?;
+    // 扫描只读取了文件的一部分（局部扫描、二进制判定后提前结束、压缩包等）时另行读取一遍计算摘要
+    let digest = if hasher.len() == file_len {
+        hasher.finish()
+    } else {
+        file_digest(path, opts.hash_mode)?.expect("content hash mode")
+    };
+    early.append(&mut scan.findings);
+    for f in &mut early {
+        if let Some(rest) = f.file_hash.strip_prefix(file_name) {
+            f.file_hash = format!("{}{}", digest, rest);
+        }
+    }
+    scan.findings = early;
+    scan.content_hash = Some(digest);
+    scan.stamp = stamp;
+    Ok(scan)
+}
+
+/// `scan_one_file` 的扫描部分：`file_name` 为命中的 file_hash；`hasher` 为 Some 时读取文件的同时计算内容摘要
+fn scan_file_contents(
+    path: &Path,
+    file_name: &str,
+    plan: Option<&PrefilterPlan>,
+    detectors_utf8: Option<&DetectorSetUtf8>,
+    opts: &ScanOptions,
+    mut hasher: Option<&mut ContentHasher>,
+    emit_batch: &mut dyn FnMut(Vec<Finding>) -> Result<()>,
+) -> Result<FileScan> {
+    if let Some(kind) = ArchiveKind::of(path).filter(|_| opts.scan_archives) {
+        let scan_entry = |buf: &[u8], entry_path: &Path, entry_hash: &str| {
+            scan_buffer(buf, entry_path, entry_hash, plan, detectors_utf8, opts)
         };
-        match res {
-            Ok(mut findings) => {
-                stats.files_scanned += 1;
-                // 文件内稳定排序
-                sort_findings_stable(&mut findings);
-                for f in findings.iter() {
-                    stats.outputs_written += 1;
-                    if !first { write!(out, ",")?; } else { first = false; }
-                    let item = serde_json::json!({ "file_hash": f.file_hash, "value": f.value });
-                    serde_json::to_writer(&mut *out, &item)?;
+        return scan_archive(path, kind, file_name, opts, scan_entry);
+    }
+    if opts.use_mmap {
+        // 只映射整读范围内的文件：超大文件仍走分块读取，内存占用与块大小（`memory_budget`）保持一致
+        if let Some(map) = MappedRegion::open(path, opts, SMALL_FILE_MAX as u64)? {
+            let (buf, region) = (map.bytes(), &map.region);
+            if let Some(h) = hasher.as_deref_mut() { h.update(buf); }
+            let res = match opts.engine {
+                ScanEngine::Bytes => Ok(scan_region_bytes(buf, region, path, file_name, plan.expect("prefilter plan not built"), opts)),
+                ScanEngine::Utf8 => scan_region_utf8(buf, region, path, file_name, detectors_utf8.expect("utf8 detectors not built"), opts),
+                ScanEngine::Auto => scan_region_auto(buf, region, path, file_name, plan.expect("prefilter plan not built"), detectors_utf8, opts),
+            };
+            // 扫描期间文件被改写：结果不可靠，按读取失败跳过
+            map.check_unchanged(path)?;
+            return res;
+        }
+    }
+    match opts.engine {
+        ScanEngine::Bytes => {
+            let plan = plan.expect("prefilter plan not built");
+            if Region::of(std::fs::metadata(path)?.len(), opts).len <= SMALL_FILE_MAX as u64 {
+                scan_file_bytes_prefilter(path, file_name, plan, opts, hasher)
+            } else {
+                scan_file_bytes_chunked_prefilter(path, file_name, plan, opts, hasher, emit_batch)
+            }
+        }
+        ScanEngine::Utf8 => {
+            let det = detectors_utf8.expect("utf8 detectors not built");
+            // 与 Bytes 引擎一致：小文件整读，超大文件分块（避免超长单行文件 OOM）
+            if Region::of(std::fs::metadata(path)?.len(), opts).len <= SMALL_FILE_MAX as u64 {
+                scan_file_utf8(path, file_name, det, opts, hasher)
+            } else {
+                scan_file_utf8_chunked(path, file_name, det, opts, hasher)
+            }
+        }
+        ScanEngine::Auto => {
+            let plan = plan.expect("prefilter plan not built");
+            scan_file_auto(path, file_name, plan, detectors_utf8, opts, hasher, emit_batch)
+        }
+    }
+}
+
+/// 按文件本身的大小跳过时的上限（`max_file_size`）：扫描压缩包内容时压缩包按条目解压后的大小另行约束，不受此限
+fn file_size_limit(path: &Path, max_file_size: Option<u64>, scan_archives: bool) -> Option<u64> {
+    max_file_size.filter(|_| !(scan_archives && ArchiveKind::of(path).is_some()))
+}
+
+/// 扫描已读入内存的完整内容（标准输入内容、压缩包条目）：局部扫描（head_bytes / tail_bytes）时只扫描区域内的内容，
+/// 偏移还原为绝对偏移；`path` 为逻辑路径，仅用于路径作用域规则与注释剥离
+fn scan_buffer(
+    content: &[u8],
+    path: &Path,
+    name: &str,
+    plan: Option<&PrefilterPlan>,
+    detectors_utf8: Option<&DetectorSetUtf8>,
+    opts: &ScanOptions,
+) -> Result<FileScan> {
+    let region = Region::of(content.len() as u64, opts);
+    let content = region.slice(content);
+    match opts.engine {
+        ScanEngine::Bytes => {
+            let plan = plan.expect("prefilter plan not built");
+            Ok(scan_region_bytes(content, &region, path, name, plan, opts))
+        }
+        ScanEngine::Utf8 => {
+            let det = detectors_utf8.expect("utf8 detectors not built");
+            scan_region_utf8(content, &region, path, name, det, opts)
+        }
+        ScanEngine::Auto => {
+            let plan = plan.expect("prefilter plan not built");
+            scan_region_auto(content, &region, path, name, plan, detectors_utf8, opts)
+        }
+    }
+}
+
+/// Auto 引擎的单文件扫描：存在 UTF-8 规则集且文件开头抽样判定为 UTF-8 文本时使用 UTF-8 引擎，
+/// 否则使用 Bytes 引擎；UTF-8 引擎因抽样之后的非法编码失败时，该文件回退到 Bytes 引擎重扫。
+/// 小文件整读一次，抽样、扫描与回退重扫都作用于同一缓冲区（见 `scan_region_auto`）；
+/// 超大文件回退重扫时重新读取，摘要随之从头计算
+fn scan_file_auto(
+    path: &Path,
+    file_name: &str,
+    plan: &PrefilterPlan,
+    detectors_utf8: Option<&DetectorSetUtf8>,
+    opts: &ScanOptions,
+    mut hasher: Option<&mut ContentHasher>,
+    emit_batch: &mut dyn FnMut(Vec<Finding>) -> Result<()>,
+) -> Result<FileScan> {
+    if Region::of(std::fs::metadata(path)?.len(), opts).len <= SMALL_FILE_MAX as u64 {
+        let (buf, region) = read_region(path, opts, hasher)?;
+        return scan_region_auto(&buf, &region, path, file_name, plan, detectors_utf8, opts);
+    }
+    if let Some(det) = detectors_utf8 {
+        if prefers_utf8_engine(&read_sample(path)?) {
+            match scan_file_utf8_chunked(path, file_name, det, opts, hasher.as_deref_mut()) {
+                Err(e) if is_invalid_utf8(&e) => {
+                    tracing::debug!(file = %path.display(), "invalid UTF-8 after the sampled prefix, rescanning with the bytes engine");
                 }
+                res => return res,
             }
-            Err(_) => continue,
         }
     }
-    write!(out, "]")?;
-    Ok(stats)
+    scan_file_bytes_chunked_prefilter(path, file_name, plan, opts, hasher, emit_batch)
 }
 
-/// 并行调度（Bytes 引擎）：
+/// Auto 引擎扫描已读入内存的区域内容（小文件整读后、或标准输入内容；`buf` 即 `region` 覆盖的字节）：
+/// 判定规则同 `scan_file_auto`，抽样取自缓冲区开头，UTF-8 引擎失败时在同一缓冲区上回退到 Bytes 引擎
+fn scan_region_auto(
+    buf: &[u8],
+    region: &Region,
+    path: &Path,
+    file_name: &str,
+    plan: &PrefilterPlan,
+    detectors_utf8: Option<&DetectorSetUtf8>,
+    opts: &ScanOptions,
+) -> Result<FileScan> {
+    if let Some(det) = detectors_utf8 {
+        let (start, end) = region.utf8_bounds(buf);
+        if prefers_utf8_engine(&buf[start..end.min(start + SAMPLE_LEN)]) {
+            match scan_region_utf8(buf, region, path, file_name, det, opts) {
+                Err(e) if is_invalid_utf8(&e) => {
+                    tracing::debug!(file = %path.display(), "invalid UTF-8 after the sampled prefix, rescanning with the bytes engine");
+                }
+                res => return res,
+            }
+        }
+    }
+    Ok(scan_region_bytes(buf, region, path, file_name, plan, opts))
+}
+
+/// 错误是否源于非法 UTF-8（整读的 `Utf8Error` 或分块读取的 `InvalidData`）
+fn is_invalid_utf8(e: &anyhow::Error) -> bool {
+    e.chain().any(|c| {
+        c.is::<std::str::Utf8Error>()
+            || c.is::<std::string::FromUtf8Error>()
+            || c.downcast_ref::<std::io::Error>().is_some_and(|io| io.kind() == std::io::ErrorKind::InvalidData)
+    })
+}
+
+/// 并行调度（各引擎通用，`scan_file` 扫描单个文件，见 `scan_one_file`）：
 /// - 建索引后使用 Rayon 线程池并行扫描
-/// - 单线程 Writer 按 idx 重排并流式写 JSON，保证稳定顺序
-fn scan_and_write_parallel_bytes(
-    files: &[PathBuf],
-    out: &mut dyn Write,
+/// - 单线程 Writer 按 idx 重排并流式推送到输出端，保证稳定顺序
+/// - 背压（`reorder_window`）：worker 按 idx 顺序领取文件，idx 超出 Writer 位置 + 窗口时等待，
+///   重排缓存中的文件数因此有上限（见 `ReorderGate`）
+/// - 大文件分块扫描时按批次发送已确定的命中，Writer 在该文件轮到输出时逐批写出，
+///   不必在通道与重排缓存中持有整个文件的命中（尚未轮到的文件的批次仍会缓存）
+fn scan_and_write_parallel<F>(
+    files: &[InputFile],
+    emitter: &mut Emitter,
     opts: &ScanOptions,
-    plan: &Arc<PrefilterPlan>,
+    scan_file: F,
     stats: &mut ScanStats,
     threads: usize,
-) -> Result<()> {
+) -> Result<()>
+where
+    F: Fn(&Path, &str, &mut dyn FnMut(Vec<Finding>) -> Result<()>) -> Result<FileScan> + Send + Sync + 'static,
+{
     use crossbeam_channel as channel;
     use rayon::prelude::*;
 
-    // 写 JSON 开始符
-    write!(out, "[")?;
-    let mut first = true;
-
     // 通道用于 worker → writer 传递结果
-    type Msg = (usize /*idx*/, Vec<Finding> /*findings*/, bool /*scanned*/);
+    type Msg = (usize /*idx*/, FilePart);
     let (tx, rx) = channel::bounded::<Msg>(256);
 
     // 为防止 &mut out 的跨线程所有权问题，Writer 保持在当前线程
     // 扫描在后台线程内创建 Rayon 线程池并执行
-    let plan = Arc::clone(plan);
-    let max_file_size = opts.max_file_size;
+    let (max_file_size, scan_archives) = (opts.max_file_size, opts.scan_archives);
+    let cancel = opts.cancel.clone();
 
-    let files_vec: Vec<(usize, PathBuf)> = files
-        .iter()
-        .enumerate()
-        .map(|(i, p)| (i, p.clone()))
-        .collect();
+    // idx 始终为文件名顺序（Writer 按 idx 输出），处理顺序按 file_order 重排
+    let files_vec = processing_order(files, opts.file_order);
+    let ordered = opts.file_order != FileOrder::Name;
+    let gate = (opts.reorder_window > 0 && !ordered).then(|| Arc::new(ReorderGate::new(opts.reorder_window)));
+    let worker_gate = gate.clone();
 
     let scan_thread = std::thread::spawn(move || {
         let pool = rayon::ThreadPoolBuilder::new()
             .num_threads(threads)
             .build()
             .expect("build rayon pool");
-        pool.install(|| {
-            files_vec.par_iter().for_each(|(idx, path)| {
-                // 路径与文件名
-                let file_name = match path.file_name().and_then(|s| s.to_str()) { Some(s) => s.to_string(), None => { let _ = tx.send((*idx, Vec::new(), false)); return; } };
-                // 大小过滤（与串行一致）
-                if let Some(max) = max_file_size { if let Ok(md) = std::fs::metadata(path) { if md.len() > max { let _ = tx.send((*idx, Vec::new(), false)); return; } } }
-
-                // 选择读取策略：小文件整读，超大文件分块
-                let findings_res = match std::fs::metadata(path) {
-                    Ok(md) => {
-                        let sz = md.len();
-                        if sz <= SMALL_FILE_MAX as u64 {
-                            crate::engine_bytes::scan_file_bytes_prefilter(path, &file_name, &plan)
-                        } else {
-                            crate::engine_bytes::scan_file_bytes_chunked_prefilter(path, &file_name, &plan)
-                        }
-                    }
-                    Err(_) => Err(anyhow::anyhow!("metadata failed")),
-                };
-
-                match findings_res {
-                    Ok(mut findings) => {
-                        // 稳定排序可在 writer 进行；此处也可预排序以降低主线程负担
-                        sort_findings_stable(&mut findings);
-                        let _ = tx.send((*idx, findings, true));
-                    }
-                    Err(_) => { let _ = tx.send((*idx, Vec::new(), false)); }
+        let scan_one = |(idx, path, file_hash): &(usize, PathBuf, Option<String>)| {
+            // 已取消：不再扫描，通知 Writer 越过该文件
+            if cancel.as_ref().is_some_and(|c| c.load(std::sync::atomic::Ordering::Relaxed)) { let _ = tx.send((*idx, FilePart::Cancelled)); return; }
+            // 背压：等待 Writer 追上
+            if let Some(gate) = &worker_gate { gate.wait_turn(*idx); }
+            // 路径与文件名
+            let file_name = match file_hash { Some(s) => s.clone(), None => { let _ = tx.send((*idx, FilePart::Done(FileScan::default(), Some(SkipReason::Error("invalid file name".into()))))); return; } };
+            // 大小过滤（与串行一致）
+            if let Some(max) = file_size_limit(path, max_file_size, scan_archives) { if let Ok(md) = std::fs::metadata(path) { if md.len() > max { let _ = tx.send((*idx, FilePart::Done(FileScan::default(), Some(SkipReason::TooLarge)))); return; } } }
+
+            // 分块扫描中已确定的命中按批次先行发送
+            let mut send_batch = |batch| {
+                let _ = tx.send((*idx, FilePart::Batch(batch)));
+                Ok(())
+            };
+            let findings_res = scan_file(path, &file_name, &mut send_batch);
+
+            match findings_res {
+                Ok(mut scan) => {
+                    // 稳定排序可在 writer 进行；此处也可预排序以降低主线程负担
+                    sort_findings_stable(&mut scan.findings);
+                    let _ = tx.send((*idx, FilePart::Done(scan, None)));
                 }
-            });
+                Err(e) => { let _ = tx.send((*idx, FilePart::Done(FileScan::default(), Some(skip_reason(&e))))); }
+            }
+        };
+        pool.install(|| {
+            // 非默认顺序：par_bridge 按迭代顺序取任务，保证先处理排在前面的文件；
+            // 背压同样要求按 idx 顺序领取，否则等待中的 worker 可能持有 Writer 需要的下一个文件之后的全部任务
+            if ordered || worker_gate.is_some() {
+                files_vec.iter().par_bridge().for_each(scan_one);
+            } else {
+                files_vec.par_iter().for_each(scan_one);
+            }
         });
         // 结束后 Sender 全部被丢弃，Receiver 将收到关闭信号
     });
 
-    // Writer：维护 next_idx 与缓存，按序输出
-    use std::collections::BTreeMap;
+    // Writer：维护 next_idx 与缓存，按序输出；当前文件的批次到达即输出，不等待整个文件
     let mut next_idx: usize = 0;
-    let mut buffer: BTreeMap<usize, (Vec<Finding>, bool)> = BTreeMap::new();
+    let mut buffer: BTreeMap<usize, Vec<FilePart>> = BTreeMap::new();
+    let mut peak_buffered = 0usize;
 
-    while let Ok((idx, findings, scanned)) = rx.recv() {
-        buffer.insert(idx, (findings, scanned));
-        // 尝试从 next_idx 开始顺序冲刷
-        while let Some((mut findings, scanned)) = buffer.remove(&next_idx).map(|v| v) {
-            if scanned { stats.files_scanned += 1; }
-            // 文件内稳定排序已在 worker 执行；此处再保证一次
-            sort_findings_stable(&mut findings);
-            for f in findings.iter() {
-                stats.outputs_written += 1;
-                if !first { write!(out, ",")?; } else { first = false; }
-                let item = serde_json::json!({ "file_hash": f.file_hash, "value": f.value });
-                serde_json::to_writer(&mut *out, &item)?;
-            }
-            next_idx += 1;
+    let written = (|| -> Result<()> {
+        while let Ok((idx, part)) = rx.recv() {
+            buffer.entry(idx).or_default().push(part);
+            peak_buffered = peak_buffered.max(buffer.len());
+            flush_in_order(&mut buffer, &mut next_idx, files, emitter, stats)?;
+            if let Some(gate) = &gate { gate.advance(next_idx); }
         }
-    }
+        Ok(())
+    })();
+    // Writer 出错提前返回时放行全部等待中的 worker（其后续发送因通道关闭而失败，很快结束）
+    if let Some(gate) = &gate { gate.advance(usize::MAX); }
+    written?;
+    tracing::debug!(peak_buffered, window = opts.reorder_window, "parallel writer reorder buffer peak (files)");
 
     // 等待扫描线程结束
     let _ = scan_thread.join();
 
     // 最终冲刷残余（理论上缓冲应已清空）
-    while let Some((mut findings, scanned)) = buffer.remove(&next_idx) {
-        if scanned { stats.files_scanned += 1; }
-        sort_findings_stable(&mut findings);
-        for f in findings.iter() {
-            stats.outputs_written += 1;
-            if !first { write!(out, ",")?; } else { first = false; }
-            let item = serde_json::json!({ "file_hash": f.file_hash, "value": f.value });
-            serde_json::to_writer(&mut *out, &item)?;
+    flush_in_order(&mut buffer, &mut next_idx, files, emitter, stats)?;
+
+    Ok(())
+}
+
+
+/// 扫描失败的跳过原因：错误链中含 IO 错误（打开 / 读取 / 元数据失败）时为 ReadError
+fn skip_reason(e: &anyhow::Error) -> SkipReason {
+    if e.chain().any(|c| c.is::<std::io::Error>()) {
+        SkipReason::ReadError(e.to_string())
+    } else {
+        SkipReason::Error(e.to_string())
+    }
+}
+
+/// 按处理顺序排列的 (文件名顺序下的 idx, 路径, file_hash)；大小 / 修改时间相同或读取失败时保持文件名顺序
+fn processing_order(files: &[InputFile], order: FileOrder) -> Vec<(usize, PathBuf, Option<String>)> {
+    let mut files_vec: Vec<(usize, PathBuf, Option<String>)> =
+        files.iter().enumerate().map(|(i, f)| (i, f.path.clone(), f.file_hash.clone())).collect();
+    match order {
+        FileOrder::Name => {}
+        FileOrder::SizeDesc => {
+            files_vec.sort_by_cached_key(|(_, p, _)| std::cmp::Reverse(std::fs::metadata(p).map(|m| m.len()).unwrap_or(0)));
+        }
+        FileOrder::MtimeDesc => {
+            files_vec.sort_by_cached_key(|(_, p, _)| std::cmp::Reverse(std::fs::metadata(p).and_then(|m| m.modified()).ok()));
+        }
+    }
+    files_vec
+}
+
+/// 并行路径中 worker 发给 Writer 的单个文件的结果片段
+enum FilePart {
+    /// 分块扫描中提前确定的命中（已按文件内稳定顺序排序）；同一文件的批次按发送顺序到达，且先于 Done
+    Batch(Vec<Finding>),
+    /// 文件扫描结束：剩余命中与跳过原因（None=已扫描）
+    Done(FileScan, Option<SkipReason>),
+    /// 扫描已取消，该文件未扫描
+    Cancelled,
+}
+
+/// 并行路径的背压闸门：Writer 推进输出位置后唤醒等待中的 worker。
+/// worker 按 idx 顺序领取文件，等待中的 worker 的 idx 总是大于所有未完成文件中最小的那个，
+/// 而后者不受限制、必然能完成，因此不会死锁
+struct ReorderGate {
+    window: usize,
+    /// Writer 的 next_idx（关闭时为 usize::MAX）
+    next_idx: Mutex<usize>,
+    advanced: Condvar,
+}
+
+impl ReorderGate {
+    fn new(window: usize) -> Self {
+        Self { window, next_idx: Mutex::new(0), advanced: Condvar::new() }
+    }
+
+    /// 阻塞直到文件 `idx` 落入窗口内
+    fn wait_turn(&self, idx: usize) {
+        let mut next = self.next_idx.lock().unwrap();
+        while idx > next.saturating_add(self.window) {
+            next = self.advanced.wait(next).unwrap();
         }
-        next_idx += 1;
     }
 
-    // 写 JSON 结束符
-    write!(out, "]")?;
+    /// Writer 输出位置推进到 `next_idx`
+    fn advance(&self, next_idx: usize) {
+        let mut next = self.next_idx.lock().unwrap();
+        if *next != next_idx {
+            *next = next_idx;
+            self.advanced.notify_all();
+        }
+    }
+}
+
+/// 从 `next_idx` 开始按文件顺序输出已到达的片段：当前文件的批次立即输出，
+/// 收到 Done 后才推进到下一个文件；后续文件的片段留在缓存中
+fn flush_in_order(
+    buffer: &mut BTreeMap<usize, Vec<FilePart>>,
+    next_idx: &mut usize,
+    files: &[InputFile],
+    emitter: &mut Emitter,
+    stats: &mut ScanStats,
+) -> Result<()> {
+    while let Some(parts) = buffer.remove(next_idx) {
+        emitter.current_path.clone_from(&files[*next_idx].rel_path);
+        let mut done = false;
+        for part in parts {
+            match part {
+                FilePart::Batch(mut batch) => emitter.emit_all(&mut batch, stats)?,
+                FilePart::Done(mut scan, skipped) => {
+                    let skipped = match skipped {
+                        None => {
+                            stats.files_scanned += 1;
+                            if let (Some(state), Some(file_hash)) = (emitter.state.as_mut(), files[*next_idx].file_hash.as_deref()) {
+                                state.file_scanned(&files[*next_idx].path, file_hash, &scan);
+                            }
+                            false
+                        }
+                        Some(reason) => { emitter.skip(&files[*next_idx].path, reason); true }
+                    };
+                    let content_hash = scan.content_hash.take();
+                    let file_hash = content_hash.as_deref().or(files[*next_idx].file_hash.as_deref()).unwrap_or_default();
+                    emitter.record_scan(file_hash, &mut scan, stats);
+                    // 文件内稳定排序已在 worker 执行；此处再保证一次
+                    sort_findings_stable(&mut scan.findings);
+                    emitter.emit_all(&mut scan.findings, stats)?;
+                    if !skipped { emitter.file_done(file_hash, &scan)?; }
+                    emitter.file_processed(&files[*next_idx].path);
+                    done = true;
+                }
+                FilePart::Cancelled => done = true,
+            }
+        }
+        // 当前文件尚未扫描完成：等待后续片段
+        if !done { break; }
+        *next_idx += 1;
+    }
     Ok(())
 }
+
+/// 预筛计划诊断：模式重复的规则组告警；死规则告警，`strict_rules` 时列出全部死规则并报错
+fn check_plan_diagnostics(plan: &PrefilterPlan, opts: &ScanOptions) -> Result<()> {
+    let diag = analyze_prefilter(plan);
+    for ids in &diag.duplicate_patterns {
+        tracing::warn!(rules = %ids.join(", "), "rules have identical patterns, each match is reported once per rule");
+    }
+    if diag.dead_rules.is_empty() { return Ok(()); }
+    let ids: Vec<&str> = diag.dead_rules.iter().map(|d| d.rule_id.as_str()).collect();
+    if opts.strict_rules {
+        bail!("{} rule(s) have no prefilter anchor and would never run: {}", ids.len(), ids.join(", "));
+    }
+    tracing::warn!(rules = %ids.join(", "), "rules have no prefilter anchor and will never run (add a literal prefix to the pattern)");
+    Ok(())
+}
+
+/// 对 bytes / UTF-8 引擎下语义不同的规则逐条告警，避免引擎相关的召回差异被忽视
+fn warn_engine_sensitive_rules(specs: &[RuleSpec]) {
+    for spec in specs {
+        let constructs = engine_sensitive_constructs(&spec.pat);
+        if constructs.is_empty() { continue; }
+        tracing::warn!(
+            rule = %spec.id,
+            constructs = %constructs.join(" "),
+            "rule semantics differ between bytes and utf8 engines; consider (?-u) or --ascii-bytes"
+        );
+    }
+}
+
+/// Writer 端：按稳定顺序输出命中并聚合统计
+/// 仅在单一线程（串行循环 / 并行 Writer）中使用，统计无需跨线程同步
+struct Emitter<'a> {
+    sink: Box<dyn FindingSink + 'a>,
+    /// 规则 id -> 严重级别
+    rule_severity: HashMap<String, Severity>,
+    /// 被跳过（未扫描）的文件，按输出顺序
+    skipped: Vec<SkippedFile>,
+    /// 跨文件聚合：value -> 出现该值的 file_hash（按输出顺序；仅在开启报告时收集）
+    cross_file: Option<BTreeMap<String, Vec<String>>>,
+    /// 原始命中位置：file_hash -> 去重前的命中位置（仅在开启报告时收集）
+    hit_positions: Option<BTreeMap<String, Vec<HitPosition>>>,
+    /// 规则 id -> 命中值最大长度（规则 max_len 优先，否则为全局 max_value_len；不限制的规则不在表中）
+    max_value_len: HashMap<String, usize>,
+    /// 规则 id -> 命中值校验器（仅声明了 validate 的规则）
+    validators: HashMap<String, Arc<dyn Validator>>,
+    validation_mode: ValidationMode,
+    only_validated: bool,
+    /// 写出前以指纹代替命中值
+    fingerprint: bool,
+    /// 置信度权重（仅 `with_score` 时为 Some）
+    scoring: Option<ScoringWeights>,
+    /// 全部规则 id（按加载顺序，仅 `unused_rules` 时为 Some）
+    rule_ids: Option<Vec<String>>,
+    /// 命中值白名单（见 `ScanOptions::allow_values`，含启用时的内置白名单）及白名单正则（见 `ScanOptions::allow_patterns`）
+    allow_values: HashSet<String>,
+    allow_patterns: Option<regex::RegexSet>,
+    /// 基线（见 `ScanOptions::baseline`）
+    baseline: Option<Arc<Baseline>>,
+    /// 最低严重级别（见 `ScanOptions::min_severity`）
+    min_severity: Option<Severity>,
+    /// 待写出的新基线及其路径（仅 `write_baseline` 时为 Some）
+    new_baseline: Option<(PathBuf, Baseline)>,
+    /// 最小熵门限（见 `ScanOptions::min_score`）及受其约束的规则 id（门限为 0 时为空）
+    min_entropy: f64,
+    entropy_gated: HashSet<String>,
+    /// 全局去重时已输出的值（`dedup_key` 归一化后；仅 `DedupScope::Global` 时为 Some）
+    seen_values: Option<HashSet<String>>,
+    normalize_dedup: bool,
+    /// 进度回调（见 `ScanOptions::on_progress`）及已处理 / 待处理的文件数
+    on_progress: Option<Arc<dyn ProgressFn>>,
+    files_done: usize,
+    files_total: usize,
+    /// 当前输出文件的相对路径（仅 `emit_path` 时为 Some，写出时填入 `Finding::path`）
+    current_path: Option<String>,
+    /// 规则先后（rule_id -> 按优先级降序、同优先级按加载顺序的名次，仅 `suppress_overlaps` 时为 Some）及当前文件的被包含命中抑制状态
+    overlap_rank: Option<HashMap<String, usize>>,
+    overlaps: OverlapFilter,
+    /// 最近一条输出命中所在的输入文件序号（`files_done`）与 file_hash（同一文件的命中连续输出，据此统计有命中的文件数）；
+    /// 内容摘要模式下内容相同的相邻文件 file_hash 相同，需按序号区分，压缩包内的条目按 file_hash 区分
+    last_file: Option<(usize, String)>,
+    /// 取消标记（见 `ScanOptions::cancel`），汇总报告时记入统计
+    cancel: Option<Arc<std::sync::atomic::AtomicBool>>,
+    /// 输出大小上限（见 `ScanOptions::max_output_bytes`）
+    max_output_bytes: Option<u64>,
+    /// 输出已达上限，其后的命中不再写出
+    truncated: bool,
+    /// 截断发生在当前文件中（该文件仍需结束输出，如分组输出中已缓冲的命中）
+    truncated_in_file: bool,
+    /// 本次扫描的增量扫描状态（仅目录扫描且 `state_path` 时为 Some）
+    state: Option<StateRecorder>,
+}
+
+/// 规则先后名次：优先级降序、同优先级按加载顺序；同一 id 出现多次时取最靠前的名次
+fn rule_rank(specs: &[RuleSpec]) -> HashMap<String, usize> {
+    let mut order: Vec<usize> = (0..specs.len()).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse(specs[i].priority));
+    let mut rank = HashMap::new();
+    for (n, i) in order.into_iter().enumerate() {
+        rank.entry(specs[i].id.clone()).or_insert(n);
+    }
+    rank
+}
+
+impl<'a> Emitter<'a> {
+    fn new(sink: Box<dyn FindingSink + 'a>, specs: &[RuleSpec], opts: &ScanOptions) -> Result<Self> {
+        let rule_severity = specs.iter().map(|r| (r.id.clone(), r.severity)).collect();
+        let cross_file = opts.cross_file_report.then(BTreeMap::new);
+        let hit_positions = opts.hit_positions.then(BTreeMap::new);
+        let max_value_len = specs
+            .iter()
+            .filter_map(|r| r.max_len.or(opts.max_value_len).map(|n| (r.id.clone(), n)))
+            .collect();
+        let validators = resolve_validators(specs, opts)?;
+        let mut allow_values = opts.allow_values.clone();
+        if opts.default_allowlist { allow_values.extend(DEFAULT_ALLOW_VALUES.iter().map(|v| v.to_string())); }
+        let allow_patterns = if opts.allow_patterns.is_empty() {
+            None
+        } else {
+            Some(regex::RegexSet::new(&opts.allow_patterns).context("invalid allowlist pattern")?)
+        };
+        let entropy_gated = if opts.min_score > 0.0 {
+            specs.iter().filter(|r| r.is_regex() && !has_curated_prefix(r)).map(|r| r.id.clone()).collect()
+        } else {
+            HashSet::new()
+        };
+        let scoring = if opts.with_score { Some(load_scoring_weights(opts)?) } else { None };
+        if opts.only_validated && validators.is_empty() {
+            tracing::warn!("only_validated is set but no rule declares a validator; no findings will be written");
+        }
+        Ok(Self {
+            sink,
+            rule_severity,
+            skipped: Vec::new(),
+            cross_file,
+            hit_positions,
+            max_value_len,
+            validators,
+            validation_mode: opts.validation_mode,
+            only_validated: opts.only_validated,
+            fingerprint: opts.fingerprint,
+            scoring,
+            rule_ids: opts.unused_rules.then(|| specs.iter().map(|r| r.id.clone()).collect()),
+            allow_values,
+            allow_patterns,
+            baseline: opts.baseline.clone(),
+            min_severity: opts.min_severity,
+            new_baseline: opts.write_baseline.clone().map(|p| (p, Baseline::default())),
+            min_entropy: opts.min_score as f64,
+            entropy_gated,
+            seen_values: (opts.dedup == DedupScope::Global).then(HashSet::new),
+            normalize_dedup: opts.normalize_dedup,
+            on_progress: opts.on_progress.clone(),
+            files_done: 0,
+            files_total: 0,
+            current_path: None,
+            overlap_rank: opts.suppress_overlaps.then(|| rule_rank(specs)),
+            overlaps: OverlapFilter::default(),
+            last_file: None,
+            cancel: opts.cancel.clone(),
+            max_output_bytes: opts.max_output_bytes,
+            truncated: false,
+            truncated_in_file: false,
+            state: None,
+        })
+    }
+
+    /// 一个输入文件处理完毕（已扫描或被跳过）：记入增量扫描状态，通知进度回调
+    fn file_processed(&mut self, path: &Path) {
+        self.overlaps.reset();
+        if let Some(state) = self.state.as_mut() { state.file_done(); }
+        self.files_done += 1;
+        if let Some(cb) = &self.on_progress {
+            cb(ProgressEvent { files_done: self.files_done, files_total: self.files_total, current_path: path });
+        }
+    }
+
+    /// 记录被跳过的文件；读取或扫描出错时同时告警（与因过大跳过区分）
+    fn skip(&mut self, path: &Path, reason: SkipReason) {
+        match &reason {
+            SkipReason::ReadError(e) => tracing::warn!(path = %path.display(), error = %e, "file could not be read, skipped"),
+            SkipReason::Error(e) => tracing::warn!(path = %path.display(), error = %e, "file could not be scanned, skipped"),
+            SkipReason::TooLarge => {}
+        }
+        self.skipped.push(SkippedFile { path: path.to_path_buf(), reason });
+    }
+
+    /// 输出同一文件的一批命中（已按文件内稳定顺序排序）；增量扫描时先按原样记入状态，`suppress_overlaps` 时先丢弃被包含的命中
+    fn emit_all(&mut self, findings: &mut Vec<Finding>, stats: &mut ScanStats) -> Result<()> {
+        if let Some(state) = self.state.as_mut() { state.record_findings(findings); }
+        if let Some(rank) = &self.overlap_rank {
+            stats.overlaps_suppressed += self.overlaps.retain(findings, |id| rank.get(id).copied().unwrap_or(usize::MAX));
+        }
+        findings.iter().try_for_each(|f| self.emit(f, stats))
+    }
+
+    /// 输出单条命中并累计统计（输出数、按严重级别与规则计数、有命中的文件数）
+    /// 白名单中的值与基线中的命中直接丢弃；规则严重级别低于 `min_severity` 的命中直接丢弃；超过最大长度或熵低于 `min_score` 的命中直接丢弃；规则声明了校验器时再校验：丢弃模式下跳过未通过的命中，标注模式下附带校验结果；
+    /// `only_validated` 时未经校验或未通过校验的命中一律丢弃；全局去重时丢弃此前已输出过的值；
+    /// `fingerprint` 时以指纹代替写出的值
+    fn emit(&mut self, f: &Finding, stats: &mut ScanStats) -> Result<()> {
+        if self.output_full() { return Ok(()); }
+        if self.allow_values.contains(&f.value) || self.allow_patterns.as_ref().is_some_and(|p| p.is_match(&f.value)) {
+            stats.allowlisted += 1;
+            return Ok(());
+        }
+        if self.baseline.as_ref().is_some_and(|b| b.contains(&f.file_hash, &f.value)) {
+            stats.suppressed += 1;
+            if let Some((_, nb)) = self.new_baseline.as_mut() { nb.insert(&f.file_hash, &f.value); }
+            return Ok(());
+        }
+        let sev = self.rule_severity.get(&f.rule_id).copied().unwrap_or_default();
+        if self.min_severity.is_some_and(|min| sev < min) {
+            stats.below_min_severity += 1;
+            return Ok(());
+        }
+        if self.max_value_len.get(&f.rule_id).is_some_and(|&max| f.value.len() > max) {
+            stats.values_too_long += 1;
+            return Ok(());
+        }
+        if self.entropy_gated.contains(&f.rule_id) && shannon_entropy(f.value.as_bytes(), EntropyAlphabet::Bytes) < self.min_entropy {
+            stats.low_entropy += 1;
+            return Ok(());
+        }
+        let annotated;
+        // 校验结果（规则未声明校验器时为 None），供置信度计算
+        let mut verdict = None;
+        let f = match self.validators.get(&f.rule_id) {
+            None if self.only_validated => {
+                stats.not_validated += 1;
+                return Ok(());
+            }
+            None => f,
+            Some(v) => {
+                let ok = v.validate(&f.value);
+                verdict = Some(ok);
+                if !ok { stats.validation_failed += 1; }
+                if !ok && self.only_validated { return Ok(()); }
+                match self.validation_mode {
+                    ValidationMode::Drop if !ok => return Ok(()),
+                    ValidationMode::Drop => f,
+                    ValidationMode::Annotate => {
+                        annotated = Finding { validated: Some(ok), ..f.clone() };
+                        &annotated
+                    }
+                }
+            }
+        };
+        if let Some(seen) = self.seen_values.as_mut() {
+            // 在写出线程上按输出顺序判断，结果与线程数无关；比较原始值（在指纹替换之前）
+            if !seen.insert(dedup_key(&f.value, self.normalize_dedup).to_string()) {
+                stats.global_duplicates += 1;
+                return Ok(());
+            }
+        }
+        if let Some((_, nb)) = self.new_baseline.as_mut() { nb.insert(&f.file_hash, &f.value); }
+        // 置信度基于原始命中值（在指纹替换之前）
+        let scored;
+        let f = match &self.scoring {
+            Some(w) => {
+                scored = Finding { confidence: Some(w.confidence(verdict, &f.value, sev)), ..f.clone() };
+                &scored
+            }
+            None => f,
+        };
+        let located;
+        let f = match &self.current_path {
+            Some(p) => {
+                located = Finding { path: Some(p.clone()), ..f.clone() };
+                &located
+            }
+            None => f,
+        };
+        let fingerprinted;
+        let f = if self.fingerprint {
+            fingerprinted = Finding { value: fingerprint_value(&f.value), ..f.clone() };
+            &fingerprinted
+        } else {
+            f
+        };
+        *stats.findings_by_severity.entry(sev).or_insert(0) += 1;
+        *stats.findings_by_rule.entry(f.rule_id.clone()).or_insert(0) += 1;
+        stats.outputs_written += 1;
+        if self.last_file.as_ref().is_none_or(|(idx, hash)| *idx != self.files_done || *hash != f.file_hash) {
+            stats.files_with_findings += 1;
+            self.last_file = Some((self.files_done, f.file_hash.clone()));
+        }
+        if let Some(cross) = self.cross_file.as_mut() {
+            // 文件内已按 value 去重，且同一文件的命中连续输出，比较末尾即可避免重复
+            let files = cross.entry(f.value.clone()).or_default();
+            if files.last() != Some(&f.file_hash) { files.push(f.file_hash.clone()); }
+        }
+        self.sink.write(f)
+    }
+
+    /// 输出是否已达大小上限：首次达到时记录截断并置位取消标记，通知扫描（含并行 worker）尽快停止
+    fn output_full(&mut self) -> bool {
+        if self.truncated { return true; }
+        let Some(max) = self.max_output_bytes else { return false };
+        if self.sink.bytes_written().is_some_and(|n| n >= max) {
+            tracing::warn!(max_output_bytes = max, "output size limit reached, remaining findings are not written");
+            self.truncated = true;
+            self.truncated_in_file = true;
+            if let Some(c) = &self.cancel { c.store(true, std::sync::atomic::Ordering::Relaxed); }
+        }
+        self.truncated
+    }
+
+    /// 单个文件的命中均已交给输出端：通知输出端该文件结束；截断之后的文件不再写出（分组输出中也不出现）
+    fn file_done(&mut self, file_hash: &str, scan: &FileScan) -> Result<()> {
+        if self.truncated && !std::mem::take(&mut self.truncated_in_file) { return Ok(()); }
+        self.sink.file_done(file_hash, scan)
+    }
+
+    /// 累计单个文件的扫描元数据：扫描字节数 / 复用、二进制跳过 / UTF-16 转码判定、原始命中数与命中位置
+    fn record_scan(&mut self, file_hash: &str, scan: &mut FileScan, stats: &mut ScanStats) {
+        if scan.reused {
+            tracing::debug!(file_hash, "unchanged since the last scan, previous results reused");
+            stats.files_reused += 1;
+        } else {
+            stats.bytes_scanned += scan.scanned_bytes;
+        }
+        if let Some(bytes) = scan.binary_skipped {
+            tracing::debug!(file_hash, bytes, "classified as binary, skipped");
+            stats.record_binary_skip(bytes);
+        }
+        if let Some(order) = scan.utf16 {
+            tracing::debug!(file_hash, encoding = order.name(), "classified as UTF-16 text, scanned after transcoding to UTF-8");
+            stats.files_utf16 += 1;
+        }
+        stats.candidates_total += scan.candidates;
+        if scan.truncated {
+            tracing::warn!(file_hash, "file reached the per-file match cap, remaining matches skipped");
+            stats.truncated_files += 1;
+        }
+        for (path, reason) in scan.skipped_entries.drain(..) {
+            self.skip(&path, reason);
+        }
+        if let (Some(report), Some(hits)) = (self.hit_positions.as_mut(), scan.hits.take()) {
+            if hits.is_empty() { return; }
+            let hits = hits.into_iter().map(|(offset, rule_id)| HitPosition { offset, rule_id }).collect();
+            report.insert(file_hash.to_string(), hits);
+        }
+    }
+
+    /// 输出单个文件的扫描结果：成功则计入已扫描并按文件内稳定顺序输出命中，失败则记为跳过
+    fn emit_file(&mut self, path: &Path, file_hash: &str, res: Result<FileScan>, stats: &mut ScanStats) -> Result<()> {
+        match res {
+            Ok(mut scan) => {
+                stats.files_scanned += 1;
+                if let Some(state) = self.state.as_mut() { state.file_scanned(path, file_hash, &scan); }
+                let content_hash = scan.content_hash.take();
+                let file_hash = content_hash.as_deref().unwrap_or(file_hash);
+                self.record_scan(file_hash, &mut scan, stats);
+                // 文件内稳定排序
+                sort_findings_stable(&mut scan.findings);
+                self.emit_all(&mut scan.findings, stats)?;
+                self.file_done(file_hash, &scan)?;
+            }
+            Err(e) => self.skip(path, skip_reason(&e)),
+        }
+        self.file_processed(path);
+        Ok(())
+    }
+
+    /// 结束输出：记录扫描是否被取消 / 输出是否被截断，把最终统计交给输出端（JSON 信封等需要在结果中附带统计）；
+    /// `write_baseline` 时写出新基线；`state_path` 时扫描完整则写出增量扫描状态
+    fn finish(&mut self, stats: &mut ScanStats) -> Result<()> {
+        if let Some((path, nb)) = &self.new_baseline { nb.write(path)?; }
+        // 因输出截断置位的取消标记不算作取消
+        stats.output_truncated = self.truncated;
+        stats.files_errored = self.skipped.iter().filter(|s| !matches!(s.reason, SkipReason::TooLarge)).count();
+        stats.cancelled = !self.truncated && self.cancel.as_ref().is_some_and(|c| c.load(std::sync::atomic::Ordering::Relaxed));
+        if let Some(state) = self.state.take() {
+            if stats.cancelled || stats.output_truncated {
+                tracing::info!("scan incomplete, incremental scan state not updated");
+            } else {
+                state.write()?;
+            }
+        }
+        self.sink.finish(stats)
+    }
+
+    /// 结束扫描，汇总为报告（跨文件报告仅保留出现在至少两个文件中的值；未命中规则取自按规则统计的输出数）
+    fn into_report(self, stats: ScanStats) -> ScanReport {
+        let unused_rules = self.rule_ids.map(|ids| {
+            let mut seen = std::collections::HashSet::new();
+            ids.into_iter().filter(|id| !stats.findings_by_rule.contains_key(id) && seen.insert(id.clone())).collect()
+        });
+        let cross_file = self.cross_file.map(|mut m| {
+            m.retain(|_, files| files.len() > 1);
+            CrossFileReport { shared: m }
+        });
+        let hit_positions = self.hit_positions.map(|files| HitPositionReport { files });
+        ScanReport { stats, skipped: self.skipped, cross_file, hit_positions, unused_rules }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    /// 等待 `entered` 达到 `len` 条，再稍等片刻确认没有更多 worker 越过闸门；返回排序后的 idx
+    fn settle(entered: &Mutex<Vec<usize>>, len: usize) -> Vec<usize> {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while entered.lock().unwrap().len() < len && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        std::thread::sleep(Duration::from_millis(50));
+        let mut got = entered.lock().unwrap().clone();
+        got.sort_unstable();
+        got
+    }
+
+    #[test]
+    fn reorder_gate_holds_workers_beyond_the_window() {
+        let gate = Arc::new(ReorderGate::new(4));
+        let entered: Arc<Mutex<Vec<usize>>> = Arc::default();
+        let workers: Vec<_> = (1..=20)
+            .map(|idx| {
+                let (gate, entered) = (gate.clone(), entered.clone());
+                std::thread::spawn(move || {
+                    gate.wait_turn(idx);
+                    entered.lock().unwrap().push(idx);
+                })
+            })
+            .collect();
+
+        // 文件 0 未完成时至多领先 window 个文件
+        assert_eq!(settle(&entered, 4), (1..=4).collect::<Vec<_>>());
+        gate.advance(6);
+        assert_eq!(settle(&entered, 10), (1..=10).collect::<Vec<_>>());
+        // 关闭时放行全部
+        gate.advance(usize::MAX);
+        for w in workers {
+            w.join().unwrap();
+        }
+        assert_eq!(settle(&entered, 20), (1..=20).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,73 @@
+//! 命中置信度（`with_score`）
+//!
+//! 综合三项信号，加权平均得到 `[0, 1]` 内的单一数值，便于按置信度排序或设阈值：
+//!
+//! ```text
+//! confidence = (w_v * V + w_e * E + w_s * S) / (w_v + w_e + w_s)
+//! ```
+//!
+//! - V（校验）：规则声明的校验器通过为 1.0，未通过为 0.0，规则未声明校验器为 0.5（未知）；
+//! - E（熵）：命中值逐字节香农熵除以该长度下的熵上限 `log2(min(len, 64))`，截断到 `[0, 1]`
+//!   （64 即 base64 字母表大小；长度不超过 1 的值记 0）；
+//! - S（严重级别）：low 0.25、medium 0.5、high 0.75、critical 1.0。
+//!
+//! 权重缺省为 `w_v = 0.4`、`w_e = 0.3`、`w_s = 0.3`，可在规则文件的 `[scoring]` 段覆盖
+//! （`validator_weight` / `entropy_weight` / `severity_weight`，须为非负数且不全为 0）。
+//! 置信度基于原始命中值计算（开启 `fingerprint` 时同样如此），结果保留三位小数。
+use anyhow::{bail, Result};
+use serde::Deserialize;
+
+use crate::entropy::{shannon_entropy, EntropyAlphabet};
+use crate::types::Severity;
+
+/// 熵上限的符号数上限（base64 字母表大小）
+const ENTROPY_MAX_SYMBOLS: usize = 64;
+
+/// 置信度权重（规则文件 `[scoring]` 段）
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub(crate) struct ScoringWeights {
+    pub validator_weight: f32,
+    pub entropy_weight: f32,
+    pub severity_weight: f32,
+}
+
+impl Default for ScoringWeights {
+    fn default() -> Self {
+        Self { validator_weight: 0.4, entropy_weight: 0.3, severity_weight: 0.3 }
+    }
+}
+
+impl ScoringWeights {
+    /// 校验权重取值（负数或全为 0 时报错）
+    pub(crate) fn validated(self) -> Result<Self> {
+        let ws = [self.validator_weight, self.entropy_weight, self.severity_weight];
+        if ws.iter().any(|w| !w.is_finite() || *w < 0.0) { bail!("[scoring] weights must be non-negative numbers"); }
+        if ws.iter().sum::<f32>() <= 0.0 { bail!("[scoring] weights must not all be zero"); }
+        Ok(self)
+    }
+
+    /// 按模块文档中的公式计算置信度
+    /// - `verdict`：校验结果（规则未声明校验器时为 None）
+    pub(crate) fn confidence(&self, verdict: Option<bool>, value: &str, severity: Severity) -> f32 {
+        let v = match verdict { Some(true) => 1.0, Some(false) => 0.0, None => 0.5 };
+        let e = entropy_signal(value.as_bytes());
+        let s = match severity {
+            Severity::Low => 0.25,
+            Severity::Medium => 0.5,
+            Severity::High => 0.75,
+            Severity::Critical => 1.0,
+        };
+        let total = self.validator_weight + self.entropy_weight + self.severity_weight;
+        let score = (self.validator_weight * v + self.entropy_weight * e + self.severity_weight * s) / total;
+        (score * 1000.0).round() / 1000.0
+    }
+}
+
+/// 归一化熵：香农熵 / log2(min(len, 64))，截断到 [0, 1]
+fn entropy_signal(value: &[u8]) -> f32 {
+    let symbols = value.len().min(ENTROPY_MAX_SYMBOLS);
+    if symbols <= 1 { return 0.0; }
+    let max = (symbols as f64).log2();
+    (shannon_entropy(value, EntropyAlphabet::Bytes) / max).clamp(0.0, 1.0) as f32
+}
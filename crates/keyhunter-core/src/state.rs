@@ -0,0 +1,232 @@
+//! 增量扫描状态文件（见 `ScanOptions::state_path`）
+//!
+//! - 记录每个已扫描文件的路径、大小、修改时间及引擎产生的扫描结果（命中尚未经过白名单、基线、校验等输出端处理）；
+//! - 再次扫描时，大小与修改时间均未变化的文件不再读取，直接复用上次的结果并经同样的输出端处理后写出，
+//!   输出与全量扫描一致（内容摘要模式下摘要同样复用）；
+//! - 状态键为 keyhunter 版本、规则集哈希（见 `metadata::ruleset_hash`）与影响引擎结果的扫描选项的 SHA-256，
+//!   键不一致时整个状态失效，全部文件重新扫描；
+//! - 扫描出错或被跳过的文件不记录，下次重新扫描；扫描被取消或输出被截断时不更新状态文件；
+//! - 先写临时文件再重命名，写出中途失败不会留下不完整的状态文件。
+//!
+//! 修改时间的精度取决于文件系统：同一时间单位内被改写且大小不变的文件无法察觉，需要时删除状态文件全量重扫。
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::encoding::Utf16Order;
+use crate::findings::{FileScan, FindingPublic as Finding};
+use crate::metadata::{hex_sha256, ruleset_hash};
+use crate::options::ScanOptions;
+use crate::types::SkipReason;
+
+/// 文件大小与修改时间（纳秒，UNIX 纪元起）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct FileStamp {
+    size: u64,
+    mtime_ns: u64,
+}
+
+impl FileStamp {
+    /// 读取文件元数据；无法获取修改时间时为 None（该文件不参与增量扫描）
+    pub(crate) fn of(path: &Path) -> Option<Self> {
+        let md = std::fs::metadata(path).ok()?;
+        let mtime = md.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Self { size: md.len(), mtime_ns: u64::try_from(mtime.as_nanos()).ok()? })
+    }
+}
+
+/// 状态文件中的一个文件
+#[derive(Serialize, Deserialize)]
+struct StateEntry {
+    path: String,
+    #[serde(flatten)]
+    stamp: FileStamp,
+    /// 扫描时命中的 file_hash（复用时替换为本次的 file_hash）
+    file_hash: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    content_hash: Option<String>,
+    /// 按输出顺序排列的命中（含分块扫描中先行输出的批次）
+    findings: Vec<Finding>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    binary_skipped: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    utf16: Option<Utf16Order>,
+    file_size: u64,
+    scanned_bytes: u64,
+    candidates: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hits: Option<Vec<(usize, String)>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    skipped_entries: Vec<(PathBuf, SkipReason)>,
+    #[serde(default)]
+    truncated: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StateFile {
+    key: String,
+    files: Vec<StateEntry>,
+}
+
+/// 状态键：版本、规则集哈希与影响引擎结果的扫描选项（只影响输出端处理的选项不计入）
+pub(crate) fn state_key(opts: &ScanOptions) -> Result<String> {
+    let settings = format!(
+        "{}|{}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}",
+        env!("CARGO_PKG_VERSION"),
+        ruleset_hash(opts)?,
+        (&opts.enabled_rules, &opts.disabled_rules),
+        opts.engine,
+        opts.hash_mode,
+        (opts.head_bytes, opts.tail_bytes),
+        (opts.max_file_size, opts.scan_archives),
+        opts.max_matches_per_file,
+        opts.max_line_length,
+        (opts.binary_mode, opts.binary_printable_threshold),
+        opts.detect_encoding,
+        (opts.broad_anchor_threshold, opts.split_broad_anchors),
+        opts.anchors_ignore_case,
+        opts.max_window_bytes,
+        (opts.window_before, opts.window_after),
+        (opts.pem_window_before, opts.pem_window_after),
+        opts.max_pem_windows,
+        opts.small_file_fullscan_threshold,
+        opts.regex_size_limit,
+        opts.rule_timeout,
+        opts.join_string_literals,
+        opts.decode_base64,
+        opts.with_location,
+        (opts.context_lines, opts.context_bytes),
+        opts.inline_suppression,
+        opts.suppression_marker,
+        opts.ignore_comments,
+        opts.no_dedup,
+        opts.normalize_dedup,
+        opts.hit_positions,
+        opts.ascii_bytes_semantics,
+        opts.use_mmap,
+        opts.memory_budget,
+        opts.emit_path,
+    );
+    Ok(hex_sha256(settings.as_bytes()))
+}
+
+/// 上一次扫描的状态（只读，扫描线程间共享）
+pub(crate) struct PreviousState {
+    files: HashMap<String, StateEntry>,
+}
+
+impl PreviousState {
+    /// 读取状态文件；不存在、无法解析或键不一致时为 None（全部文件重新扫描）
+    pub(crate) fn load(path: &Path, key: &str) -> Option<Self> {
+        let txt = std::fs::read(path).ok()?;
+        let file: StateFile = match serde_json::from_slice(&txt) {
+            Ok(f) => f,
+            Err(e) => {
+                tracing::warn!(path = %path.display(), error = %e, "scan state file unreadable, all files rescanned");
+                return None;
+            }
+        };
+        if file.key != key {
+            tracing::info!(path = %path.display(), "rules or scan options changed since the state was written, all files rescanned");
+            return None;
+        }
+        tracing::debug!(path = %path.display(), files = file.files.len(), "scan state loaded");
+        Some(Self { files: file.files.into_iter().map(|e| (e.path.clone(), e)).collect() })
+    }
+
+    /// 文件未变化（大小与修改时间一致）时复用上次的结果；`file_hash` 为本次的 file_hash
+    pub(crate) fn reuse(&self, path: &Path, stamp: FileStamp, file_hash: &str) -> Option<FileScan> {
+        let e = self.files.get(path.to_str()?).filter(|e| e.stamp == stamp)?;
+        // 内容摘要模式下 file_hash 即摘要；否则按本次的文件名 / 相对路径改写（压缩包条目保留 `!<条目路径>` 后缀）
+        let new_hash = e.content_hash.as_deref().unwrap_or(file_hash);
+        let findings = e
+            .findings
+            .iter()
+            .cloned()
+            .map(|mut f| {
+                if let Some(rest) = f.file_hash.strip_prefix(e.file_hash.as_str()) {
+                    f.file_hash = format!("{}{}", new_hash, rest);
+                }
+                f
+            })
+            .collect();
+        Some(FileScan {
+            findings,
+            binary_skipped: e.binary_skipped,
+            utf16: e.utf16,
+            file_size: e.file_size,
+            scanned_bytes: e.scanned_bytes,
+            candidates: e.candidates,
+            hits: e.hits.clone(),
+            skipped_entries: e.skipped_entries.clone(),
+            content_hash: e.content_hash.clone(),
+            truncated: e.truncated,
+            stamp: Some(stamp),
+            reused: true,
+        })
+    }
+}
+
+/// 本次扫描的状态（Writer 端按输出顺序记录）
+pub(crate) struct StateRecorder {
+    path: PathBuf,
+    key: String,
+    files: Vec<StateEntry>,
+    /// 当前文件：扫描结束时登记的元数据，以及已交给输出端的命中
+    current: Option<StateEntry>,
+    pending: Vec<Finding>,
+}
+
+impl StateRecorder {
+    pub(crate) fn new(path: PathBuf, key: String) -> Self {
+        Self { path, key, files: Vec::new(), current: None, pending: Vec::new() }
+    }
+
+    /// 当前文件的一批命中即将交给输出端
+    pub(crate) fn record_findings(&mut self, findings: &[Finding]) {
+        self.pending.extend_from_slice(findings);
+    }
+
+    /// 当前文件扫描成功：登记其元数据（须在取走 `content_hash` / `hits` / `skipped_entries` 之前调用）；
+    /// 未记录扫描前大小与修改时间的文件不登记
+    pub(crate) fn file_scanned(&mut self, path: &Path, file_hash: &str, scan: &FileScan) {
+        let (Some(stamp), Some(path)) = (scan.stamp, path.to_str()) else { return };
+        self.current = Some(StateEntry {
+            path: path.to_string(),
+            stamp,
+            file_hash: scan.content_hash.clone().unwrap_or_else(|| file_hash.to_string()),
+            content_hash: scan.content_hash.clone(),
+            findings: Vec::new(),
+            binary_skipped: scan.binary_skipped,
+            utf16: scan.utf16,
+            file_size: scan.file_size,
+            scanned_bytes: scan.scanned_bytes,
+            candidates: scan.candidates,
+            hits: scan.hits.clone(),
+            skipped_entries: scan.skipped_entries.clone(),
+            truncated: scan.truncated,
+        });
+    }
+
+    /// 当前文件处理完毕：扫描成功的文件连同其命中一并记录
+    pub(crate) fn file_done(&mut self) {
+        let findings = std::mem::take(&mut self.pending);
+        if let Some(mut entry) = self.current.take() {
+            entry.findings = findings;
+            self.files.push(entry);
+        }
+    }
+
+    /// 写出状态文件（临时文件 + 重命名）
+    pub(crate) fn write(self) -> Result<()> {
+        let files = self.files.len();
+        let body = serde_json::to_vec(&StateFile { key: self.key, files: self.files })?;
+        let tmp = self.path.with_extension(format!("{}.tmp", std::process::id()));
+        std::fs::write(&tmp, body).with_context(|| format!("write scan state {}", tmp.display()))?;
+        std::fs::rename(&tmp, &self.path).with_context(|| format!("replace scan state {}", self.path.display()))?;
+        tracing::debug!(path = %self.path.display(), files, "scan state written");
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+//! 行内抑制注释（inline suppression）
+//!
+//! 在命中所在行或其上一行出现抑制标记（默认 `keyhunter:ignore`）时丢弃该命中，
+//! 用于源码中刻意保留的测试凭据，例如：
+//! ```text
+//! # keyhunter:ignore
+//! TEST_TOKEN = "ghp_..."
+//! api_key = "sk-..."  // keyhunter:ignore
+//! ```
+use crate::findings::FindingPublic as Finding;
+
+/// 过滤被行内注释抑制的命中
+/// - `buf`：命中所在的缓冲区；`base_offset`：缓冲区在原文件中的起始偏移
+/// - 偏移不在缓冲区内的命中保持不变
+pub(crate) fn retain_unsuppressed(buf: &[u8], base_offset: usize, findings: &mut Vec<Finding>, marker: &str) {
+    if marker.is_empty() || findings.is_empty() { return; }
+    let marker = marker.as_bytes();
+    // 快速路径：缓冲区内不含标记则无需逐条检查
+    if memchr::memmem::find(buf, marker).is_none() { return; }
+    findings.retain(|f| {
+        match f.start_offset.checked_sub(base_offset) {
+            Some(pos) if pos < buf.len() => !is_suppressed(buf, pos, marker),
+            _ => true,
+        }
+    });
+}
+
+/// 判断位置 `pos` 所在行或上一行是否包含抑制标记
+fn is_suppressed(buf: &[u8], pos: usize, marker: &[u8]) -> bool {
+    let line_start = memchr::memrchr(b'\n', &buf[..pos]).map(|i| i + 1).unwrap_or(0);
+    let line_end = memchr::memchr(b'\n', &buf[pos..]).map(|i| pos + i).unwrap_or(buf.len());
+    if memchr::memmem::find(&buf[line_start..line_end], marker).is_some() { return true; }
+    if line_start == 0 { return false; }
+    // 上一行：[prev_start, line_start - 1)
+    let prev_end = line_start - 1;
+    let prev_start = memchr::memrchr(b'\n', &buf[..prev_end]).map(|i| i + 1).unwrap_or(0);
+    memchr::memmem::find(&buf[prev_start..prev_end], marker).is_some()
+}
@@ -1,10 +1,207 @@
 //! 公共类型（对外暴露）
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::options::ScanStats;
+
+/// `OutputItem` 序列化后的全部字段名（按字段声明顺序），`output_fields` 重映射只接受这些原字段名
+pub const OUTPUT_FIELDS: &[&str] = &[
+    "file_hash", "value", "rule_id", "rule_name", "ruleset", "description", "references", "validated",
+    "start_line", "end_line", "context", "confidence", "group_index", "source", "path", "severity",
+];
 
 /// 输出项结构（对应 result.json 的单个元素）
 #[derive(Debug, Clone, Serialize)]
 pub struct OutputItem<'a> {
     pub file_hash: &'a str,
     pub value: &'a str,
+    /// 产生该命中的规则 id（仅 `emit_rule_id` 时输出）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule_id: Option<&'a str>,
+    /// 产生该命中的规则名称（仅 `emit_rule_id` 且规则声明了 name 时输出）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule_name: Option<&'a str>,
+    /// 命中规则所属的命名规则集（仅 `with_ruleset` 且配置了命名规则集时输出）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ruleset: Option<&'a str>,
+    /// 规则说明（仅 `with_rule_meta` 时输出）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<&'a str>,
+    /// 规则参考链接（仅 `with_rule_meta` 时输出）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub references: Option<&'a [String]>,
+    /// 校验结果（仅规则声明了校验器且为标注模式时输出）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validated: Option<bool>,
+    /// 命中起始行（从 1 开始，仅 `with_location` 时输出）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_line: Option<usize>,
+    /// 命中结束行（起始行加上命中值内的换行数，仅 `with_location` 时输出）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_line: Option<usize>,
+    /// 命中所在行及前后若干行，或前后若干字节的片段（命中值已脱敏，仅 `context_lines` / `context_bytes` 时输出）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<&'a str>,
+    /// 置信度（0~1，综合校验结果、熵与规则严重级别，仅 `with_score` 时输出）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f32>,
+    /// 产生该命中的捕获组序号（仅 `emit_all_groups` 规则输出）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_index: Option<usize>,
+    /// 产生该命中的扫描路径（诊断用，仅 `with_source` 时输出）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<&'a str>,
+    /// 文件相对所在输入根目录的路径（仅 `emit_path` 时输出）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<&'a str>,
+    /// 命中规则的严重级别（仅 `with_severity` 时输出）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub severity: Option<Severity>,
+}
+
+/// 输出项的自有版本（供库调用方持有，见 `scan_to_result`）
+/// 也可从 result.json 反序列化（见 `load_results`）：结果中没有的 rule_id / start_offset 取空值
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OwnedOutputItem {
+    pub file_hash: String,
+    pub value: String,
+    /// 命中规则 id（从不含 rule_id 的 result.json 读取时为空，序列化时省略）
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub rule_id: String,
+    /// 命中规则所属的命名规则集（仅配置了命名规则集时为 Some）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ruleset: Option<String>,
+    /// 命中的起始字节偏移（相对于逻辑内容：解压 / 归档条目为解压后条目内容内的偏移）
+    #[serde(default)]
+    pub start_offset: usize,
+    /// 校验结果（仅规则声明了校验器且为标注模式时为 Some）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validated: Option<bool>,
+    /// 命中起止行（从 1 开始，仅 `with_location` 时为 Some）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_line: Option<usize>,
+    /// 命中所在行及前后若干行，或前后若干字节的片段（命中值已脱敏，仅 `context_lines` / `context_bytes` 时为 Some）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    /// 置信度（0~1，仅 `with_score` 时为 Some）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f32>,
+    /// 产生该命中的捕获组序号（仅 `emit_all_groups` 规则为 Some）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_index: Option<usize>,
+}
+
+/// 文件被跳过（未计入 files_scanned）的原因
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "kind", content = "detail")]
+pub enum SkipReason {
+    /// 超过 `max_file_size`
+    TooLarge,
+    /// 读取失败：元数据、打开或读取时的 IO 错误，如权限不足（附错误信息）
+    ReadError(String),
+    /// 扫描失败（附错误信息），如 UTF-8 引擎遇到非法编码、文件名无法解析
+    Error(String),
+}
+
+/// 被跳过的文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SkippedFile {
+    pub path: PathBuf,
+    pub reason: SkipReason,
 }
 
+/// 跨文件共享密钥报告：value -> 出现该值的 file_hash 列表（按扫描顺序）
+/// 仅包含出现在至少两个文件中的值；序列化为单个 JSON 对象
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(transparent)]
+pub struct CrossFileReport {
+    pub shared: BTreeMap<String, Vec<String>>,
+}
+
+/// 单个原始命中位置（文件内按 value 去重之前）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HitPosition {
+    pub offset: usize,
+    pub rule_id: String,
+}
+
+/// 原始命中位置报告：file_hash -> 命中位置（按偏移升序），用于渲染命中密度；
+/// 与输出的命中不同，同一 value 的每次出现都会列出；序列化为单个 JSON 对象
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(transparent)]
+pub struct HitPositionReport {
+    pub files: BTreeMap<String, Vec<HitPosition>>,
+}
+
+/// 流式扫描（`scan_and_write_report`）的汇总：统计、跳过的文件与可选的跨文件报告
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanReport {
+    pub stats: ScanStats,
+    pub skipped: Vec<SkippedFile>,
+    /// 仅在 `ScanOptions::cross_file_report` 开启时生成
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cross_file: Option<CrossFileReport>,
+    /// 仅在 `ScanOptions::hit_positions` 开启时生成
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hit_positions: Option<HitPositionReport>,
+    /// 仅在 `ScanOptions::unused_rules` 开启时生成：输出命中数为 0 的规则 id（按规则加载顺序）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unused_rules: Option<Vec<String>>,
+}
+
+/// 一次扫描的完整结果：命中项（稳定顺序）+ 统计 + 跳过的文件
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanResult {
+    pub items: Vec<OwnedOutputItem>,
+    pub stats: ScanStats,
+    pub skipped: Vec<SkippedFile>,
+}
+
+
+/// 规则严重级别（由低到高，可比较大小）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    /// 未声明严重级别的规则默认为 medium
+    #[default]
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "low" => Ok(Severity::Low),
+            "medium" => Ok(Severity::Medium),
+            "high" => Ok(Severity::High),
+            "critical" => Ok(Severity::Critical),
+            other => Err(format!("unknown severity '{}', expected low|medium|high|critical", other)),
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//! 目录遍历过滤：排除模式（`exclude_globs`）、`.gitignore`（`respect_gitignore`）与文件包含条件
+//! （`include_extensions` / `name_globs`）
+//!
+//! `.gitignore` 为常用语法的子集：`#` 注释、`!` 取反、`/` 结尾只匹配目录、开头或中间含 `/` 时相对
+//! `.gitignore` 所在目录匹配（否则匹配任意层级的名称）、`*` `?` `[...]` `**` 通配。
+//! 只读取输入根目录及其子目录中的 `.gitignore`，不读取 `.git/info/exclude` 与全局忽略文件；
+//! 被忽略的目录整棵子树不再遍历，其中的文件无法被 `!` 重新包含（与 git 一致）。
+use anyhow::{Context, Result};
+use globset::{Glob, GlobBuilder, GlobMatcher, GlobSet, GlobSetBuilder};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// 编译排除模式；没有排除模式时为 None
+pub(crate) fn build_excludes(globs: &[String]) -> Result<Option<GlobSet>> {
+    if globs.is_empty() { return Ok(None); }
+    let mut builder = GlobSetBuilder::new();
+    for g in globs {
+        builder.add(Glob::new(g).with_context(|| format!("invalid exclude glob '{}'", g))?);
+    }
+    Ok(Some(builder.build().context("build exclude globs")?))
+}
+
+/// 文件包含条件：扩展名（小写，不含前导 `.`）与名称模式，文件满足其一即扫描
+pub(crate) struct Includes {
+    extensions: Vec<String>,
+    globs: Option<GlobSet>,
+}
+
+impl Includes {
+    /// 编译包含条件；两者均未配置时为 None（扫描全部文件）
+    pub(crate) fn build(extensions: &[String], globs: &[String]) -> Result<Option<Self>> {
+        if extensions.is_empty() && globs.is_empty() { return Ok(None); }
+        let extensions = extensions.iter().map(|e| e.trim_start_matches('.').to_ascii_lowercase()).collect();
+        let globs = if globs.is_empty() {
+            None
+        } else {
+            let mut builder = GlobSetBuilder::new();
+            for g in globs {
+                builder.add(Glob::new(g).with_context(|| format!("invalid name glob '{}'", g))?);
+            }
+            Some(builder.build().context("build name globs")?)
+        };
+        Ok(Some(Self { extensions, globs }))
+    }
+
+    /// 文件是否满足包含条件：文件名（不区分大小写）以 `.<扩展名>` 结尾（`.env` 这类点文件与 `tar.gz`
+    /// 这类多段扩展名同样适用），或名称模式匹配相对根目录的路径 / 文件名
+    pub(crate) fn matches(&self, path: &Path, root: &Path) -> bool {
+        let Some(name) = path.file_name() else { return false };
+        let lower = name.to_string_lossy().to_ascii_lowercase();
+        if self.extensions.iter().any(|e| lower.strip_suffix(e.as_str()).is_some_and(|rest| rest.ends_with('.'))) { return true; }
+        self.globs.as_ref().is_some_and(|set| set.is_match(path.strip_prefix(root).unwrap_or(path)) || set.is_match(name))
+    }
+}
+
+/// 单条 `.gitignore` 规则
+struct IgnoreRule {
+    matcher: GlobMatcher,
+    /// `!` 开头：重新包含此前被忽略的路径
+    negated: bool,
+    /// `/` 结尾：只匹配目录
+    dir_only: bool,
+    /// 含 `/`：匹配相对 `.gitignore` 所在目录的路径；否则只匹配名称
+    anchored: bool,
+}
+
+/// 单个输入根目录的遍历过滤器
+pub(crate) struct WalkFilter {
+    root: PathBuf,
+    excludes: Option<GlobSet>,
+    gitignore: bool,
+    /// 目录 -> 其中 `.gitignore` 的规则（按需读取并缓存；没有该文件时为空）
+    ignores: HashMap<PathBuf, Vec<IgnoreRule>>,
+}
+
+impl WalkFilter {
+    pub(crate) fn new(root: &Path, excludes: Option<GlobSet>, gitignore: bool) -> Self {
+        Self { root: root.to_path_buf(), excludes, gitignore, ignores: HashMap::new() }
+    }
+
+    /// 遍历到的条目是否被排除（目录被排除时不再进入）。输入根本身从不排除；
+    /// 排除模式同时匹配相对根目录的路径与条目名称（`*.min.js`、`node_modules` 均在任意层级生效）
+    pub(crate) fn is_excluded(&mut self, path: &Path, is_dir: bool) -> bool {
+        if path == self.root { return false; }
+        let Some(name) = path.file_name() else { return false };
+        if let Some(set) = &self.excludes {
+            let rel = path.strip_prefix(&self.root).unwrap_or(path);
+            if set.is_match(rel) || set.is_match(name) { return true; }
+        }
+        if !self.gitignore { return false; }
+        if is_dir && name == ".git" { return true; }
+        self.gitignored(path, is_dir)
+    }
+
+    /// 自根目录向下逐级应用各目录的 `.gitignore`：越深、越靠后的规则优先
+    fn gitignored(&mut self, path: &Path, is_dir: bool) -> bool {
+        let Some(name) = path.file_name() else { return false };
+        let dirs: Vec<&Path> = path.ancestors().skip(1).take_while(|d| d.starts_with(&self.root)).collect();
+        let mut ignored = false;
+        for dir in dirs.into_iter().rev() {
+            let rules = self.ignores.entry(dir.to_path_buf()).or_insert_with(|| load_gitignore(dir));
+            let rel = path.strip_prefix(dir).unwrap_or(path);
+            for rule in rules.iter() {
+                if rule.dir_only && !is_dir { continue; }
+                let target = if rule.anchored { rel } else { Path::new(name) };
+                if rule.matcher.is_match(target) { ignored = !rule.negated; }
+            }
+        }
+        ignored
+    }
+}
+
+/// 读取并解析目录中的 `.gitignore`；文件不存在或不可读时为空，无法解析的行忽略
+fn load_gitignore(dir: &Path) -> Vec<IgnoreRule> {
+    let path = dir.join(".gitignore");
+    let Ok(text) = std::fs::read_to_string(&path) else { return Vec::new() };
+    text.lines()
+        .filter_map(|line| {
+            let rule = parse_ignore_line(line);
+            if rule.is_none() && !line.trim().is_empty() && !line.starts_with('#') {
+                tracing::debug!(file = %path.display(), line, "unsupported .gitignore pattern, ignored");
+            }
+            rule
+        })
+        .collect()
+}
+
+/// 解析一行 `.gitignore`；空行、注释与无法编译的模式返回 None
+fn parse_ignore_line(line: &str) -> Option<IgnoreRule> {
+    let line = line.trim_end();
+    if line.is_empty() || line.starts_with('#') { return None; }
+    let (negated, pat) = match line.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, line.strip_prefix('\\').unwrap_or(line)),
+    };
+    let (dir_only, pat) = match pat.strip_suffix('/') {
+        Some(rest) => (true, rest),
+        None => (false, pat),
+    };
+    let anchored = pat.contains('/');
+    let pat = pat.strip_prefix('/').unwrap_or(pat);
+    if pat.is_empty() { return None; }
+    let matcher = GlobBuilder::new(pat).literal_separator(true).build().ok()?.compile_matcher();
+    Some(IgnoreRule { matcher, negated, dir_only, anchored })
+}
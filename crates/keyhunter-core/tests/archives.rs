@@ -0,0 +1,100 @@
+//! 压缩包扫描（`ScanOptions::scan_archives`）
+mod common;
+
+use common::{default_opts, stored_gzip, stored_zip, TempDir, GHP_TOKEN};
+use keyhunter_core::{scan_to_result, ScanOptions, SkipReason};
+
+fn archive_opts() -> ScanOptions {
+    ScanOptions { scan_archives: true, ..default_opts() }
+}
+
+#[test]
+fn zip_entry_token_is_found() {
+    let dir = TempDir::new("zip");
+    let env = format!("GITHUB_TOKEN={}\n", GHP_TOKEN);
+    dir.write("bundle.zip", stored_zip(&[("docs/", b""), ("config/.env", env.as_bytes())]));
+
+    let res = scan_to_result(dir.path(), &archive_opts()).unwrap();
+    assert_eq!(res.items.len(), 1, "{:?}", res.items);
+    assert_eq!(res.items[0].file_hash, "bundle.zip!config/.env");
+    assert_eq!(res.items[0].value, GHP_TOKEN);
+    assert!(res.skipped.is_empty(), "{:?}", res.skipped);
+}
+
+#[test]
+fn zip_is_opaque_without_scan_archives() {
+    let dir = TempDir::new("zip-off");
+    let env = format!("GITHUB_TOKEN={}\n", GHP_TOKEN);
+    dir.write("bundle.zip", stored_zip(&[("config/.env", env.as_bytes())]));
+
+    // stored 条目在压缩包中以明文出现：不展开压缩包时按普通文件扫描，file_hash 为压缩包本身
+    let res = scan_to_result(dir.path(), &default_opts()).unwrap();
+    assert!(res.items.iter().all(|it| it.file_hash == "bundle.zip"), "{:?}", res.items);
+}
+
+#[test]
+fn oversized_zip_central_directory_is_skipped_without_allocating() {
+    let dir = TempDir::new("zip-cd");
+    let mut zip = stored_zip(&[("a.txt", b"hello")]);
+    // 中央目录大小改为 0xFFFF_FFF0：超出文件大小，应报错跳过而不是按该大小分配缓冲区
+    let eocd = zip.len() - 22;
+    zip[eocd + 12..eocd + 16].copy_from_slice(&0xFFFF_FFF0u32.to_le_bytes());
+    dir.write("bad.zip", zip);
+
+    let res = scan_to_result(dir.path(), &archive_opts()).unwrap();
+    assert!(res.items.is_empty());
+    assert_eq!(res.skipped.len(), 1, "{:?}", res.skipped);
+}
+
+#[test]
+fn oversized_zip_entry_is_skipped_without_allocating() {
+    let dir = TempDir::new("zip-entry");
+    let env = format!("GITHUB_TOKEN={}\n", GHP_TOKEN);
+    let mut zip = stored_zip(&[("huge.bin", b"xxxx"), ("config/.env", env.as_bytes())]);
+    // 第一个条目中央目录记录的压缩后大小改为 0x7FFF_FFFF（解压后大小不变）：该条目记为跳过，其余条目照常扫描
+    let cd = u32::from_le_bytes(zip[zip.len() - 6..zip.len() - 2].try_into().unwrap()) as usize;
+    zip[cd + 20..cd + 24].copy_from_slice(&0x7FFF_FFFFu32.to_le_bytes());
+    dir.write("bundle.zip", zip);
+
+    let res = scan_to_result(dir.path(), &archive_opts()).unwrap();
+    assert_eq!(res.items.len(), 1, "{:?}", res.items);
+    assert_eq!(res.items[0].file_hash, "bundle.zip!config/.env");
+    assert!(res.skipped.iter().any(|s| s.path.to_string_lossy().ends_with("bundle.zip!huge.bin")), "{:?}", res.skipped);
+}
+
+#[test]
+fn zip_entry_offsets_are_relative_to_entry_content() {
+    let dir = TempDir::new("zip-offset");
+    let padding = "# padding line\n".repeat(7);
+    let env = format!("{}GITHUB_TOKEN={}\n", padding, GHP_TOKEN);
+    let expected = padding.len() + "GITHUB_TOKEN=".len();
+    dir.write("bundle.zip", stored_zip(&[("readme.txt", b"nothing to see here\n"), ("config/.env", env.as_bytes())]));
+
+    let res = scan_to_result(dir.path(), &archive_opts()).unwrap();
+    assert_eq!(res.items.len(), 1, "{:?}", res.items);
+    assert_eq!(res.items[0].file_hash, "bundle.zip!config/.env");
+    // 偏移相对于解压后的条目内容，而非压缩包内的位置（条目前还有本地文件头与前一个条目）
+    assert_eq!(res.items[0].start_offset, expected);
+}
+
+#[test]
+fn gzip_file_is_scanned_as_a_single_entry() {
+    let dir = TempDir::new("gz");
+    let log = format!("{}GITHUB_TOKEN={}\n", "request ok\n".repeat(8000), GHP_TOKEN);
+    dir.write("app.log.gz", stored_gzip(log.as_bytes()));
+    dir.write("broken.gz", b"\x1f\x8b\x08\x00not really gzip data at all");
+
+    let opts = ScanOptions { with_location: true, ..archive_opts() };
+    let res = scan_to_result(dir.path(), &opts).unwrap();
+    assert_eq!(res.items.len(), 1, "{:?}", res.items);
+    assert_eq!(res.items[0].file_hash, "app.log.gz!app.log");
+    assert_eq!(res.items[0].start_offset, log.find("ghp_").unwrap());
+    assert_eq!(res.items[0].start_line, Some(8001));
+    // 损坏的 gzip 记为跳过
+    assert_eq!(res.skipped.len(), 1, "{:?}", res.skipped);
+
+    // 解压后超过 max_file_size 的内容记为过大
+    let res = scan_to_result(dir.path(), &ScanOptions { max_file_size: Some(1024), ..opts }).unwrap();
+    assert!(res.items.is_empty());
+    assert!(res.skipped.iter().any(|s| s.reason == SkipReason::TooLarge), "{:?}", res.skipped);
+}
@@ -0,0 +1,54 @@
+//! 基线文件（`ScanOptions::baseline` / `write_baseline`）
+mod common;
+
+use std::sync::Arc;
+
+use common::{default_opts, TempDir, GHP_TOKEN};
+use keyhunter_core::{scan_to_result, Baseline, ScanOptions};
+
+fn write_inputs(dir: &TempDir) {
+    dir.write("in/a.env", format!("GITHUB_TOKEN={}\n", GHP_TOKEN));
+    dir.write("in/b.env", format!("TOKEN={}\n", GHP_TOKEN));
+}
+
+#[test]
+fn baseline_entries_are_suppressed_and_counted() {
+    let dir = TempDir::new("baseline");
+    write_inputs(&dir);
+    let path = dir.write("baseline.json", format!(r#"[{{"file_hash": "a.env", "value": "{}"}}]"#, GHP_TOKEN));
+
+    let opts = ScanOptions { baseline: Some(Arc::new(Baseline::load(&path).unwrap())), ..default_opts() };
+    let res = scan_to_result(&dir.path().join("in"), &opts).unwrap();
+    // 基线按 (file_hash, 命中值) 匹配：b.env 中的同一值仍然报告
+    assert_eq!(res.items.len(), 1, "{:?}", res.items);
+    assert_eq!(res.items[0].file_hash, "b.env");
+    assert_eq!(res.stats.suppressed, 1);
+}
+
+#[test]
+fn written_baseline_round_trips() {
+    let dir = TempDir::new("baseline-write");
+    write_inputs(&dir);
+    let path = dir.path().join("baseline.json");
+
+    let first = scan_to_result(&dir.path().join("in"), &ScanOptions { write_baseline: Some(path.clone()), ..default_opts() }).unwrap();
+    assert_eq!(first.items.len(), 2);
+    // 生成的基线只含 sha256，不保存密钥原文
+    let txt = std::fs::read_to_string(&path).unwrap();
+    assert!(!txt.contains(GHP_TOKEN), "{}", txt);
+    let baseline = Baseline::load(&path).unwrap();
+    assert_eq!(baseline.len(), 2);
+
+    let opts = ScanOptions { baseline: Some(Arc::new(baseline)), ..default_opts() };
+    let second = scan_to_result(&dir.path().join("in"), &opts).unwrap();
+    assert!(second.items.is_empty(), "{:?}", second.items);
+    assert_eq!(second.stats.suppressed, 2);
+}
+
+#[test]
+fn malformed_baseline_is_rejected() {
+    let dir = TempDir::new("baseline-bad");
+    let path = dir.write("baseline.json", r#"[{"file_hash": "a.env", "sha256": "not-hex"}]"#);
+    let err = Baseline::load(&path).unwrap_err();
+    assert!(format!("{:#}", err).contains("not a hex SHA-256"), "{:#}", err);
+}
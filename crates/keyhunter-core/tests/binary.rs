@@ -0,0 +1,50 @@
+//! 二进制文件处理（`ScanOptions::binary_mode` / `binary_printable_threshold`）
+mod common;
+
+use common::{default_opts, TempDir, GHP_TOKEN};
+use keyhunter_core::{scan_to_result, BinaryMode, ScanOptions};
+
+/// 令牌前后只有一个 NUL 字节的文件：默认判为二进制跳过，raw 模式按原始字节扫描
+#[test]
+fn embedded_nul_is_skipped_by_default_and_scanned_raw() {
+    let dir = TempDir::new("binary-nul");
+    dir.write("config.bin", format!("token={}\0trailer\n", GHP_TOKEN));
+
+    let skipped = scan_to_result(dir.path(), &default_opts()).unwrap();
+    assert!(skipped.items.is_empty(), "{:?}", skipped.items);
+    assert_eq!(skipped.stats.files_skipped_binary, 1);
+
+    let raw = scan_to_result(dir.path(), &ScanOptions { binary_mode: BinaryMode::Raw, ..default_opts() }).unwrap();
+    assert_eq!(raw.items.len(), 1, "{:?}", raw.items);
+    assert_eq!(raw.items[0].value, GHP_TOKEN);
+    assert_eq!(raw.items[0].start_offset, "token=".len());
+    assert_eq!(raw.stats.files_skipped_binary, 0);
+}
+
+#[test]
+fn strings_mode_scans_printable_runs() {
+    let dir = TempDir::new("binary-strings");
+    let mut content = vec![0u8, 1, 2, 3];
+    content.extend_from_slice(GHP_TOKEN.as_bytes());
+    content.extend_from_slice(&[0, 0xFF, 0]);
+    dir.write("blob.bin", &content);
+
+    let res = scan_to_result(dir.path(), &ScanOptions { binary_mode: BinaryMode::Strings, ..default_opts() }).unwrap();
+    assert_eq!(res.items.len(), 1, "{:?}", res.items);
+    assert_eq!(res.items[0].start_offset, 4);
+}
+
+/// 不含 NUL 但可打印字符占比低于阈值的内容按二进制处理；阈值为 0 时只按 NUL 判定
+#[test]
+fn printable_threshold_is_tunable() {
+    let dir = TempDir::new("binary-ratio");
+    let mut content: Vec<u8> = vec![0x80; 200];
+    content.extend_from_slice(format!(" {} ", GHP_TOKEN).as_bytes());
+    dir.write("mostly-high.dat", &content);
+
+    let default = scan_to_result(dir.path(), &default_opts()).unwrap();
+    assert!(default.items.is_empty(), "{:?}", default.items);
+
+    let lenient = scan_to_result(dir.path(), &ScanOptions { binary_printable_threshold: 0.0, ..default_opts() }).unwrap();
+    assert_eq!(lenient.items.len(), 1, "{:?}", lenient.items);
+}
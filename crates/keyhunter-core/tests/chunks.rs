@@ -0,0 +1,83 @@
+//! 大文件分块扫描：块边界附近的命中与整体扫描一致，流式读取（`scan_reader`）与按文件扫描一致
+mod common;
+
+use common::{default_opts, TempDir, GHP_TOKEN};
+use std::io::Read;
+
+use keyhunter_core::{load_prefilter_plan, scan_reader, scan_to_result, OwnedOutputItem, ScanEngine, ScanOptions};
+
+const MIB: usize = 1024 * 1024;
+
+/// 16 MiB 的填充文本，在 `positions` 处各写入一个不同的令牌（令牌从该偏移开始）
+fn padded_file(dir: &TempDir, positions: &[usize]) -> Vec<usize> {
+    let line = b"filler text without any secrets in it at all\n";
+    let mut buf: Vec<u8> = line.iter().copied().cycle().take(16 * MIB).collect();
+    for (i, &pos) in positions.iter().enumerate() {
+        let token = format!("ghp_{:02}{}", i, &GHP_TOKEN[6..]);
+        buf[pos - 1] = b'\n';
+        buf[pos..pos + token.len()].copy_from_slice(token.as_bytes());
+        buf[pos + token.len()] = b'\n';
+    }
+    dir.write("big.log", buf);
+    positions.to_vec()
+}
+
+fn scan(dir: &TempDir, opts: ScanOptions) -> Vec<OwnedOutputItem> {
+    scan_to_result(dir.path(), &ScanOptions { with_location: true, ..opts }).unwrap().items
+}
+
+#[test]
+fn key_on_a_chunk_boundary_matches_single_read() {
+    let dir = TempDir::new("chunks-boundary");
+    // 令牌起点正好在 4 MiB 块边界上、跨越 8 MiB 边界、锚点被 12 MiB 边界截断，以及远离边界的对照
+    let positions = padded_file(&dir, &[4 * MIB, 8 * MIB - 20, 12 * MIB - 3, 2 * MIB + 100]);
+
+    // 每个令牌都在原位置命中恰好一次，作为参照
+    let single = scan(&dir, ScanOptions { engine: ScanEngine::Bytes, ..default_opts() });
+    let mut offsets: Vec<usize> = single.iter().map(|it| it.start_offset).collect();
+    offsets.sort();
+    let mut expected = positions.clone();
+    expected.sort();
+    assert_eq!(offsets, expected);
+
+    for engine in [ScanEngine::Bytes, ScanEngine::Auto] {
+        let chunked = scan(&dir, ScanOptions { engine, ..default_opts() });
+        assert_eq!(chunked, single, "{:?}", engine);
+        // 超过整读上限的文件即使开启内存映射也分块读取
+        let mapped = scan(&dir, ScanOptions { engine, use_mmap: true, ..default_opts() });
+        assert_eq!(mapped, single, "{:?} with mmap", engine);
+        // 更小的块（64 KiB）：边界更多，结果不变
+        let small = scan(&dir, ScanOptions { engine, memory_budget: Some(64 * 1024), ..default_opts() });
+        assert_eq!(small, single, "{:?} with small chunks", engine);
+    }
+}
+
+/// 每次至多返回 `max` 字节的读取器（模拟管道 / 网络流的短读）
+struct ShortReads<R> {
+    inner: R,
+    max: usize,
+}
+
+impl<R: Read> Read for ShortReads<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = buf.len().min(self.max);
+        self.inner.read(&mut buf[..n])
+    }
+}
+
+#[test]
+fn streaming_reader_matches_file_scan() {
+    let dir = TempDir::new("chunks-reader");
+    padded_file(&dir, &[4 * MIB, 8 * MIB - 20, 12 * MIB - 3, 2 * MIB + 100]);
+    let opts = ScanOptions { engine: ScanEngine::Bytes, with_location: true, ..default_opts() };
+    let from_file = scan(&dir, opts.clone());
+    assert_eq!(from_file.len(), 4);
+
+    let plan = load_prefilter_plan(&opts).unwrap();
+    let path = dir.path().join("big.log");
+    let whole = scan_reader(std::fs::File::open(&path).unwrap(), "big.log", &opts, &plan).unwrap();
+    assert_eq!(whole, from_file);
+    // 短读不影响块的划分与命中偏移
+    let short = ShortReads { inner: std::fs::File::open(&path).unwrap(), max: 7777 };
+    assert_eq!(scan_reader(short, "big.log", &opts, &plan).unwrap(), from_file);
+}
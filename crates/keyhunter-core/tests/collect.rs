@@ -0,0 +1,46 @@
+//! 库调用收集结果（`scan_to_result`）与收集后写出的 JSON（`scan_and_write`）、流式 JSON 输出（`scan_and_write_report`）一致
+mod common;
+
+use common::{default_opts, TempDir, GHP_TOKEN};
+use keyhunter_core::{scan_and_write, scan_and_write_report, scan_to_result, OwnedOutputItem, ScanOptions};
+
+fn write_inputs(dir: &TempDir) {
+    for i in 0..12 {
+        let token = format!("ghp_{:02}kq2LmZ7vXw4NtY9pHs3JdC6bFg1aUe5Qo0", i);
+        dir.write(&format!("svc{:02}.env", i), format!("# service {}\nGITHUB_TOKEN={}\nBACKUP={}\n", i, token, GHP_TOKEN));
+    }
+    dir.write("empty.txt", "no secrets here\n");
+}
+
+#[test]
+fn collected_items_equal_parsed_json_output() {
+    let dir = TempDir::new("collect");
+    write_inputs(&dir);
+
+    for threads in [1, 4] {
+        let opts = ScanOptions { threads: Some(threads), emit_rule_id: true, with_location: true, with_score: true, ..default_opts() };
+        let collected = scan_to_result(dir.path(), &opts).unwrap();
+
+        let mut out = Vec::new();
+        let stats = scan_and_write(dir.path(), &mut out, &opts).unwrap();
+        let parsed: Vec<OwnedOutputItem> = serde_json::from_slice(&out).unwrap();
+
+        // JSON 输出不含 start_offset，比较时忽略
+        let items: Vec<OwnedOutputItem> = collected.items.into_iter().map(|it| OwnedOutputItem { start_offset: 0, ..it }).collect();
+        assert_eq!(items.len(), 24, "threads={}", threads);
+        assert_eq!(items, parsed, "threads={}", threads);
+        assert_eq!(collected.stats.outputs_written, stats.outputs_written);
+        assert_eq!(collected.stats.files_scanned, stats.files_scanned);
+
+        // 边扫边写的输出与收集后写出的输出逐字节相同
+        let mut streamed = Vec::new();
+        scan_and_write_report(dir.path(), &mut streamed, &opts).unwrap();
+        assert_eq!(streamed, out, "threads={}", threads);
+    }
+
+    // 输出大小上限需要边扫边写
+    let opts = ScanOptions { max_output_bytes: Some(256), ..default_opts() };
+    assert!(scan_and_write(dir.path(), &mut Vec::new(), &opts).is_err());
+    let mut streamed = Vec::new();
+    assert!(scan_and_write_report(dir.path(), &mut streamed, &opts).unwrap().stats.output_truncated);
+}
@@ -0,0 +1,29 @@
+//! 命中上下文（`context_bytes` / `context_lines`）：取自被扫描的缓冲区，命中值已脱敏
+mod common;
+
+use common::{default_opts, TempDir, GHP_TOKEN};
+use keyhunter_core::{scan_to_result, ScanOptions};
+
+#[test]
+fn context_snippet_surrounds_the_masked_value() {
+    let dir = TempDir::new("context");
+    dir.write("a.env", format!("# deploy settings\nexport GITHUB_TOKEN={} # rotate monthly\nDEBUG=1\n", GHP_TOKEN));
+    let context = |opts: ScanOptions| -> Option<String> {
+        let res = scan_to_result(dir.path(), &opts).unwrap();
+        assert_eq!(res.items.len(), 1);
+        assert_eq!(res.items[0].value, GHP_TOKEN);
+        res.items.into_iter().next().unwrap().context
+    };
+
+    assert_eq!(context(default_opts()), None);
+    // 命中前后各 12 字节
+    assert_eq!(context(ScanOptions { context_bytes: 12, ..default_opts() }).as_deref(), Some("ITHUB_TOKEN=ghp_****5Qo0 # rotate mo"));
+    // 命中所在行及前后各一行
+    assert_eq!(
+        context(ScanOptions { context_lines: Some(1), ..default_opts() }).as_deref(),
+        Some("# deploy settings\nexport GITHUB_TOKEN=ghp_****5Qo0 # rotate monthly\nDEBUG=1")
+    );
+    // 片段在缓冲区开头截断
+    dir.write("a.env", format!("{}\n", GHP_TOKEN));
+    assert_eq!(context(ScanOptions { context_bytes: 12, ..default_opts() }).as_deref(), Some("ghp_****5Qo0\n"));
+}
@@ -0,0 +1,51 @@
+//! CSV 输出：经 CSV 读取器解析后与 JSON 输出的命中逐条一致（含逗号、引号、换行与制表符的值）
+mod common;
+
+use common::{custom_opts, TempDir, GHP_TOKEN};
+use keyhunter_core::{scan_and_write, OutputFormat, ScanOptions};
+use serde_json::Value;
+
+const RULES: &str = "[[rules]]\nid = \"test.pat\"\nname = \"Test PAT\"\nprefixes = [\"ghp_\"]\npattern = \"[0-9a-zA-Z]{36}\"\n\n\
+                     [[rules]]\nid = \"test.odd\"\nprefixes = [\"tk_live_\"]\npattern = '[a-z0-9,\"\\t\\n]{12}'\n";
+
+fn scan(dir: &TempDir, opts: &ScanOptions) -> String {
+    let mut out = Vec::new();
+    scan_and_write(dir.path(), &mut out, opts).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn csv_round_trips_to_the_json_findings() {
+    let (rules, dir) = (TempDir::new("csv-rules"), TempDir::new("csv"));
+    dir.write("a.env", format!("GITHUB_TOKEN={}\nkey = tk_live_ab,cd\"ef\tgh1\n", GHP_TOKEN));
+    dir.write("b,c.txt", "one = tk_live_abc\ndef,gh12\n");
+    let opts = ScanOptions { emit_rule_id: true, with_location: true, ..custom_opts(&rules, RULES) };
+
+    let json: Vec<Value> = serde_json::from_str(&scan(&dir, &opts)).unwrap();
+    assert_eq!(json.len(), 3, "{:?}", json);
+    let csv_text = scan(&dir, &ScanOptions { format: OutputFormat::Csv, ..opts });
+
+    let mut reader = csv::Reader::from_reader(csv_text.as_bytes());
+    let headers: Vec<String> = reader.headers().unwrap().iter().map(String::from).collect();
+    for col in ["file_hash", "value", "rule_id", "rule_name", "start_line", "end_line"] {
+        assert!(headers.iter().any(|h| h == col), "missing column {}: {:?}", col, headers);
+    }
+    let rows: Vec<csv::StringRecord> = reader.records().map(|r| r.unwrap()).collect();
+    assert_eq!(rows.len(), json.len());
+
+    // 每行的每一列与 JSON 中同名字段一致（JSON 中缺失的字段为空串），顺序相同
+    for (row, item) in rows.iter().zip(&json) {
+        assert_eq!(row.len(), headers.len());
+        for (col, cell) in headers.iter().zip(row.iter()) {
+            let expected = match &item[col.as_str()] {
+                Value::Null => String::new(),
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            assert_eq!(cell, expected, "column {}", col);
+        }
+    }
+    let values: Vec<&str> = rows.iter().map(|r| r.get(1).unwrap()).collect();
+    assert_eq!(values, [GHP_TOKEN, "tk_live_ab,cd\"ef\tgh1", "tk_live_abc\ndef,gh12"]);
+    assert_eq!(rows[2].get(0), Some("b,c.txt"));
+}
@@ -0,0 +1,73 @@
+//! 命中去重：文件内按值去重、`no_dedup`、`normalize_dedup` 与 `DedupScope`
+mod common;
+
+use common::{custom_opts, default_opts, TempDir, GHP_TOKEN};
+use keyhunter_core::{scan_to_result, DedupScope, ScanEngine, ScanOptions};
+
+#[test]
+fn overlapping_windows_keep_the_earliest_offset() {
+    let dir = TempDir::new("dedup-overlap");
+    // 两次出现相距很近，两个锚点窗口互相覆盖，都能匹配到同一个值
+    let first = "token: ".len();
+    dir.write("a.txt", format!("token: {t}\nagain: {t}\n", t = GHP_TOKEN));
+
+    for engine in [ScanEngine::Bytes, ScanEngine::Utf8, ScanEngine::Auto] {
+        let res = scan_to_result(dir.path(), &ScanOptions { engine, ..default_opts() }).unwrap();
+        assert_eq!(res.items.len(), 1, "{:?}: {:?}", engine, res.items);
+        assert_eq!(res.items[0].start_offset, first, "{:?}", engine);
+    }
+}
+
+#[test]
+fn no_dedup_keeps_every_occurrence() {
+    let dir = TempDir::new("dedup-off");
+    let content = format!("a={t}\nb={t}\nc={t}\n", t = GHP_TOKEN);
+    let offsets: Vec<usize> = content.match_indices(GHP_TOKEN).map(|(i, _)| i).collect();
+    dir.write("a.txt", &content);
+
+    let res = scan_to_result(dir.path(), &default_opts()).unwrap();
+    assert_eq!(res.items.len(), 1, "{:?}", res.items);
+
+    for engine in [ScanEngine::Bytes, ScanEngine::Utf8] {
+        let res = scan_to_result(dir.path(), &ScanOptions { engine, no_dedup: true, ..default_opts() }).unwrap();
+        let got: Vec<usize> = res.items.iter().map(|it| it.start_offset).collect();
+        assert_eq!(got, offsets, "{:?}", engine);
+        assert!(res.items.iter().all(|it| it.value == GHP_TOKEN));
+    }
+}
+
+#[test]
+fn normalize_dedup_merges_bare_and_quoted_values() {
+    let (rules, dir) = (TempDir::new("dedup-norm-rules"), TempDir::new("dedup-norm"));
+    // 模式允许带上结尾的引号，同一密钥的带引号写法得到不同的命中值
+    let opts = custom_opts(&rules, "[[rules]]\nid = \"test.key\"\nprefixes = [\"tk_live_\"]\npattern = '[a-z0-9]{12}\"?'\n");
+    dir.write("a.txt", "key = \"tk_live_0123456789ab\"\nkey = tk_live_0123456789ab\n");
+
+    let values = |opts: &ScanOptions| -> Vec<String> {
+        scan_to_result(dir.path(), opts).unwrap().items.into_iter().map(|it| it.value).collect()
+    };
+    assert_eq!(values(&opts), ["tk_live_0123456789ab\"", "tk_live_0123456789ab"]);
+    // 只影响去重的键：保留偏移最小的一条，输出原始命中值
+    assert_eq!(values(&ScanOptions { normalize_dedup: true, ..opts }), ["tk_live_0123456789ab\""]);
+}
+
+#[test]
+fn global_dedup_keeps_the_first_file_only() {
+    let dir = TempDir::new("dedup-global");
+    for name in ["a.env", "b.env", "c.env"] {
+        dir.write(name, format!("GITHUB_TOKEN={}\n", GHP_TOKEN));
+    }
+
+    for threads in [1, 4] {
+        let opts = ScanOptions { threads: Some(threads), ..default_opts() };
+        let res = scan_to_result(dir.path(), &opts).unwrap();
+        let files: Vec<&str> = res.items.iter().map(|it| it.file_hash.as_str()).collect();
+        assert_eq!(files, ["a.env", "b.env", "c.env"], "threads {}", threads);
+        assert_eq!(res.stats.global_duplicates, 0);
+
+        let res = scan_to_result(dir.path(), &ScanOptions { dedup: DedupScope::Global, ..opts }).unwrap();
+        let files: Vec<&str> = res.items.iter().map(|it| it.file_hash.as_str()).collect();
+        assert_eq!(files, ["a.env"], "threads {}", threads);
+        assert_eq!(res.stats.global_duplicates, 2, "threads {}", threads);
+    }
+}
@@ -0,0 +1,51 @@
+//! 内容摘要作为 file_hash（`HashMode::Sha256` / `HashMode::Md5`）
+mod common;
+
+use common::{default_opts, TempDir, GHP_TOKEN};
+use keyhunter_core::{scan_to_result, HashMode, ScanOptions};
+use sha2::{Digest, Sha256};
+
+fn sha256_hex(content: &[u8]) -> String {
+    Sha256::digest(content).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 约 1.5 MiB、超过整读上限的文件，令牌位于中部
+fn large_content() -> Vec<u8> {
+    let filler: Vec<u8> = (0..768 * 1024).map(|i| b"abcdefghij \n"[i % 12]).collect();
+    let mut content = filler.clone();
+    content.extend_from_slice(format!(" {} ", GHP_TOKEN).as_bytes());
+    content.extend_from_slice(&filler);
+    content
+}
+
+#[test]
+fn identical_contents_share_the_sha256_file_hash() {
+    let dir = TempDir::new("digest-same");
+    let content = format!("GITHUB_TOKEN={}\n", GHP_TOKEN);
+    dir.write("a.env", &content);
+    dir.write("b.env", &content);
+
+    let opts = ScanOptions { hash_mode: HashMode::Sha256, ..default_opts() };
+    let res = scan_to_result(dir.path(), &opts).unwrap();
+    assert_eq!(res.items.len(), 2, "{:?}", res.items);
+    let expected = sha256_hex(content.as_bytes());
+    assert!(res.items.iter().all(|it| it.file_hash == expected), "{:?}", res.items);
+}
+
+#[test]
+fn chunked_scan_hashes_the_whole_file() {
+    let dir = TempDir::new("digest-chunked");
+    let content = large_content();
+    dir.write("large.txt", &content);
+    let expected = sha256_hex(&content);
+
+    // 小内存预算迫使分块读取；局部扫描只读文件头部，摘要另行计算，仍覆盖整个文件
+    let chunked = ScanOptions { hash_mode: HashMode::Sha256, memory_budget: Some(128 * 1024), ..default_opts() };
+    let head = ScanOptions { head_bytes: Some(1024 * 1024), ..chunked.clone() };
+    for opts in [chunked, head] {
+        let res = scan_to_result(dir.path(), &opts).unwrap();
+        assert_eq!(res.items.len(), 1, "{:?}", res.items);
+        assert_eq!(res.items[0].value, GHP_TOKEN);
+        assert_eq!(res.items[0].file_hash, expected);
+    }
+}
@@ -0,0 +1,87 @@
+//! 读取失败的文件：记为跳过（`SkipReason::ReadError`）并计入 `files_errored`，其余文件照常扫描
+mod common;
+
+use std::sync::Arc;
+
+use common::{default_opts, TempDir, GHP_TOKEN};
+use keyhunter_core::{scan_to_result, ProgressEvent, ScanOptions, SkipReason};
+
+#[test]
+fn file_removed_before_reading_is_reported() {
+    let dir = TempDir::new("errors-gone");
+    dir.write("a.env", format!("GITHUB_TOKEN={}\n", GHP_TOKEN));
+    let gone = dir.write("b.env", format!("GITHUB_TOKEN={}\n", GHP_TOKEN));
+    dir.write("c.env", format!("GITHUB_TOKEN={}\n", GHP_TOKEN));
+
+    // 遍历之后、读取之前删除 b.env（单线程按文件名顺序处理：a.env 处理完时 b.env 尚未打开）
+    let victim = gone.clone();
+    let on_progress = move |ev: ProgressEvent<'_>| {
+        if ev.files_done == 1 { std::fs::remove_file(&victim).unwrap(); }
+    };
+    let opts = ScanOptions { on_progress: Some(Arc::new(on_progress)), ..default_opts() };
+    let res = scan_to_result(dir.path(), &opts).unwrap();
+
+    let files: Vec<&str> = res.items.iter().map(|it| it.file_hash.as_str()).collect();
+    assert_eq!(files, ["a.env", "c.env"]);
+    assert_eq!(res.stats.files_scanned, 2);
+    assert_eq!(res.stats.files_errored, 1);
+    assert_eq!(res.skipped.len(), 1, "{:?}", res.skipped);
+    assert_eq!(res.skipped[0].path, gone);
+    assert!(matches!(res.skipped[0].reason, SkipReason::ReadError(_)), "{:?}", res.skipped[0].reason);
+}
+
+#[cfg(unix)]
+#[test]
+fn dangling_symlink_is_reported_when_following_links() {
+    let dir = TempDir::new("errors-link");
+    dir.write("a.env", format!("GITHUB_TOKEN={}\n", GHP_TOKEN));
+    std::os::unix::fs::symlink(dir.path().join("missing.env"), dir.path().join("broken.env")).unwrap();
+
+    let res = scan_to_result(dir.path(), &ScanOptions { follow_symlinks: true, ..default_opts() }).unwrap();
+    assert_eq!(res.items.len(), 1, "{:?}", res.items);
+    assert_eq!(res.stats.files_errored, 1);
+    assert_eq!(res.skipped.len(), 1, "{:?}", res.skipped);
+    assert!(matches!(res.skipped[0].reason, SkipReason::ReadError(_)), "{:?}", res.skipped[0].reason);
+}
+
+#[cfg(unix)]
+#[test]
+fn read_errors_are_counted_apart_from_size_skips() {
+    let dir = TempDir::new("errors-kinds");
+    dir.write("a.env", format!("GITHUB_TOKEN={}\n", GHP_TOKEN));
+    dir.write("big.env", format!("{}GITHUB_TOKEN={}\n", "#".repeat(4096), GHP_TOKEN));
+    std::os::unix::fs::symlink(dir.path().join("missing.env"), dir.path().join("broken.env")).unwrap();
+
+    // 串行与并行路径一致：读取失败计入 files_errored，过大的文件只记为跳过
+    for threads in [1, 4] {
+        let opts = ScanOptions { follow_symlinks: true, max_file_size: Some(1024), threads: Some(threads), ..default_opts() };
+        let res = scan_to_result(dir.path(), &opts).unwrap();
+        assert_eq!(res.items.len(), 1, "threads {}", threads);
+        assert_eq!(res.stats.files_scanned, 1);
+        assert_eq!(res.stats.files_errored, 1, "threads {}", threads);
+        let reasons: Vec<(&str, &SkipReason)> =
+            res.skipped.iter().map(|s| (s.path.file_name().unwrap().to_str().unwrap(), &s.reason)).collect();
+        assert_eq!(reasons.len(), 2, "{:?}", reasons);
+        // 悬空链接在遍历时即报告，排在扫描阶段的跳过之前
+        assert!(matches!(reasons[0], ("broken.env", SkipReason::ReadError(_))), "{:?}", reasons);
+        assert!(matches!(reasons[1], ("big.env", SkipReason::TooLarge)), "{:?}", reasons);
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn permission_denied_is_reported() {
+    use std::os::unix::fs::PermissionsExt;
+    let dir = TempDir::new("errors-perm");
+    dir.write("a.env", format!("GITHUB_TOKEN={}\n", GHP_TOKEN));
+    let locked = dir.write("locked.env", format!("GITHUB_TOKEN={}\n", GHP_TOKEN));
+    std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o000)).unwrap();
+    // 以 root 运行时权限位不起作用
+    if std::fs::File::open(&locked).is_ok() { return; }
+
+    let res = scan_to_result(dir.path(), &default_opts()).unwrap();
+    assert_eq!(res.stats.files_scanned, 1);
+    assert_eq!(res.stats.files_errored, 1);
+    assert_eq!(res.skipped.len(), 1);
+    assert!(matches!(res.skipped[0].reason, SkipReason::ReadError(_)), "{:?}", res.skipped[0].reason);
+}
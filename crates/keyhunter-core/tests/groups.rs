@@ -0,0 +1,37 @@
+//! 捕获组的取值：优先第 1 组，第 1 组未参与匹配或匹配为空时退回整个匹配
+mod common;
+
+use common::{custom_opts, TempDir};
+use keyhunter_core::{scan_to_result, ScanEngine, ScanOptions};
+
+const RULES: &str = r#"
+[[rules]]
+id = "test.optional_group"
+pattern = "(sk-[a-z]*)?API_KEY=([A-Za-z0-9]+)"
+
+[[rules]]
+id = "test.empty_group"
+pattern = "([a-z]*)PRIVATE_TOKEN=[A-Za-z0-9]+"
+"#;
+
+#[test]
+fn empty_or_missing_group_one_falls_back_to_the_whole_match() {
+    let (rules, dir) = (TempDir::new("groups-rules"), TempDir::new("groups"));
+    dir.write("a.txt", "API_KEY=abc123XYZ\nsk-liveAPI_KEY=def456UVW\nPRIVATE_TOKEN=Zx9Yw8Vu7\n");
+
+    for engine in [ScanEngine::Bytes, ScanEngine::Utf8] {
+        let opts = ScanOptions { engine, ..custom_opts(&rules, RULES) };
+        let res = scan_to_result(dir.path(), &opts).unwrap();
+        let got: Vec<(&str, &str)> = res.items.iter().map(|it| (it.rule_id.as_str(), it.value.as_str())).collect();
+        assert_eq!(
+            got,
+            [
+                ("test.optional_group", "API_KEY=abc123XYZ"),
+                ("test.optional_group", "sk-live"),
+                ("test.empty_group", "PRIVATE_TOKEN=Zx9Yw8Vu7"),
+            ],
+            "{:?}",
+            engine
+        );
+    }
+}
@@ -0,0 +1,30 @@
+//! 单行长度上限（`max_line_length`）：超长行（压缩后的 JS 等）内的命中跳过
+mod common;
+
+use common::{default_opts, TempDir, GHP_TOKEN};
+use keyhunter_core::{scan_to_result, ScanEngine, ScanOptions};
+
+#[test]
+fn key_inside_a_minified_line_is_skipped() {
+    let dir = TempDir::new("long-lines");
+    let decoy = GHP_TOKEN.replace("R8kq", "M1nj");
+    let mut minified = String::from("!function(){var a=1;");
+    while minified.len() < 2 * 1024 * 1024 {
+        minified.push_str("var b=function(c){return c+1};");
+    }
+    minified.push_str(&format!("var t=\"{}\";", decoy));
+    minified.push_str(&"a=b(a);".repeat(1000));
+    dir.write("bundle.js", format!("GITHUB_TOKEN={}\n{}\n", GHP_TOKEN, minified));
+
+    let found = |max_line_length: Option<usize>| {
+        let opts = ScanOptions { engine: ScanEngine::Bytes, max_line_length, ..default_opts() };
+        let res = scan_to_result(dir.path(), &opts).unwrap();
+        (res.items.into_iter().map(|it| it.value).collect::<Vec<_>>(), res.stats.long_lines_skipped)
+    };
+    // 缺省不限制：两个令牌都命中
+    assert_eq!(found(None), (vec![GHP_TOKEN.to_string(), decoy.clone()], 0));
+    // 只保留正常行中的令牌
+    let (values, skipped) = found(Some(10_000));
+    assert_eq!(values, [GHP_TOKEN]);
+    assert!(skipped >= 1, "{}", skipped);
+}
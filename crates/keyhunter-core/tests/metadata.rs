@@ -0,0 +1,61 @@
+//! 运行元数据嵌入输出（`ScanOptions::with_metadata`）
+mod common;
+
+use std::path::PathBuf;
+
+use common::{default_opts, TempDir, GHP_TOKEN};
+use keyhunter_core::{scan_roots_and_write_report, OutputFormat, ScanOptions};
+use serde_json::Value;
+
+fn scan_json(dir: &TempDir, opts: &ScanOptions) -> Value {
+    let mut out = Vec::new();
+    scan_roots_and_write_report(&[PathBuf::from(dir.path())], &mut out, opts).unwrap();
+    serde_json::from_slice(&out).unwrap()
+}
+
+fn assert_metadata(meta: &Value) {
+    assert_eq!(meta["run_id"].as_str().unwrap().len(), 36, "{}", meta);
+    assert!(meta["timestamp"].as_str().unwrap().ends_with('Z'), "{}", meta);
+    assert_eq!(meta["tool_version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(meta["ruleset_hash"].as_str().unwrap().len(), 64, "{}", meta);
+    // 统计为扫描结束时的最终值
+    assert_eq!(meta["stats"]["files_scanned"], 1);
+    assert_eq!(meta["stats"]["outputs_written"], 1);
+}
+
+fn write_input(tag: &str) -> TempDir {
+    let dir = TempDir::new(tag);
+    dir.write("a.env", format!("GITHUB_TOKEN={}\n", GHP_TOKEN));
+    dir
+}
+
+#[test]
+fn grouped_json_embeds_metadata() {
+    let dir = write_input("meta-grouped");
+    let plain = scan_json(&dir, &ScanOptions { format: OutputFormat::GroupedJson, ..default_opts() });
+    assert!(plain.is_array(), "{}", plain);
+
+    let doc = scan_json(&dir, &ScanOptions { format: OutputFormat::GroupedJson, with_metadata: true, ..default_opts() });
+    assert_eq!(doc["files"], plain);
+    assert_metadata(&doc["metadata"]);
+}
+
+#[test]
+fn sarif_embeds_metadata_in_run_properties() {
+    let dir = write_input("meta-sarif");
+    let plain = scan_json(&dir, &ScanOptions { format: OutputFormat::Sarif, ..default_opts() });
+    assert!(plain["runs"][0].get("properties").is_none(), "{}", plain);
+
+    let doc = scan_json(&dir, &ScanOptions { format: OutputFormat::Sarif, with_metadata: true, ..default_opts() });
+    let run = &doc["runs"][0];
+    assert_eq!(run["results"], plain["runs"][0]["results"]);
+    assert_metadata(&run["properties"]["metadata"]);
+}
+
+/// 扁平 JSON 数组不受影响（元数据由调用方写入旁路文件）
+#[test]
+fn flat_json_stays_an_array() {
+    let dir = write_input("meta-flat");
+    let doc = scan_json(&dir, &ScanOptions { with_metadata: true, ..default_opts() });
+    assert_eq!(doc.as_array().map(Vec::len), Some(1), "{}", doc);
+}
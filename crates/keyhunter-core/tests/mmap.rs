@@ -0,0 +1,30 @@
+//! 内存映射读取（`ScanOptions::use_mmap`）与普通读取结果一致
+mod common;
+
+use common::{default_opts, TempDir, GHP_TOKEN};
+use keyhunter_core::{scan_and_write, ScanEngine, ScanOptions};
+
+#[test]
+fn mmap_output_matches_the_read_path() {
+    let dir = TempDir::new("mmap");
+    // 超过映射下限（64 KiB）的文件与低于下限（仍走读取路径）的文件
+    let mut big = String::new();
+    for i in 0..3000 {
+        big.push_str(&format!("line {:04} of filler text\n", i));
+        if i % 1000 == 500 { big.push_str(&format!("TOKEN_{}=ghp_{:04}q2LmZ7vXw4NtY9pHs3JdC6bFg1aUe5Qo0\n", i, i)); }
+    }
+    dir.write("big.env", &big);
+    dir.write("small.env", format!("GITHUB_TOKEN={}\n", GHP_TOKEN));
+
+    for engine in [ScanEngine::Bytes, ScanEngine::Utf8, ScanEngine::Auto] {
+        let scan = |use_mmap: bool| {
+            let mut out = Vec::new();
+            let opts = ScanOptions { engine, use_mmap, with_location: true, ..default_opts() };
+            let stats = scan_and_write(dir.path(), &mut out, &opts).unwrap();
+            (String::from_utf8(out).unwrap(), stats.outputs_written, stats.bytes_scanned)
+        };
+        let (read, mapped) = (scan(false), scan(true));
+        assert_eq!(read.1, 4, "{:?}: {}", engine, read.0);
+        assert_eq!(mapped, read, "{:?}", engine);
+    }
+}
@@ -0,0 +1,37 @@
+//! 被包含命中的抑制（`suppress_overlaps`）：区间完全落在另一条命中内的命中丢弃
+mod common;
+
+use common::{custom_opts, TempDir};
+use keyhunter_core::{scan_to_result, ScanOptions};
+
+const SPECIFIC: &str = "[[rules]]\nid = \"test.specific\"\nprefixes = [\"sk-live-\"]\npattern = \"[A-Za-z0-9]{20}\"\n";
+const GENERIC: &str = "[[rules]]\nid = \"test.generic\"\nprefixes = [\"live-\"]\npattern = \"[A-Za-z0-9]{20}\"\n";
+
+#[test]
+fn contained_match_is_suppressed() {
+    let dir = TempDir::new("overlaps");
+    // 第一行的 live- 命中落在 sk-live- 命中之内；第二行只有通用规则命中
+    dir.write("a.env", "KEY=sk-live-Ab12Cd34Ef56Gh78Ij90\nOTHER=live-Zz98Yy76Xx54Ww32Vv10\n");
+
+    // 与规则在文件中的先后顺序无关
+    for (tag, rules) in [("a", format!("{}\n{}", SPECIFIC, GENERIC)), ("b", format!("{}\n{}", GENERIC, SPECIFIC))] {
+        let rules_dir = TempDir::new(&format!("overlaps-rules-{}", tag));
+        let opts = custom_opts(&rules_dir, &rules);
+        let found = |opts: &ScanOptions| -> (Vec<(String, String)>, usize) {
+            let res = scan_to_result(dir.path(), opts).unwrap();
+            (res.items.into_iter().map(|it| (it.rule_id, it.value)).collect(), res.stats.overlaps_suppressed)
+        };
+
+        let (items, suppressed) = found(&opts);
+        assert_eq!(items.len(), 3, "{:?}", items);
+        assert_eq!(suppressed, 0);
+
+        let (items, suppressed) = found(&ScanOptions { suppress_overlaps: true, ..opts });
+        let expected = [
+            ("test.specific".to_string(), "sk-live-Ab12Cd34Ef56Gh78Ij90".to_string()),
+            ("test.generic".to_string(), "live-Zz98Yy76Xx54Ww32Vv10".to_string()),
+        ];
+        assert_eq!(items, expected, "rule order {}", tag);
+        assert_eq!(suppressed, 1);
+    }
+}
@@ -0,0 +1,43 @@
+//! 进度回调（`ScanOptions::on_progress`）：每个文件一次，files_done 单调递增，串行与并行路径一致
+mod common;
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use common::{default_opts, TempDir, GHP_TOKEN};
+use keyhunter_core::{scan_to_result, ProgressEvent, ScanOptions};
+
+#[test]
+fn progress_fires_once_per_scanned_file() {
+    let dir = TempDir::new("progress");
+    for i in 0..12 {
+        let body = if i % 3 == 0 { format!("GITHUB_TOKEN={}\n", GHP_TOKEN) } else { format!("plain file {}\n", i) };
+        dir.write(&format!("f{:02}.env", i), body);
+    }
+
+    for threads in [1, 4] {
+        let events: Arc<Mutex<Vec<(usize, usize, PathBuf)>>> = Arc::default();
+        let in_callback = Arc::new(AtomicBool::new(false));
+        let (ev_sink, busy) = (events.clone(), in_callback.clone());
+        let on_progress = move |ev: ProgressEvent<'_>| {
+            // 回调不会被并发调用
+            assert!(!busy.swap(true, Ordering::SeqCst), "progress callback invoked concurrently");
+            ev_sink.lock().unwrap().push((ev.files_done, ev.files_total, ev.current_path.to_path_buf()));
+            busy.store(false, Ordering::SeqCst);
+        };
+        let opts = ScanOptions { threads: Some(threads), on_progress: Some(Arc::new(on_progress)), ..default_opts() };
+        let res = scan_to_result(dir.path(), &opts).unwrap();
+
+        let events = events.lock().unwrap();
+        assert_eq!(res.stats.files_scanned, 12);
+        assert_eq!(events.len(), res.stats.files_scanned, "threads {}", threads);
+        let done: Vec<usize> = events.iter().map(|e| e.0).collect();
+        assert_eq!(done, (1..=12).collect::<Vec<_>>(), "threads {}", threads);
+        assert!(events.iter().all(|e| e.1 == 12));
+        // 按文件名顺序完成
+        let paths: Vec<PathBuf> = events.iter().map(|e| e.2.clone()).collect();
+        let expected: Vec<PathBuf> = (0..12).map(|i| dir.path().join(format!("f{:02}.env", i))).collect();
+        assert_eq!(paths, expected, "threads {}", threads);
+    }
+}
@@ -0,0 +1,36 @@
+//! 关键字邻近的通用赋值检测（规则的 `keyword` / `proximity`）
+mod common;
+
+use common::{custom_opts, TempDir};
+use keyhunter_core::{scan_to_result, ScanEngine, ScanOptions};
+
+const RULES: &str = r#"
+[[rules]]
+id = "generic.password"
+keyword = ["password", "secret"]
+proximity = 4
+pattern = "[\"']?([A-Za-z0-9!@#$%^&*_-]{8,})"
+"#;
+
+#[test]
+fn keyword_assignment_matches_but_prose_does_not() {
+    let (rules, dir) = (TempDir::new("proximity-rules"), TempDir::new("proximity"));
+    dir.write(
+        "a.txt",
+        concat!(
+            "Please reset your password before logging in again tomorrow.\n",
+            "password=\"s3cr3tValue123\"\n",
+            "DB_PASSWORD: hunter2hunter2\n",
+            "the secret ingredient is love and patience\n",
+            "password == comparison_only_value\n",
+        ),
+    );
+
+    for engine in [ScanEngine::Bytes, ScanEngine::Utf8] {
+        let opts = ScanOptions { engine, ..custom_opts(&rules, RULES) };
+        let res = scan_to_result(dir.path(), &opts).unwrap();
+        let values: Vec<&str> = res.items.iter().map(|it| it.value.as_str()).collect();
+        assert_eq!(values, ["s3cr3tValue123", "hunter2hunter2"], "{:?}", engine);
+        assert!(res.items.iter().all(|it| it.rule_id == "generic.password"));
+    }
+}
@@ -0,0 +1,39 @@
+//! 小文件只读取一次：扫描、内容摘要、行号与上下文共用同一次读入的缓冲区
+//! （按 /proc/self/io 的 rchar 统计本进程读取的字节数；本文件只含一个测试，避免其它测试线程的读取混入）
+#![cfg(target_os = "linux")]
+mod common;
+
+use common::{custom_opts, TempDir, GHP_TOKEN};
+use keyhunter_core::{scan_to_result, HashMode, ScanEngine, ScanOptions};
+
+fn bytes_read() -> u64 {
+    let io = std::fs::read_to_string("/proc/self/io").unwrap();
+    io.lines().find_map(|l| l.strip_prefix("rchar: ")).unwrap().parse().unwrap()
+}
+
+#[test]
+fn small_file_is_read_once() {
+    let (rules, dir) = (TempDir::new("read-once-rules"), TempDir::new("read-once"));
+    let rules_toml = "[[rules]]\nid = \"github.pat\"\nprefixes = [\"ghp_\"]\npattern = \"[0-9a-zA-Z]{36}\"\n";
+    let mut content = "# filler line for the read-once test\n".repeat(12_000);
+    content.push_str(&format!("GITHUB_TOKEN={}\n", GHP_TOKEN));
+    dir.write("big.env", &content);
+    let size = content.len() as u64;
+
+    for engine in [ScanEngine::Bytes, ScanEngine::Utf8, ScanEngine::Auto] {
+        let opts = ScanOptions {
+            engine,
+            hash_mode: HashMode::Sha256,
+            with_location: true,
+            context_lines: Some(1),
+            fingerprint: true,
+            ..custom_opts(&rules, rules_toml)
+        };
+        let before = bytes_read();
+        let res = scan_to_result(dir.path(), &opts).unwrap();
+        let read = bytes_read() - before;
+        assert_eq!(res.items.len(), 1, "{:?}: {:?}", engine, res.items);
+        // 规则文件与 /proc 本身的读取只有几百字节，读两遍则至少是文件大小的两倍
+        assert!(read >= size && read < size + size / 2, "{:?}: read {} bytes of a {} byte file", engine, read, size);
+    }
+}
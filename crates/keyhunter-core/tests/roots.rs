@@ -0,0 +1,36 @@
+//! 多个输入根目录：根目录间的同名文件与输出顺序
+mod common;
+
+use std::path::PathBuf;
+
+use common::{default_opts, TempDir, GHP_TOKEN};
+use keyhunter_core::{scan_roots_and_write_report, HashMode, OwnedOutputItem, ScanOptions};
+
+fn scan_roots(roots: &[PathBuf], opts: &ScanOptions) -> Vec<OwnedOutputItem> {
+    let mut out = Vec::new();
+    scan_roots_and_write_report(roots, &mut out, opts).unwrap();
+    serde_json::from_slice(&out).unwrap()
+}
+
+#[test]
+fn same_named_files_in_two_roots_are_told_apart_by_relpath() {
+    let (first, second) = (TempDir::new("roots-a"), TempDir::new("roots-b"));
+    let other = GHP_TOKEN.replace("R8kq", "Q7jp");
+    first.write("config.env", format!("GITHUB_TOKEN={}\n", GHP_TOKEN));
+    first.write("z.env", format!("GITHUB_TOKEN={}\n", other));
+    second.write("config.env", format!("GITHUB_TOKEN={}\n", other));
+    let roots = [second.path().to_path_buf(), first.path().to_path_buf()];
+
+    // 按根目录给出的顺序、根目录内按文件名排列，与线程数无关
+    for threads in [1, 4] {
+        let opts = ScanOptions { hash_mode: HashMode::Relpath, threads: Some(threads), ..default_opts() };
+        let got: Vec<(String, String)> = scan_roots(&roots, &opts).into_iter().map(|it| (it.file_hash, it.value)).collect();
+        let expected = [("0/config.env", other.as_str()), ("1/config.env", GHP_TOKEN), ("1/z.env", other.as_str())];
+        let expected: Vec<(String, String)> = expected.iter().map(|(h, v)| (h.to_string(), v.to_string())).collect();
+        assert_eq!(got, expected, "threads {}", threads);
+    }
+
+    // 默认按文件名：两个根目录下的 config.env 无法区分
+    let hashes: Vec<String> = scan_roots(&roots, &default_opts()).into_iter().map(|it| it.file_hash).collect();
+    assert_eq!(hashes, ["config.env", "config.env", "z.env"]);
+}
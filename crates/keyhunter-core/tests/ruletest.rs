@@ -0,0 +1,97 @@
+//! 规则自测：`run_rule_tests`（正例 / 反例样本）与 `run_rule_examples`（规则自带样本）；单个字符串的分类（`classify`）
+mod common;
+
+use common::{custom_opts, default_opts, fixture, TempDir, GHP_TOKEN};
+use keyhunter_core::{classify, classify_with_plan, load_prefilter_plan, load_rule_test_cases, run_rule_examples, run_rule_tests, RuleMatch, RuleTestCase, ScanOptions};
+
+#[test]
+fn positive_and_negative_samples_report_the_matching_rule() {
+    let cases = [
+        RuleTestCase { input: format!("token = {}", GHP_TOKEN), expect_match: true },
+        RuleTestCase { input: "notakey".into(), expect_match: false },
+        // 期望命中但没有规则命中：判为失败
+        RuleTestCase { input: "just some prose".into(), expect_match: true },
+    ];
+    let outcomes = run_rule_tests(&default_opts(), &cases).unwrap();
+    assert_eq!(outcomes.len(), 3);
+
+    assert!(outcomes[0].passed);
+    let m = outcomes[0].matches.iter().find(|m| m.rule_id == "github.pat").expect("github.pat should match");
+    assert_eq!(m.value, GHP_TOKEN);
+    assert!(m.prefilter_hit);
+
+    assert!(outcomes[1].passed);
+    assert!(outcomes[1].matches.is_empty(), "{:?}", outcomes[1].matches);
+    assert!(!outcomes[2].passed);
+}
+
+#[test]
+fn test_case_file_is_loaded_in_order() {
+    let (rules, cases_dir) = (TempDir::new("ruletest-rules"), TempDir::new("ruletest-cases"));
+    let opts = custom_opts(&rules, "[[rules]]\nid = \"test.key\"\nprefixes = [\"tk_live_\"]\npattern = \"[a-z0-9]{12}\"\n");
+    let path = cases_dir.write(
+        "cases.toml",
+        "expect_match = [\"tk_live_0123456789ab\"]\nexpect_no_match = [\"tk_live_short\", \"tk_test_0123456789ab\"]\n",
+    );
+
+    let cases = load_rule_test_cases(&path).unwrap();
+    let expect: Vec<bool> = cases.iter().map(|c| c.expect_match).collect();
+    assert_eq!(expect, [true, false, false]);
+
+    let outcomes = run_rule_tests(&opts, &cases).unwrap();
+    assert!(outcomes.iter().all(|o| o.passed), "{:?}", outcomes);
+    assert_eq!(outcomes[0].matches[0].rule_id, "test.key");
+    assert_eq!(outcomes[0].matches[0].value, "tk_live_0123456789ab");
+}
+
+#[test]
+fn malformed_test_case_file_is_rejected() {
+    let dir = TempDir::new("ruletest-bad");
+    let path = dir.write("cases.toml", "expect_match = \"not a list\"\n");
+    assert!(load_rule_test_cases(&path).is_err());
+}
+
+#[test]
+fn rule_examples_report_each_sample() {
+    let opts = ScanOptions { rules_path: Some(fixture("rule_examples.toml")), ..ScanOptions::default() };
+    let outcomes = run_rule_examples(&opts).unwrap();
+    // 按规则加载顺序，每条规则正例在前；没有样本的规则不出现
+    let got: Vec<(&str, &str, bool, bool)> =
+        outcomes.iter().map(|o| (o.rule_id.as_str(), o.input.as_str(), o.expect_match, o.passed)).collect();
+    assert_eq!(
+        got,
+        [
+            ("test.pass", "key = tk_live_0123456789ab", true, true),
+            ("test.pass", "key = tk_live_short", false, true),
+            ("test.pass", "key = tk_test_0123456789ab", false, true),
+            ("test.fail", "sk_prod_0123", true, false),
+            ("test.fail", "sk_prod_0123456789ab", false, false),
+        ]
+    );
+    assert_eq!(outcomes[0].matched.as_ref().unwrap().value, "tk_live_0123456789ab");
+    assert!(outcomes[3].matched.is_none());
+    assert_eq!(outcomes[4].matched.as_ref().unwrap().value, "sk_prod_0123456789ab");
+}
+
+#[test]
+fn default_rule_examples_pass() {
+    let outcomes = run_rule_examples(&default_opts()).unwrap();
+    assert!(!outcomes.is_empty());
+    let failed: Vec<_> = outcomes.iter().filter(|o| !o.passed).collect();
+    assert!(failed.is_empty(), "{:?}", failed);
+}
+
+#[test]
+fn classify_reports_the_matching_rule_and_span() {
+    let matches = classify(GHP_TOKEN, &default_opts()).unwrap();
+    assert_eq!(matches, [RuleMatch { rule_id: "github.pat".into(), value: GHP_TOKEN.into(), start: 0, end: GHP_TOKEN.len() }]);
+
+    // 随机字符串不命中任何规则；复用同一计划与单次调用结论一致
+    let plan = load_prefilter_plan(&default_opts()).unwrap();
+    assert!(classify_with_plan("q8w7e6r5t4y3u2i1o0p", &plan).is_empty());
+    let text = format!("token: {}", GHP_TOKEN);
+    let matches = classify_with_plan(&text, &plan);
+    assert_eq!(matches.len(), 1, "{:?}", matches);
+    assert_eq!((matches[0].start, matches[0].end), (7, text.len()));
+    assert_eq!(&text[matches[0].start..matches[0].end], GHP_TOKEN);
+}
@@ -0,0 +1,38 @@
+//! 熵门限（`ScanOptions::min_score`）
+mod common;
+
+use common::{custom_opts, TempDir, GHP_TOKEN};
+use keyhunter_core::{scan_to_result, ScanEngine, ScanOptions};
+
+const RULES: &str = r#"
+[[rules]]
+id = "test.generic"
+pattern = "secret_value=([A-Za-z0-9]{20})"
+
+[[rules]]
+id = "test.prefixed"
+prefixes = ["ghp_"]
+pattern = "[0-9a-zA-Z]{36}"
+"#;
+
+#[test]
+fn low_entropy_values_are_dropped_unless_the_rule_has_a_prefix() {
+    let (rules, dir) = (TempDir::new("score-rules"), TempDir::new("score"));
+    dir.write(
+        "a.txt",
+        format!("secret_value=aaaaaaaaaaaaaaaaaaaa\nsecret_value=Zq8Xw2Lk9Vb4Nm7Rt3Ys\ntoken={}\nplaceholder=ghp_{}\n", GHP_TOKEN, "a".repeat(36)),
+    );
+
+    for engine in [ScanEngine::Bytes, ScanEngine::Utf8] {
+        let opts = ScanOptions { engine, ..custom_opts(&rules, RULES) };
+        let res = scan_to_result(dir.path(), &opts).unwrap();
+        assert_eq!(res.items.len(), 4, "{:?}: {:?}", engine, res.items);
+
+        let res = scan_to_result(dir.path(), &ScanOptions { min_score: 3.5, ..opts }).unwrap();
+        let values: Vec<String> = res.items.iter().map(|it| it.value.clone()).collect();
+        // 带前缀的规则不受门限约束，即使命中值本身熵很低
+        let expected = ["Zq8Xw2Lk9Vb4Nm7Rt3Ys".to_string(), GHP_TOKEN.to_string(), format!("ghp_{}", "a".repeat(36))];
+        assert_eq!(values, expected, "{:?}", engine);
+        assert_eq!(res.stats.low_entropy, 1, "{:?}", engine);
+    }
+}
@@ -0,0 +1,46 @@
+//! 规则严重级别与最低严重级别过滤（`ScanOptions::min_severity`）
+mod common;
+
+use common::{custom_opts, TempDir};
+use keyhunter_core::{scan_and_write, scan_to_result, ScanOptions, Severity};
+
+const RULES: &str = r#"
+[[rules]]
+id = "test.high"
+prefixes = ["hk_live_"]
+pattern = "[a-z0-9]{12}"
+severity = "high"
+
+[[rules]]
+id = "test.unspecified"
+prefixes = ["mk_live_"]
+pattern = "[a-z0-9]{12}"
+"#;
+
+#[test]
+fn min_severity_drops_lower_severity_findings() {
+    let (rules, dir) = (TempDir::new("severity-rules"), TempDir::new("severity"));
+    dir.write("a.txt", "a = hk_live_0123456789ab\nb = mk_live_0123456789ab\n");
+    let opts = custom_opts(&rules, RULES);
+
+    let res = scan_to_result(dir.path(), &opts).unwrap();
+    assert_eq!(res.items.len(), 2);
+    // 未声明严重级别的规则按 medium 统计
+    assert_eq!(res.stats.findings_by_severity.get(&Severity::High), Some(&1));
+    assert_eq!(res.stats.findings_by_severity.get(&Severity::Medium), Some(&1));
+
+    for (min, expected, dropped) in [(Severity::Medium, 2, 0), (Severity::High, 1, 1), (Severity::Critical, 0, 2)] {
+        let res = scan_to_result(dir.path(), &ScanOptions { min_severity: Some(min), ..opts.clone() }).unwrap();
+        assert_eq!(res.items.len(), expected, "{:?}: {:?}", min, res.items);
+        assert_eq!(res.stats.below_min_severity, dropped, "{:?}", min);
+    }
+    let res = scan_to_result(dir.path(), &ScanOptions { min_severity: Some(Severity::High), ..opts.clone() }).unwrap();
+    assert_eq!(res.items[0].rule_id, "test.high");
+
+    // with_severity 时 JSON 输出附带 severity
+    let mut out = Vec::new();
+    scan_and_write(dir.path(), &mut out, &ScanOptions { with_severity: true, ..opts }).unwrap();
+    let items: Vec<serde_json::Value> = serde_json::from_slice(&out).unwrap();
+    let severities: Vec<&str> = items.iter().map(|it| it["severity"].as_str().unwrap()).collect();
+    assert_eq!(severities, ["high", "medium"]);
+}
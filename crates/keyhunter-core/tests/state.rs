@@ -0,0 +1,64 @@
+//! 增量扫描（`state_path`）：未变化的文件复用上次的结果，输出与全量扫描一致
+mod common;
+
+use std::time::{Duration, SystemTime};
+
+use common::{custom_opts, default_opts, TempDir, GHP_TOKEN};
+use keyhunter_core::{scan_and_write, ScanOptions, ScanStats};
+
+fn scan(dir: &TempDir, opts: &ScanOptions) -> (String, ScanStats) {
+    let mut out = Vec::new();
+    let stats = scan_and_write(dir.path(), &mut out, opts).unwrap();
+    (String::from_utf8(out).unwrap(), stats)
+}
+
+#[test]
+fn unchanged_files_are_reused_and_changed_files_rescanned() {
+    let (state, dir) = (TempDir::new("state-file"), TempDir::new("state"));
+    for name in ["a.env", "b.env", "c.env"] {
+        dir.write(name, format!("GITHUB_TOKEN={}\n", GHP_TOKEN.replace("R8kq", &name[..1].repeat(4))));
+    }
+    let opts = ScanOptions { state_path: Some(state.path().join("state.json")), ..default_opts() };
+
+    // 第一次运行全部扫描并写出状态
+    let (first, stats) = scan(&dir, &opts);
+    assert_eq!((stats.files_scanned, stats.files_reused), (3, 0));
+    assert!(state.path().join("state.json").is_file());
+    assert_eq!(first, scan(&dir, &default_opts()).0);
+
+    // 没有变化：全部复用，输出相同
+    let (second, stats) = scan(&dir, &opts);
+    assert_eq!((stats.files_scanned, stats.files_reused, stats.bytes_scanned), (3, 3, 0));
+    assert_eq!(second, first);
+
+    // 改写 b.env（大小不变，修改时间推后）：只重新扫描它，输出与全量扫描一致
+    let changed = dir.write("b.env", format!("GITHUB_TOKEN={}\n", GHP_TOKEN.replace("R8kq", "Zz9Z")));
+    let later = SystemTime::now() + Duration::from_secs(10);
+    std::fs::File::options().write(true).open(&changed).unwrap().set_modified(later).unwrap();
+    let (third, stats) = scan(&dir, &opts);
+    assert_eq!((stats.files_scanned, stats.files_reused), (3, 2));
+    assert_eq!(stats.bytes_scanned, std::fs::metadata(&changed).unwrap().len());
+    assert_eq!(third, scan(&dir, &default_opts()).0);
+    assert!(third.contains("ghp_Zz9Z") && !third.contains("ghp_bbbb"), "{}", third);
+}
+
+#[test]
+fn state_is_invalidated_when_rules_change() {
+    let (state, rules, dir) = (TempDir::new("state-rules-file"), TempDir::new("state-rules"), TempDir::new("state-rules-in"));
+    dir.write("a.env", format!("GITHUB_TOKEN={}\nkey = tk_live_0123456789ab\n", GHP_TOKEN));
+    let state_path = Some(state.path().join("state.json"));
+    let pat = "[[rules]]\nid = \"test.pat\"\nprefixes = [\"ghp_\"]\npattern = \"[0-9a-zA-Z]{36}\"\n";
+    let key = "[[rules]]\nid = \"test.key\"\nprefixes = [\"tk_live_\"]\npattern = \"[a-z0-9]{12}\"\n";
+
+    let opts = ScanOptions { state_path: state_path.clone(), ..custom_opts(&rules, pat) };
+    let (_, stats) = scan(&dir, &opts);
+    assert_eq!(stats.files_reused, 0);
+    assert_eq!(scan(&dir, &opts).1.files_reused, 1);
+
+    // 规则集变化：状态失效，重新扫描并报告新规则的命中
+    let opts = ScanOptions { state_path, ..custom_opts(&rules, &format!("{}\n{}", pat, key)) };
+    let (out, stats) = scan(&dir, &opts);
+    assert_eq!(stats.files_reused, 0);
+    assert!(out.contains("tk_live_0123456789ab"), "{}", out);
+    assert_eq!(scan(&dir, &opts).1.files_reused, 1);
+}
@@ -0,0 +1,33 @@
+//! 规则匹配时间上限（`ScanOptions::rule_timeout`）
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::{custom_opts, TempDir};
+use keyhunter_core::{scan_to_result, ScanEngine, ScanOptions};
+
+/// 允许重叠匹配、且每次匹配都延伸到整段末尾：n 个起点各扫描 O(n) 字节，整体为平方级
+const SLOW_RULE: &str = "[[rules]]\nid = \"test.slow\"\noverlapping = true\nprefixes = [\"slow_\"]\npattern = \"[a-z_]+\"\n";
+
+#[test]
+fn slow_rule_is_abandoned_after_its_timeout() {
+    let (rules, dir) = (TempDir::new("timeout-rules"), TempDir::new("timeout"));
+    dir.write("a.txt", format!("{}\n", "slow_".repeat(12_000)));
+
+    let opts = ScanOptions {
+        engine: ScanEngine::Bytes,
+        rule_timeout: Some(Duration::from_millis(20)),
+        // 命中值会延伸到整段末尾，取消长度上限以免被丢弃
+        max_value_len: None,
+        ..custom_opts(&rules, SLOW_RULE)
+    };
+    let started = Instant::now();
+    let res = scan_to_result(dir.path(), &opts).unwrap();
+    // 不设上限时调试构建下约需一分钟；此处只验证扫描能结束并记录超时，不对耗时做精确断言
+    assert!(started.elapsed() < Duration::from_secs(30));
+    assert!(res.stats.rule_timeouts >= 1, "{:?}", res.stats);
+    assert_eq!(res.stats.files_scanned, 1);
+    // 超时前得到的命中保留
+    assert!(!res.items.is_empty());
+    assert!(res.items.iter().all(|it| it.rule_id == "test.slow"));
+}
@@ -0,0 +1,31 @@
+//! 单文件命中数上限（`max_matches_per_file`）：按偏移顺序保留前 N 条，计入 `truncated_files`
+mod common;
+
+use common::{custom_opts, TempDir};
+use keyhunter_core::{scan_to_result, ScanOptions};
+
+const RULE: &str = "[[rules]]\nid = \"test.key\"\nprefixes = [\"tk_live_\"]\npattern = \"[a-z0-9]{12}\"\n";
+
+#[test]
+fn matches_are_capped_per_file_in_offset_order() {
+    let (rules, dir) = (TempDir::new("truncate-rules"), TempDir::new("truncate"));
+    let token = |i: usize| format!("tk_live_{:012}", i);
+    let body: String = (0..10_000).map(|i| format!("key{} = {}\n", i, token(i))).collect();
+    dir.write("many.txt", body);
+    dir.write("few.txt", format!("key = {}\n", token(99_999)));
+
+    for threads in [1, 4] {
+        let opts = ScanOptions { threads: Some(threads), max_matches_per_file: Some(100), ..custom_opts(&rules, RULE) };
+        let res = scan_to_result(dir.path(), &opts).unwrap();
+        let many: Vec<&str> = res.items.iter().filter(|it| it.file_hash == "many.txt").map(|it| it.value.as_str()).collect();
+        let expected: Vec<String> = (0..100).map(token).collect();
+        assert_eq!(many, expected, "threads {}", threads);
+        // 未达上限的文件不受影响
+        assert_eq!(res.items.iter().filter(|it| it.file_hash == "few.txt").count(), 1);
+        assert_eq!(res.stats.truncated_files, 1);
+    }
+
+    let res = scan_to_result(dir.path(), &custom_opts(&rules, RULE)).unwrap();
+    assert_eq!(res.items.len(), 10_001);
+    assert_eq!(res.stats.truncated_files, 0);
+}
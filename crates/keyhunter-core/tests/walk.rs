@@ -0,0 +1,161 @@
+//! 目录遍历：递归深度与文件顺序、.gitignore 与排除模式、单文件输入、相对路径输出、扩展名 / 名称过滤、符号链接
+mod common;
+
+use common::{default_opts, TempDir, GHP_TOKEN};
+use keyhunter_core::{scan_and_write, scan_to_result, HashMode, ScanOptions};
+use serde_json::Value;
+
+fn env(token: &str) -> String {
+    format!("GITHUB_TOKEN={}\n", token)
+}
+
+#[test]
+fn max_depth_controls_how_deep_the_walk_goes() {
+    let dir = TempDir::new("walk-depth");
+    let deep = GHP_TOKEN.replace("R8kq", "D3ep");
+    dir.write("top.env", env(GHP_TOKEN));
+    dir.write("a/b/deep.env", env(&deep));
+
+    let found = |max_depth: Option<usize>| -> Vec<String> {
+        let opts = ScanOptions { max_depth, hash_mode: HashMode::Relpath, ..default_opts() };
+        scan_to_result(dir.path(), &opts).unwrap().items.into_iter().map(|it| it.file_hash).collect()
+    };
+    // 缺省只扫描顶层；a/b/deep.env 位于第 3 层
+    assert_eq!(found(Some(1)), ["top.env"]);
+    assert_eq!(found(Some(2)), ["top.env"]);
+    assert_eq!(found(Some(3)), ["a/b/deep.env", "top.env"]);
+    assert_eq!(found(None), ["a/b/deep.env", "top.env"]);
+}
+
+#[test]
+fn same_name_in_nested_directories_sorts_by_relative_path() {
+    let dir = TempDir::new("walk-order");
+    let other = GHP_TOKEN.replace("R8kq", "Q7jp");
+    dir.write("b/config.env", env(GHP_TOKEN));
+    dir.write("a/config.env", env(&other));
+
+    for threads in [1, 4] {
+        let opts = ScanOptions { max_depth: None, threads: Some(threads), ..default_opts() };
+        let got: Vec<(String, String)> = scan_to_result(dir.path(), &opts).unwrap().items.into_iter().map(|it| (it.file_hash, it.value)).collect();
+        let expected = vec![("config.env".to_string(), other.clone()), ("config.env".to_string(), GHP_TOKEN.to_string())];
+        assert_eq!(got, expected, "threads {}", threads);
+    }
+}
+
+#[test]
+fn single_file_input_matches_its_directory() {
+    let dir = TempDir::new("walk-file");
+    let file = dir.write("only.env", format!("A={}\nB={}\n", GHP_TOKEN, GHP_TOKEN.replace("R8kq", "Q7jp")));
+
+    let scan = |input: &std::path::Path| {
+        let mut out = Vec::new();
+        let stats = scan_and_write(input, &mut out, &default_opts()).unwrap();
+        (String::from_utf8(out).unwrap(), stats.files_scanned, stats.bytes_scanned, stats.outputs_written)
+    };
+    let (from_file, from_dir) = (scan(&file), scan(dir.path()));
+    assert_eq!(from_file, from_dir);
+    assert_eq!(from_file.1, 1);
+    assert_eq!(from_file.3, 2);
+    assert!(from_file.0.starts_with("[{\"file_hash\":\"only.env\""), "{}", from_file.0);
+}
+
+#[test]
+fn gitignored_directory_is_skipped_only_when_requested() {
+    let dir = TempDir::new("walk-gitignore");
+    dir.write(".gitignore", "build/\n");
+    dir.write("app.env", env(GHP_TOKEN));
+    dir.write("build/out.env", env(&GHP_TOKEN.replace("R8kq", "B1ld")));
+    dir.write("web/app.min.js", env(&GHP_TOKEN.replace("R8kq", "M1nj")));
+
+    let found = |opts: ScanOptions| -> Vec<String> {
+        let opts = ScanOptions { max_depth: None, hash_mode: HashMode::Relpath, ..opts };
+        scan_to_result(dir.path(), &opts).unwrap().items.into_iter().map(|it| it.file_hash).collect()
+    };
+    assert_eq!(found(default_opts()), ["app.env", "build/out.env", "web/app.min.js"]);
+    assert_eq!(found(ScanOptions { respect_gitignore: true, ..default_opts() }), ["app.env", "web/app.min.js"]);
+    assert_eq!(
+        found(ScanOptions { respect_gitignore: true, exclude_globs: vec!["*.min.js".into()], ..default_opts() }),
+        ["app.env"]
+    );
+}
+
+#[test]
+fn emitted_path_is_the_relative_path_on_disk() {
+    let dir = TempDir::new("walk-path");
+    dir.write("top.env", env(GHP_TOKEN));
+    dir.write("a/b/deep.env", env(&GHP_TOKEN.replace("R8kq", "D3ep")));
+
+    let items = |opts: &ScanOptions| -> Vec<Value> {
+        let mut out = Vec::new();
+        scan_and_write(dir.path(), &mut out, opts).unwrap();
+        serde_json::from_slice(&out).unwrap()
+    };
+    for threads in [1, 4] {
+        let opts = ScanOptions { max_depth: None, emit_path: true, threads: Some(threads), ..default_opts() };
+        // file_hash 仍为文件名，path 为相对输入目录的路径
+        let items = items(&opts);
+        let got: Vec<(&str, &str)> = items.iter().map(|it| (it["file_hash"].as_str().unwrap(), it["path"].as_str().unwrap())).collect();
+        assert_eq!(got, [("deep.env", "a/b/deep.env"), ("top.env", "top.env")], "threads {}", threads);
+    }
+    // 缺省不输出 path
+    let opts = ScanOptions { max_depth: None, ..default_opts() };
+    assert!(items(&opts).iter().all(|it| it.get("path").is_none()));
+}
+
+#[test]
+fn extension_and_name_filters_keep_only_matching_files() {
+    let dir = TempDir::new("walk-ext");
+    for (i, name) in ["a.env", "B.ENV", ".env", "c.yaml", "d.json", "e.pem", "f.txt", "env"].iter().enumerate() {
+        dir.write(name, env(&GHP_TOKEN.replace("R8kq", &format!("X{}y{}", i, i))));
+    }
+    dir.write("big.txt", "x".repeat(4096));
+
+    let found = |opts: ScanOptions| -> (Vec<String>, usize) {
+        let res = scan_to_result(dir.path(), &ScanOptions { max_file_size: Some(1024), ..opts }).unwrap();
+        // 被过滤掉的文件不参与大小检查，不计为跳过
+        (res.items.into_iter().map(|it| it.file_hash).collect(), res.skipped.len())
+    };
+    let ext = |exts: &[&str]| ScanOptions { include_extensions: exts.iter().map(|e| e.to_string()).collect(), ..default_opts() };
+    // 扩展名不区分大小写；点文件 .env 同样匹配，无扩展名的 env 不匹配
+    assert_eq!(found(ext(&["env"])), (vec![".env".to_string(), "B.ENV".into(), "a.env".into()], 0));
+    assert_eq!(found(ext(&[".pem", "YAML"])).0, ["c.yaml", "e.pem"]);
+    // 与 name_globs 同时配置时满足其一即可
+    let opts = ScanOptions { name_globs: vec!["*.json".into()], ..ext(&["pem"]) };
+    assert_eq!(found(opts).0, ["d.json", "e.pem"]);
+    // 未过滤时 big.txt 因过大被跳过
+    assert_eq!(found(default_opts()).1, 1);
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinks_are_followed_only_when_requested() {
+    use std::os::unix::fs::symlink;
+    let (dir, outside) = (TempDir::new("walk-link"), TempDir::new("walk-link-outside"));
+    dir.write("own.env", env(GHP_TOKEN));
+    let target = outside.write("samples/linked.env", env(&GHP_TOKEN.replace("R8kq", "L1nk")));
+    symlink(&target, dir.path().join("file-link.env")).unwrap();
+    symlink(outside.path().join("samples"), dir.path().join("dir-link")).unwrap();
+
+    let found = |follow_symlinks: bool| -> Vec<String> {
+        let opts = ScanOptions { max_depth: None, hash_mode: HashMode::Relpath, follow_symlinks, ..default_opts() };
+        scan_to_result(dir.path(), &opts).unwrap().items.into_iter().map(|it| it.file_hash).collect()
+    };
+    assert_eq!(found(false), ["own.env"]);
+    // 指向输入目录之外的链接同样扫描，file_hash 按链接所在位置计算
+    assert_eq!(found(true), ["dir-link/linked.env", "file-link.env", "own.env"]);
+}
+
+#[cfg(unix)]
+#[test]
+fn symlink_loop_terminates() {
+    let dir = TempDir::new("walk-loop");
+    dir.write("a/app.env", env(GHP_TOKEN));
+    std::os::unix::fs::symlink(dir.path(), dir.path().join("a/back")).unwrap();
+    std::os::unix::fs::symlink(dir.path().join("a"), dir.path().join("a/self")).unwrap();
+
+    let opts = ScanOptions { max_depth: None, hash_mode: HashMode::Relpath, follow_symlinks: true, ..default_opts() };
+    let res = scan_to_result(dir.path(), &opts).unwrap();
+    let files: Vec<&str> = res.items.iter().map(|it| it.file_hash.as_str()).collect();
+    assert_eq!(files, ["a/app.env"]);
+    assert_eq!(res.stats.files_scanned, 1);
+}
@@ -0,0 +1,27 @@
+//! 锚点窗口（`ScanOptions::window_before` / `window_after` 与规则级覆盖）
+mod common;
+
+use common::{custom_opts, TempDir};
+use keyhunter_core::{scan_to_result, ScanEngine, ScanOptions, DEFAULT_WINDOW_AFTER};
+
+const RULE: &str = "[[rules]]\nid = \"test.far\"\npattern = 'auth_token\":\\s*\"([A-Za-z0-9]{30,})\"'\n";
+
+#[test]
+fn wider_window_recovers_a_value_far_from_its_anchor() {
+    let (rules, rules_wide, dir) = (TempDir::new("windows-rules"), TempDir::new("windows-rules-wide"), TempDir::new("windows"));
+    let value = "Zq8Xw2Lk9Vb4Nm7Rt3YsPp5Hh6Jj1Kk0";
+    // 值距锚点约 2 KiB，超出默认的向后窗口
+    dir.write("a.json", format!("{{\"auth_token\": {}\"{}\"}}\n", " ".repeat(2 * DEFAULT_WINDOW_AFTER), value));
+
+    // 关闭小文件整缓冲区扫描，确保按窗口扫描
+    let base = ScanOptions { engine: ScanEngine::Bytes, small_file_fullscan_threshold: 0, ..custom_opts(&rules, RULE) };
+    let values = |opts: &ScanOptions| -> Vec<String> {
+        scan_to_result(dir.path(), opts).unwrap().items.into_iter().map(|it| it.value).collect()
+    };
+    assert!(values(&base).is_empty());
+    assert_eq!(values(&ScanOptions { window_after: 4 * DEFAULT_WINDOW_AFTER, ..base.clone() }), [value]);
+
+    // 规则级覆盖只作用于该规则
+    let wide = format!("{}window_after = {}\n", RULE, 4 * DEFAULT_WINDOW_AFTER);
+    assert_eq!(values(&ScanOptions { rules_path: custom_opts(&rules_wide, &wide).rules_path, ..base }), [value]);
+}